@@ -0,0 +1,121 @@
+//! Compares catching up on 1000 units fed one by one through the parents channel with adding
+//! them at once with `Creator::add_units`. Run with `cargo bench --bench catch_up`.
+use codec::{Decode, Encode};
+use rush::{
+    nodes::{NodeCount, NodeIndex, NodeMap},
+    Config, Creator, MyIndex, PreUnit, Round, Unit,
+};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, oneshot};
+
+const N_MEMBERS: usize = 4;
+const N_UNITS: usize = 1000;
+const N_RUNS: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+struct NodeId(u64);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Node-{}", self.0)
+    }
+}
+
+impl MyIndex for NodeId {
+    fn my_index(&self) -> Option<NodeIndex> {
+        Some(NodeIndex::from_usize(self.0 as usize))
+    }
+}
+
+fn coord_hash(round: usize, creator: usize) -> u64 {
+    (round * N_MEMBERS + creator) as u64
+}
+
+// Hashes a preunit to its coordinates, so that our own units match the ones in the stream.
+fn hashing(data: &[u8]) -> u64 {
+    let mut input = data;
+    match PreUnit::<u64>::decode(&mut input) {
+        Ok(pu) if input.is_empty() => coord_hash(pu.round().0, pu.creator().as_usize()),
+        _ => data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100_0000_01b3)
+        }),
+    }
+}
+
+fn units() -> Vec<Unit<u64>> {
+    (0..N_UNITS / N_MEMBERS)
+        .flat_map(|round| (0..N_MEMBERS).map(move |creator| (round, creator)))
+        .map(|(round, creator)| {
+            let pu = PreUnit::new_from_parents(
+                NodeIndex::from_usize(creator),
+                Round(round),
+                &NodeMap::new_with_len(NodeCount::from_usize(N_MEMBERS)),
+                &hashing,
+            );
+            Unit::new_from_preunit(pu, coord_hash(round, creator))
+        })
+        .collect()
+}
+
+type Hashing = fn(&[u8]) -> u64;
+type Notifications = mpsc::UnboundedReceiver<rush::NotificationOut<u64>>;
+
+fn creator(
+    parents_rx: mpsc::UnboundedReceiver<Unit<u64>>,
+) -> (Creator<u64, NodeId, Hashing>, Notifications) {
+    let conf = Config::builder()
+        .node_id(NodeId(0))
+        .n_members(NodeCount::from_usize(N_MEMBERS))
+        .build()
+        .expect("the config is valid");
+    let (new_units_tx, new_units_rx) = mpsc::unbounded_channel();
+    let creator = Creator::new(conf, parents_rx, new_units_tx, hashing as Hashing)
+        .expect("the node has an index");
+    (creator, new_units_rx)
+}
+
+async fn one_by_one(units: Vec<Unit<u64>>) -> Duration {
+    let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+    let (mut creator, _new_units_rx) = creator(parents_rx);
+    for unit in units {
+        let _ = parents_tx.send(unit);
+    }
+    drop(parents_tx);
+    let (_exit_tx, exit_rx) = oneshot::channel();
+    let start = Instant::now();
+    creator
+        .create(exit_rx)
+        .await
+        .expect("the notifications are received");
+    let elapsed = start.elapsed();
+    assert_eq!(creator.stats().created as usize, N_UNITS / N_MEMBERS + 1);
+    elapsed
+}
+
+fn batch(units: Vec<Unit<u64>>) -> Duration {
+    let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+    let (mut creator, _new_units_rx) = creator(parents_rx);
+    let start = Instant::now();
+    // the unit of round 0 needs no parents, all the following ones are created from the batch
+    creator.add_units(Vec::new());
+    creator.add_units(units);
+    let elapsed = start.elapsed();
+    assert_eq!(creator.stats().created as usize, N_UNITS / N_MEMBERS + 1);
+    elapsed
+}
+
+fn main() {
+    let mut runtime = tokio::runtime::Runtime::new().expect("a runtime can be started");
+    let units = units();
+    for run in 0..N_RUNS {
+        let one_by_one = runtime.block_on(one_by_one(units.clone()));
+        let batch = batch(units.clone());
+        println!(
+            "run {}: {} units one by one in {:?}, as a batch in {:?}",
+            run, N_UNITS, one_by_one, batch
+        );
+    }
+}
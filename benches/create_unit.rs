@@ -0,0 +1,97 @@
+//! Measures creating a unit of a committee of 1000 members, i.e., choosing its parents out of
+//! the candidates and computing its control hash. Run with `cargo bench --bench create_unit`.
+use codec::{Decode, Encode};
+use rush::{
+    nodes::{NodeCount, NodeIndex, NodeMap},
+    Config, Creator, MyIndex, PreUnit, Round, Unit,
+};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+const N_MEMBERS: usize = 1000;
+const N_ROUNDS: usize = 200;
+const N_RUNS: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+struct NodeId(u64);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Node-{}", self.0)
+    }
+}
+
+impl MyIndex for NodeId {
+    fn my_index(&self) -> Option<NodeIndex> {
+        Some(NodeIndex::from_usize(self.0 as usize))
+    }
+}
+
+fn coord_hash(round: usize, creator: usize) -> u64 {
+    (round * N_MEMBERS + creator) as u64
+}
+
+// Hashes a preunit to its coordinates, so that our own units match the ones we feed back.
+fn hashing(data: &[u8]) -> u64 {
+    let mut input = data;
+    match PreUnit::<u64>::decode(&mut input) {
+        Ok(pu) if input.is_empty() => coord_hash(pu.round().0, pu.creator().as_usize()),
+        _ => data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100_0000_01b3)
+        }),
+    }
+}
+
+fn unit(round: usize, creator: usize) -> Unit<u64> {
+    let pu = PreUnit::new_from_parents(
+        NodeIndex::from_usize(creator),
+        Round(round),
+        &NodeMap::new_with_len(NodeCount::from_usize(N_MEMBERS)),
+        &hashing,
+    );
+    Unit::new_from_preunit(pu, coord_hash(round, creator))
+}
+
+type Hashing = fn(&[u8]) -> u64;
+
+// The total time of creating the units of rounds 1 to N_ROUNDS, each out of a full round of
+// candidates. Only adding our own unit, the last candidate missing, is timed.
+fn create_units() -> Duration {
+    let conf = Config::builder()
+        .node_id(NodeId(0))
+        .n_members(NodeCount::from_usize(N_MEMBERS))
+        .build()
+        .expect("the config is valid");
+    let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+    let (new_units_tx, _new_units_rx) = mpsc::unbounded_channel();
+    let mut creator = Creator::new(conf, parents_rx, new_units_tx, hashing as Hashing)
+        .expect("the node has an index");
+    assert_eq!(creator.add_units(Vec::new()), 1);
+    let mut elapsed = Duration::default();
+    for round in 0..N_ROUNDS {
+        let others: Vec<_> = (1..N_MEMBERS).map(|creator| unit(round, creator)).collect();
+        assert_eq!(creator.add_units(others), 0);
+        let own = unit(round, 0);
+        let start = Instant::now();
+        assert_eq!(creator.add_units(vec![own]), 1);
+        elapsed += start.elapsed();
+    }
+    elapsed
+}
+
+fn main() {
+    for run in 0..N_RUNS {
+        let elapsed = create_units();
+        println!(
+            "run {}: {} units of {} members created in {:?}, {:?} per unit",
+            run,
+            N_ROUNDS,
+            N_MEMBERS,
+            elapsed,
+            elapsed / N_ROUNDS as u32
+        );
+    }
+}
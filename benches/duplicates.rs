@@ -0,0 +1,100 @@
+//! Measures how fast the Creator processes a stream of parents in which 90% of the units are
+//! copies of units it has already received. Run with `cargo bench --bench duplicates`.
+use codec::{Decode, Encode};
+use rush::{
+    nodes::{NodeCount, NodeIndex, NodeMap},
+    Config, Creator, MyIndex, PreUnit, Round, Unit,
+};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, oneshot};
+
+const N_MEMBERS: usize = 16;
+const N_ROUNDS: usize = 500;
+// every unit is sent this many times, i.e., 9 out of 10 received units are duplicates
+const N_COPIES: usize = 10;
+const N_RUNS: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+struct NodeId(u64);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Node-{}", self.0)
+    }
+}
+
+impl MyIndex for NodeId {
+    fn my_index(&self) -> Option<NodeIndex> {
+        Some(NodeIndex::from_usize(self.0 as usize))
+    }
+}
+
+fn hashing(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+fn units() -> Vec<Unit<u64>> {
+    let mut units = Vec::new();
+    for round in 0..N_ROUNDS {
+        for _ in 0..N_COPIES {
+            for creator in 0..N_MEMBERS {
+                let pu = PreUnit::new_from_parents(
+                    NodeIndex::from_usize(creator),
+                    Round(round),
+                    &NodeMap::new_with_len(NodeCount::from_usize(N_MEMBERS)),
+                    &hashing,
+                );
+                let hash = (round * N_MEMBERS + creator) as u64;
+                units.push(Unit::new_from_preunit(pu, hash));
+            }
+        }
+    }
+    units
+}
+
+// Returns how long an observing Creator takes to process all the given units.
+async fn process(units: Vec<Unit<u64>>) -> Duration {
+    let conf = Config::builder()
+        .node_id(NodeId(0))
+        .n_members(NodeCount::from_usize(N_MEMBERS))
+        .observer(true)
+        .build()
+        .expect("the config is valid");
+    let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+    let (new_units_tx, _new_units_rx) = mpsc::unbounded_channel();
+    for unit in units {
+        let _ = parents_tx.send(unit);
+    }
+    drop(parents_tx);
+    let mut creator =
+        Creator::new(conf, parents_rx, new_units_tx, hashing).expect("the node has an index");
+    let (_exit_tx, exit_rx) = oneshot::channel();
+    let start = Instant::now();
+    creator
+        .create(exit_rx)
+        .await
+        .expect("the notifications are received");
+    start.elapsed()
+}
+
+fn main() {
+    let mut runtime = tokio::runtime::Runtime::new().expect("a runtime can be started");
+    let units = units();
+    let n_units = units.len();
+    for run in 0..N_RUNS {
+        let elapsed = runtime.block_on(process(units.clone()));
+        println!(
+            "run {}: {} units ({}% duplicates) in {:?}, {:.0} units/s",
+            run,
+            n_units,
+            100 - 100 / N_COPIES,
+            elapsed,
+            n_units as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Runs a Creator of a committee of 4 members for a few rounds and prints the tracing spans of
+//! its rounds, with their `round`, `node_index` and `parent_count` fields, as they close. Run with
+//! `cargo run --example round_spans --features tracing`.
+use codec::{Decode, Encode};
+use rush::{
+    nodes::{NodeCount, NodeIndex, NodeMap},
+    Config, Creator, MyIndex, NotificationOut, PreUnit, Round, Unit,
+};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use tokio::sync::{mpsc, oneshot};
+use tracing_subscriber::fmt::format::FmtSpan;
+
+const N_MEMBERS: usize = 4;
+const N_ROUNDS: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+struct NodeId(u64);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Node-{}", self.0)
+    }
+}
+
+impl MyIndex for NodeId {
+    fn my_index(&self) -> Option<NodeIndex> {
+        Some(NodeIndex::from_usize(self.0 as usize))
+    }
+}
+
+fn coord_hash(round: usize, creator: usize) -> u64 {
+    (round * N_MEMBERS + creator) as u64
+}
+
+// Hashes a preunit to its coordinates, so that our own units match the ones we feed back.
+fn hashing(data: &[u8]) -> u64 {
+    let mut input = data;
+    match PreUnit::<u64>::decode(&mut input) {
+        Ok(pu) if input.is_empty() => coord_hash(pu.round().0, pu.creator().as_usize()),
+        _ => data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100_0000_01b3)
+        }),
+    }
+}
+
+fn unit(round: usize, creator: usize) -> Unit<u64> {
+    let pu = PreUnit::new_from_parents(
+        NodeIndex::from_usize(creator),
+        Round(round),
+        &NodeMap::new_with_len(NodeCount::from_usize(N_MEMBERS)),
+        &hashing,
+    );
+    Unit::new_from_preunit(pu, coord_hash(round, creator))
+}
+
+#[tokio::main]
+async fn main() {
+    // prints lines like
+    // DEBUG create{node_index=0}:round{round=1 node_index=0 parent_count=3}: rush-creator: close ...
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
+    let conf = Config::builder()
+        .node_id(NodeId(0))
+        .n_members(NodeCount::from_usize(N_MEMBERS))
+        .build()
+        .expect("the config is valid");
+    let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+    let (new_units_tx, mut new_units_rx) = mpsc::unbounded_channel();
+    let mut creator = Creator::new(conf, parents_rx, new_units_tx, hashing as fn(&[u8]) -> u64)
+        .expect("the node has an index");
+    let (exit_tx, exit_rx) = oneshot::channel();
+    let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+
+    let mut created = 0;
+    while let Some(notification) = new_units_rx.recv().await {
+        if let NotificationOut::CreatedPreUnit(pu) = notification {
+            created += 1;
+            if created > N_ROUNDS {
+                break;
+            }
+            for creator in 0..N_MEMBERS {
+                let _ = parents_tx.send(unit(pu.round().0, creator));
+            }
+        }
+    }
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+}
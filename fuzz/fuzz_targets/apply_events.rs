@@ -0,0 +1,106 @@
+//! Feeds a Creator with arbitrary sequences of units and creation attempts, checking that it
+//! never panics and that every unit it creates is valid. Run with
+//! `cargo +nightly fuzz run apply_events` from the root of the crate.
+#![no_main]
+use codec::{Decode, Encode};
+use libfuzzer_sys::fuzz_target;
+use rush::{
+    nodes::{NodeCount, NodeIndex},
+    Config, Creator, CreatorEvent, MyIndex, NotificationOut, PreUnit, Round,
+};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use tokio::sync::mpsc;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+struct NodeId(u64);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Node-{}", self.0)
+    }
+}
+
+impl MyIndex for NodeId {
+    fn my_index(&self) -> Option<NodeIndex> {
+        Some(NodeIndex::from_usize(self.0 as usize))
+    }
+}
+
+fn coord_hash(round: usize, creator: usize) -> u64 {
+    ((round as u64) << 32) | creator as u64
+}
+
+// Hashes a preunit to its coordinates, so that our own units match the ones we feed back.
+fn hashing(data: &[u8]) -> u64 {
+    let mut input = data;
+    match PreUnit::<u64>::decode(&mut input) {
+        Ok(pu) if input.is_empty() => coord_hash(pu.round().0, pu.creator().as_usize()),
+        _ => data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100_0000_01b3)
+        }),
+    }
+}
+
+type Hashing = fn(&[u8]) -> u64;
+
+// The first byte gives the size of the committee, then every three bytes make an event: an
+// attempt to create a unit, or a unit of the given round and creator, possibly a fork. Creators
+// outside of the committee are not filtered out on purpose.
+fn events(data: &[u8]) -> (NodeCount, Vec<CreatorEvent<u64>>) {
+    let n_members = NodeCount::from_usize(1 + *data.first().unwrap_or(&0) as usize % 16);
+    let events = data
+        .get(1..)
+        .unwrap_or_default()
+        .chunks_exact(3)
+        .map(|chunk| {
+            let (tag, round, pid) = (chunk[0], chunk[1] as usize, chunk[2] as usize);
+            match tag % 4 {
+                0 => CreatorEvent::TryCreate,
+                fork => CreatorEvent::AddUnit {
+                    round: Round(round),
+                    pid: NodeIndex::from_usize(pid),
+                    hash: coord_hash(round, pid) + (fork == 3) as u64,
+                },
+            }
+        })
+        .collect();
+    (n_members, events)
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (n_members, events) = events(data);
+    let conf = Config::builder()
+        .node_id(NodeId(0))
+        .n_members(n_members)
+        .build()
+        .expect("the config is valid");
+    let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+    let (new_units_tx, mut new_units_rx) = mpsc::unbounded_channel();
+    let mut creator = Creator::new(conf, parents_rx, new_units_tx, hashing as Hashing)
+        .expect("the node has an index");
+    let n_created = creator.apply_events(&events);
+
+    let required = n_members.threshold_two_thirds().as_usize() + 1;
+    let mut next_round = Round(0);
+    let mut n_received = 0;
+    while let Ok(notification) = new_units_rx.try_recv() {
+        let pu = match notification {
+            NotificationOut::CreatedPreUnit(pu) => pu,
+            _ => continue,
+        };
+        n_received += 1;
+        assert_eq!(pu.creator(), NodeIndex::from_usize(0));
+        assert_eq!(pu.round(), next_round);
+        next_round = next_round.next();
+        let parents = &pu.control_hash().parents;
+        assert_eq!(parents.into_iter().count(), n_members.as_usize());
+        if pu.round() == Round(0) {
+            assert!(parents.into_iter().all(|parent| !parent));
+            continue;
+        }
+        let n_parents = parents.into_iter().filter(|parent| **parent).count();
+        assert!(n_parents >= required);
+        assert!(parents[NodeIndex::from_usize(0)], "our own unit is always a parent");
+    }
+    assert_eq!(n_received, n_created);
+});
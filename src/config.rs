@@ -0,0 +1,1265 @@
+use codec::Encode;
+use parking_lot::Mutex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+use tokio::time::Duration;
+
+use crate::{
+    nodes::{NodeCount, NodeIndex, NodeMap},
+    Clock, DataProvider, HashT, LagStrategy, MetricsSink, NodeIdT, NoopSink, ParentOrdering,
+    ParentPreference, ParentSelector, PreUnit, Round, Signer, ThresholdPolicy, TokioClock,
+    TwoThirdsPolicy,
+};
+
+// A DataProvider with the type of its data erased by encoding it.
+pub(crate) type EncodedDataProvider = Arc<Mutex<dyn FnMut(Round) -> Vec<u8> + Send>>;
+
+/// The largest create_lag (or lag produced by a [LagStrategy]) accepted by [ConfigBuilder::build].
+pub const MAX_CREATE_LAG: Duration = Duration::from_secs(60 * 60);
+
+/// Candidates for rounds further than that ahead of the current round are ignored by default, so
+/// that a unit with an absurd round number cannot make the Creator allocate the candidate maps
+/// for all rounds up to it. See [ConfigBuilder::max_round_lead].
+pub const DEFAULT_MAX_ROUND_LEAD: usize = 1000;
+
+/// The target of the logs of a Creator, unless configured otherwise with
+/// [ConfigBuilder::log_target].
+pub const DEFAULT_LOG_TARGET: &str = "rush-creator";
+
+/// With the `serde` feature the config can be read from a config file. Durations are given as
+/// integer numbers of milliseconds and all settings but the node id and the member count are
+/// optional. The parent selector, the on_created callback, the data provider, the signer and the
+/// clock cannot be (de)serialized, a deserialized config always uses the defaults for them.
+/// Deserialization validates the config in the same way as [ConfigBuilder::build].
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(
+        into = "ConfigFile<NI>",
+        try_from = "ConfigFile<NI>",
+        bound(serialize = "NI: Serialize", deserialize = "NI: Deserialize<'de>")
+    )
+)]
+pub struct Config<H: HashT, NI: NodeIdT> {
+    pub(crate) node_id: NI,
+    pub(crate) n_members: NodeCount,
+    pub(crate) lag_strategy: LagStrategy,
+    pub(crate) parent_selector: Option<Arc<dyn ParentSelector<H>>>,
+    pub(crate) threshold_policy: Arc<dyn ThresholdPolicy>,
+    pub(crate) max_parents: Option<NodeCount>,
+    pub(crate) parent_preference: ParentPreference,
+    pub(crate) min_parents: Option<NodeCount>,
+    pub(crate) min_parents_grace: Duration,
+    pub(crate) weights: Option<NodeMap<u64>>,
+    pub(crate) stall_timeout: Option<Duration>,
+    pub(crate) own_unit_timeout: Option<Duration>,
+    pub(crate) watchdog_interval: Option<Duration>,
+    pub(crate) watchdog_threshold: Duration,
+    pub(crate) progress_log_interval: Option<Duration>,
+    pub(crate) candidate_ttl: Option<Duration>,
+    pub(crate) on_created: Option<Arc<dyn Fn(&PreUnit<H>) + Send + Sync>>,
+    pub(crate) parent_wait: Option<Duration>,
+    pub(crate) start_round: Round,
+    pub(crate) max_round: Option<Round>,
+    pub(crate) max_round_lead: usize,
+    pub(crate) max_units_per_creator: Option<usize>,
+    pub(crate) bootstrap_peers: Option<NodeCount>,
+    pub(crate) initial_delay: Duration,
+    pub(crate) jitter: Duration,
+    pub(crate) rng_seed: Option<u64>,
+    pub(crate) session_id: Option<u64>,
+    pub(crate) parent_ordering: ParentOrdering,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) metrics_sink: Arc<dyn MetricsSink>,
+    pub(crate) graceful_shutdown: bool,
+    pub(crate) data_provider: Option<EncodedDataProvider>,
+    pub(crate) signer: Option<Arc<dyn Signer>>,
+    pub(crate) dry_run: bool,
+    pub(crate) observer: bool,
+    pub(crate) include_parent_list: bool,
+    pub(crate) skip_own_hash_check: bool,
+    pub(crate) log_target: String,
+    pub(crate) log_label: Option<String>,
+}
+
+impl<H: HashT, NI: NodeIdT> Config<H, NI> {
+    /// Creates a config with default values of all the optional settings. Note that, contrary to
+    /// [ConfigBuilder::build], the arguments are not validated.
+    pub fn new(node_id: NI, n_members: NodeCount, create_lag: Duration) -> Self {
+        Config {
+            node_id,
+            n_members,
+            lag_strategy: LagStrategy::Fixed(create_lag),
+            parent_selector: None,
+            threshold_policy: Arc::new(TwoThirdsPolicy),
+            max_parents: None,
+            parent_preference: ParentPreference::LowestIndex,
+            min_parents: None,
+            min_parents_grace: Duration::from_millis(0),
+            weights: None,
+            stall_timeout: None,
+            own_unit_timeout: None,
+            watchdog_interval: None,
+            progress_log_interval: None,
+            candidate_ttl: None,
+            watchdog_threshold: Duration::from_millis(0),
+            on_created: None,
+            parent_wait: None,
+            start_round: Round(0),
+            max_round: None,
+            max_round_lead: DEFAULT_MAX_ROUND_LEAD,
+            max_units_per_creator: None,
+            bootstrap_peers: None,
+            initial_delay: Duration::from_millis(0),
+            jitter: Duration::from_millis(0),
+            rng_seed: None,
+            session_id: None,
+            parent_ordering: ParentOrdering::ByIndex,
+            clock: Arc::new(TokioClock),
+            metrics_sink: Arc::new(NoopSink),
+            graceful_shutdown: false,
+            data_provider: None,
+            signer: None,
+            dry_run: false,
+            observer: false,
+            include_parent_list: false,
+            skip_own_hash_check: false,
+            log_target: DEFAULT_LOG_TARGET.to_string(),
+            log_label: None,
+        }
+    }
+
+    pub fn builder() -> ConfigBuilder<H, NI> {
+        ConfigBuilder::new()
+    }
+}
+
+/// The reasons for which [ConfigBuilder::build] may reject a config.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// No node id was given.
+    MissingNodeId,
+    /// The node id does not have an index in the committee, but the Creator is not an observer.
+    MissingNodeIndex,
+    /// The member count is zero.
+    ZeroMembers,
+    /// The index of the node id is not smaller than the member count.
+    NodeIndexOutOfRange {
+        index: NodeIndex,
+        n_members: NodeCount,
+    },
+    /// The create_lag (or one of the lags of the [LagStrategy]) exceeds [MAX_CREATE_LAG].
+    CreateLagTooLarge(Duration),
+    /// The factor of [LagStrategy::ExponentialBackoff] is zero.
+    ZeroBackoffFactor,
+    /// The number of weights differs from the member count.
+    WeightsLength {
+        n_weights: NodeCount,
+        n_members: NodeCount,
+    },
+    /// All the weights are zero.
+    ZeroTotalWeight,
+    /// The limit of units per creator is zero.
+    ZeroUnitsPerCreator,
+    /// More parents are waited for than a unit may have.
+    MinParentsAboveMax {
+        min_parents: NodeCount,
+        max_parents: NodeCount,
+    },
+    /// More units of round 0 by others are needed for bootstrapping than there are other members.
+    TooManyBootstrapPeers {
+        bootstrap_peers: NodeCount,
+        n_members: NodeCount,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ConfigError::MissingNodeId => write!(f, "no node id given"),
+            ConfigError::MissingNodeIndex => write!(f, "the node id has no index"),
+            ConfigError::ZeroMembers => write!(f, "the member count is zero"),
+            ConfigError::NodeIndexOutOfRange { index, n_members } => write!(
+                f,
+                "node index {} out of range for {} members",
+                index.0, n_members.0
+            ),
+            ConfigError::CreateLagTooLarge(lag) => {
+                write!(f, "create lag of {:?} exceeds {:?}", lag, MAX_CREATE_LAG)
+            }
+            ConfigError::ZeroBackoffFactor => write!(f, "the backoff factor is zero"),
+            ConfigError::WeightsLength {
+                n_weights,
+                n_members,
+            } => write!(
+                f,
+                "{} weights given for {} members",
+                n_weights.0, n_members.0
+            ),
+            ConfigError::ZeroTotalWeight => write!(f, "all the weights are zero"),
+            ConfigError::ZeroUnitsPerCreator => write!(f, "the limit of units per creator is zero"),
+            ConfigError::MinParentsAboveMax {
+                min_parents,
+                max_parents,
+            } => write!(
+                f,
+                "waiting for {} parents of units of at most {} parents",
+                min_parents.0, max_parents.0
+            ),
+            ConfigError::TooManyBootstrapPeers {
+                bootstrap_peers,
+                n_members,
+            } => write!(
+                f,
+                "{} bootstrap peers needed out of {} members",
+                bootstrap_peers.0, n_members.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Builds a [Config], validating it in [ConfigBuilder::build]. The node id and the member count
+/// are required, the create_lag defaults to zero.
+pub struct ConfigBuilder<H: HashT, NI: NodeIdT> {
+    node_id: Option<NI>,
+    n_members: NodeCount,
+    lag_strategy: LagStrategy,
+    parent_selector: Option<Arc<dyn ParentSelector<H>>>,
+    threshold_policy: Arc<dyn ThresholdPolicy>,
+    max_parents: Option<NodeCount>,
+    parent_preference: ParentPreference,
+    min_parents: Option<NodeCount>,
+    min_parents_grace: Duration,
+    weights: Option<NodeMap<u64>>,
+    stall_timeout: Option<Duration>,
+    own_unit_timeout: Option<Duration>,
+    watchdog_interval: Option<Duration>,
+    watchdog_threshold: Duration,
+    progress_log_interval: Option<Duration>,
+    candidate_ttl: Option<Duration>,
+    on_created: Option<Arc<dyn Fn(&PreUnit<H>) + Send + Sync>>,
+    parent_wait: Option<Duration>,
+    start_round: Round,
+    max_round: Option<Round>,
+    max_round_lead: usize,
+    max_units_per_creator: Option<usize>,
+    bootstrap_peers: Option<NodeCount>,
+    initial_delay: Duration,
+    jitter: Duration,
+    rng_seed: Option<u64>,
+    session_id: Option<u64>,
+    parent_ordering: ParentOrdering,
+    clock: Arc<dyn Clock>,
+    metrics_sink: Arc<dyn MetricsSink>,
+    graceful_shutdown: bool,
+    data_provider: Option<EncodedDataProvider>,
+    signer: Option<Arc<dyn Signer>>,
+    dry_run: bool,
+    observer: bool,
+    include_parent_list: bool,
+    skip_own_hash_check: bool,
+    log_target: String,
+    log_label: Option<String>,
+}
+
+impl<H: HashT, NI: NodeIdT> ConfigBuilder<H, NI> {
+    fn new() -> Self {
+        ConfigBuilder {
+            node_id: None,
+            n_members: NodeCount(0),
+            lag_strategy: LagStrategy::Fixed(Duration::from_millis(0)),
+            parent_selector: None,
+            threshold_policy: Arc::new(TwoThirdsPolicy),
+            max_parents: None,
+            parent_preference: ParentPreference::LowestIndex,
+            min_parents: None,
+            min_parents_grace: Duration::from_millis(0),
+            weights: None,
+            stall_timeout: None,
+            own_unit_timeout: None,
+            watchdog_interval: None,
+            progress_log_interval: None,
+            candidate_ttl: None,
+            watchdog_threshold: Duration::from_millis(0),
+            on_created: None,
+            parent_wait: None,
+            start_round: Round(0),
+            max_round: None,
+            max_round_lead: DEFAULT_MAX_ROUND_LEAD,
+            max_units_per_creator: None,
+            bootstrap_peers: None,
+            initial_delay: Duration::from_millis(0),
+            jitter: Duration::from_millis(0),
+            rng_seed: None,
+            session_id: None,
+            parent_ordering: ParentOrdering::ByIndex,
+            clock: Arc::new(TokioClock),
+            metrics_sink: Arc::new(NoopSink),
+            graceful_shutdown: false,
+            data_provider: None,
+            signer: None,
+            dry_run: false,
+            observer: false,
+            include_parent_list: false,
+            skip_own_hash_check: false,
+            log_target: DEFAULT_LOG_TARGET.to_string(),
+            log_label: None,
+        }
+    }
+
+    pub fn node_id(mut self, node_id: NI) -> Self {
+        self.node_id = Some(node_id);
+        self
+    }
+
+    pub fn n_members(mut self, n_members: NodeCount) -> Self {
+        self.n_members = n_members;
+        self
+    }
+
+    /// Sets a fixed lag between creating consecutive units.
+    pub fn create_lag(mut self, create_lag: Duration) -> Self {
+        self.lag_strategy = LagStrategy::Fixed(create_lag);
+        self
+    }
+
+    /// Replaces the fixed create_lag with the given strategy.
+    pub fn lag_strategy(mut self, lag_strategy: LagStrategy) -> Self {
+        self.lag_strategy = lag_strategy;
+        self
+    }
+
+    /// Replaces the strategy the Creator uses to choose parents for its units. By default all the
+    /// candidates become parents, as with the [GreedySelector](crate::GreedySelector).
+    pub fn parent_selector(mut self, selector: impl ParentSelector<H> + 'static) -> Self {
+        self.parent_selector = Some(Arc::new(selector));
+        self
+    }
+
+    /// Replaces the policy deciding how many parents a unit needs (by default [TwoThirdsPolicy],
+    /// i.e., floor(2*N/3) + 1).
+    pub fn threshold_policy(mut self, policy: impl ThresholdPolicy + 'static) -> Self {
+        self.threshold_policy = Arc::new(policy);
+        self
+    }
+
+    /// Limits the number of parents of every created unit. The limit never goes below the number
+    /// of parents required by the threshold policy, by default floor(2*N/3) + 1.
+    pub fn max_parents(mut self, max_parents: NodeCount) -> Self {
+        self.max_parents = Some(max_parents);
+        self
+    }
+
+    /// Sets which candidates to keep when there are more of them than the max_parents limit
+    /// allows, by default [ParentPreference::LowestIndex]. Our own unit is always kept.
+    pub fn parent_preference(mut self, parent_preference: ParentPreference) -> Self {
+        self.parent_preference = parent_preference;
+        self
+    }
+
+    /// Makes the Creator wait for at least `min_parents` parents (but never more than all the
+    /// members) before creating a unit, even if fewer are enough according to the threshold
+    /// policy. If that many parents do not arrive within `grace` since the round of the unit
+    /// became the current one, the unit is created with the parents required by the threshold.
+    /// It cannot be more than the max_parents limit.
+    pub fn min_parents(mut self, min_parents: NodeCount, grace: Duration) -> Self {
+        self.min_parents = Some(min_parents);
+        self.min_parents_grace = grace;
+        self
+    }
+
+    /// Weighs the members, e.g., by their stake, with the weights indexed by node index. A unit
+    /// then needs parents of more than 2/3 of the total weight, instead of the number of parents
+    /// required by the threshold policy, and the max_parents limit is not applied. Our own unit
+    /// of the previous round is required as always.
+    pub fn weights(mut self, weights: NodeMap<u64>) -> Self {
+        self.weights = Some(weights);
+        self
+    }
+
+    /// Makes the Creator emit [crate::NotificationOut::CreatorStalled] whenever it neither
+    /// creates a unit nor receives a new parent candidate for the given amount of time.
+    pub fn stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = Some(stall_timeout);
+        self
+    }
+
+    /// Makes the Creator emit [crate::NotificationOut::OwnUnitMissing] if only our own unit of
+    /// the previous round keeps it from creating a unit for the given amount of time, e.g., so
+    /// that the unit is broadcast again.
+    pub fn own_unit_timeout(mut self, own_unit_timeout: Duration) -> Self {
+        self.own_unit_timeout = Some(own_unit_timeout);
+        self
+    }
+
+    /// Registers a callback invoked on every preunit created by the Creator, right before it is
+    /// sent out.
+    pub fn on_created(mut self, on_created: impl Fn(&PreUnit<H>) + Send + Sync + 'static) -> Self {
+        self.on_created = Some(Arc::new(on_created));
+        self
+    }
+
+    /// Makes the Creator check every `interval` whether it created a unit within the last
+    /// `threshold`, and log a warning if not. The Creator behaves the same either way.
+    pub fn watchdog(mut self, interval: Duration, threshold: Duration) -> Self {
+        self.watchdog_interval = Some(interval);
+        self.watchdog_threshold = threshold;
+        self
+    }
+
+    /// Makes the Creator log every `interval` how many candidates for the parents of its next
+    /// unit it has and whether it is ready, regardless of whether it makes progress. There is no
+    /// such log by default.
+    pub fn progress_log_interval(mut self, interval: Duration) -> Self {
+        self.progress_log_interval = Some(interval);
+        self
+    }
+
+    /// Makes the Creator drop the candidates for parents that were not used for the given amount
+    /// of time since they arrived, so that if the Dag stalls, the units that eventually get
+    /// created are not built on very old data. A dropped candidate is taken again if it arrives
+    /// again. Our own units are never dropped. By default candidates are dropped only once their
+    /// rounds are too old.
+    pub fn candidate_ttl(mut self, ttl: Duration) -> Self {
+        self.candidate_ttl = Some(ttl);
+        self
+    }
+
+    /// Makes the Creator wait for the given amount of time once it has enough parents for a unit,
+    /// to possibly collect more of them before creating the unit.
+    pub fn parent_wait(mut self, parent_wait: Duration) -> Self {
+        self.parent_wait = Some(parent_wait);
+        self
+    }
+
+    /// Makes the Creator start at the given round instead of round 0, e.g., when joining an
+    /// ongoing session. The first unit is created only once enough parents from the previous
+    /// round are available.
+    pub fn start_round(mut self, start_round: Round) -> Self {
+        self.start_round = start_round;
+        self
+    }
+
+    /// Ends the session at the given round: once its unit of that round is created and sent, the
+    /// Creator sends [crate::NotificationOut::SessionComplete] and stops. No unit of a later round
+    /// is ever created.
+    pub fn max_round(mut self, max_round: Round) -> Self {
+        self.max_round = Some(max_round);
+        self
+    }
+
+    /// Makes the Creator ignore candidates of rounds more than `max_round_lead` rounds ahead of
+    /// the round of its next unit, instead of [DEFAULT_MAX_ROUND_LEAD].
+    pub fn max_round_lead(mut self, max_round_lead: usize) -> Self {
+        self.max_round_lead = max_round_lead;
+        self
+    }
+
+    /// Makes the Creator take at most `max_units` units by any single creator of the rounds after
+    /// the one of its next unit, while its next unit is of the same round, dropping the excess.
+    /// Only the units that become new candidates count, not the duplicates, and the units of the
+    /// current and the previous round, which the Creator needs, are always taken. A correct
+    /// member sends at most one unit per round, so a limit of a few units is enough for the
+    /// rounds it may be ahead, while a flooding member cannot take more of the Creator's time
+    /// than that. By default there is no limit.
+    pub fn max_units_per_creator(mut self, max_units: usize) -> Self {
+        self.max_units_per_creator = Some(max_units);
+        self
+    }
+
+    /// Makes the Creator wait for units of round 0 by `bootstrap_peers` other members before
+    /// creating its own one, so that it does not race ahead building rounds while nobody else is
+    /// online yet. The units of round 0 still have no parents. By default the unit of round 0 is
+    /// created right away.
+    pub fn bootstrap_peers(mut self, bootstrap_peers: NodeCount) -> Self {
+        self.bootstrap_peers = Some(bootstrap_peers);
+        self
+    }
+
+    /// Makes the Creator wait for the given amount of time before creating its first unit, e.g.,
+    /// to give the network a chance to connect. By default the first unit is created right away.
+    pub fn initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Makes the Creator wait a random amount of time of at most `jitter`, sampled anew every
+    /// round, on top of the lag after creating a unit, so that members using the same lag do not
+    /// create their units in synchronized bursts. The jitter is never subtracted from the lag.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Seeds the random source of the Creator, used for all its randomized decisions, e.g., the
+    /// jitter, making them deterministic so that a session can be replayed. By default the random
+    /// source is seeded from the entropy of the system.
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// Hashes the session id, together with the round and the creator of a unit, ahead of its
+    /// parents when computing its control hash, so that the control hashes of different sessions
+    /// never collide. By default nothing is prepended, and the control hash is that of the
+    /// parents only.
+    pub fn session_id(mut self, session_id: u64) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Sets the order in which the hashes of the parents of a unit are fed into its control
+    /// hash. All the members of a session have to use the same ordering. By default it is
+    /// [ParentOrdering::ByIndex].
+    pub fn parent_ordering(mut self, parent_ordering: ParentOrdering) -> Self {
+        self.parent_ordering = parent_ordering;
+        self
+    }
+
+    /// Makes the Creator run all its timers on the given clock instead of the tokio timer.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Makes the Creator report the events of its lifecycle to the given sink, e.g., to export
+    /// them as metrics. By default the events are dropped by [NoopSink].
+    pub fn metrics_sink(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.metrics_sink = Arc::new(sink);
+        self
+    }
+
+    /// Makes the Creator, upon receiving the exit signal, first process the units already waiting
+    /// in its channel and create the unit of the current round if possible. By default it exits
+    /// immediately.
+    pub fn graceful_shutdown(mut self, graceful_shutdown: bool) -> Self {
+        self.graceful_shutdown = graceful_shutdown;
+        self
+    }
+
+    /// Makes the Creator include the data returned by the given provider in every unit it
+    /// creates, see [PreUnit::decode_data].
+    pub fn data_provider<D: Encode>(
+        mut self,
+        mut provider: impl DataProvider<D> + 'static,
+    ) -> Self {
+        let get_data = move |round| provider.get_data(round).encode();
+        self.data_provider = Some(Arc::new(Mutex::new(get_data)));
+        self
+    }
+
+    /// Makes the Creator sign every unit it creates and send it out as
+    /// [crate::NotificationOut::SignedUnit].
+    pub fn signer(mut self, signer: impl Signer + 'static) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Makes the Creator record the units it creates instead of sending them out, see
+    /// [crate::Creator::dry_run_units]. Meant for simulations and tests.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Makes the Creator a passive observer: it never creates units, it only keeps track of the
+    /// candidates received from the parents channel, following the rounds of the Dag.
+    /// Contrary to other nodes, the node id of an observer need not have an index.
+    pub fn observer(mut self, observer: bool) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Makes the Creator follow the notification about every created unit with
+    /// [crate::NotificationOut::CreatedUnitParents], listing the parents of the unit.
+    pub fn include_parent_list(mut self, include_parent_list: bool) -> Self {
+        self.include_parent_list = include_parent_list;
+        self
+    }
+
+    /// Makes the Creator take the units with its own index at face value. By default, a unit of
+    /// ours coming back with a hash different from the one the Creator computed for it, i.e.,
+    /// the hash of the encoded preunit by the Creator's hasher, is rejected and reported with
+    /// [crate::NotificationOut::ImpostorUnit]. This needs the environment to hash the units the
+    /// same way, otherwise our own genuine units are all rejected and the Creator stalls, so the
+    /// check should be skipped if it does not.
+    pub fn skip_own_hash_check(mut self, skip_own_hash_check: bool) -> Self {
+        self.skip_own_hash_check = skip_own_hash_check;
+        self
+    }
+
+    /// Makes the Creator log with the given target instead of [DEFAULT_LOG_TARGET].
+    pub fn log_target(mut self, log_target: impl Into<String>) -> Self {
+        self.log_target = log_target.into();
+        self
+    }
+
+    /// Makes the Creator prefix all its log messages with the given label, e.g., the name of the
+    /// session, to tell apart the logs of several Creators running in one process.
+    pub fn log_label(mut self, log_label: impl Into<String>) -> Self {
+        self.log_label = Some(log_label.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Config<H, NI>, ConfigError> {
+        let node_id = self.node_id.ok_or(ConfigError::MissingNodeId)?;
+        if self.n_members == NodeCount(0) {
+            return Err(ConfigError::ZeroMembers);
+        }
+        // only an observer may be outside of the committee
+        match node_id.my_index() {
+            Some(index) if index.0 >= self.n_members.0 => {
+                return Err(ConfigError::NodeIndexOutOfRange {
+                    index,
+                    n_members: self.n_members,
+                })
+            }
+            Some(_) => {}
+            None if self.observer => {}
+            None => return Err(ConfigError::MissingNodeIndex),
+        }
+        check_lag_strategy(&self.lag_strategy)?;
+        if let Some(weights) = &self.weights {
+            if weights.len() != self.n_members.as_usize() {
+                return Err(ConfigError::WeightsLength {
+                    n_weights: NodeCount::from_usize(weights.len()),
+                    n_members: self.n_members,
+                });
+            }
+            if weights.iter().all(|weight| *weight == 0) {
+                return Err(ConfigError::ZeroTotalWeight);
+            }
+        }
+        if self.max_units_per_creator == Some(0) {
+            return Err(ConfigError::ZeroUnitsPerCreator);
+        }
+        if let (Some(min_parents), Some(max_parents)) = (self.min_parents, self.max_parents) {
+            let min_parents = min_parents.min(self.n_members);
+            if min_parents > max_parents {
+                return Err(ConfigError::MinParentsAboveMax {
+                    min_parents,
+                    max_parents,
+                });
+            }
+        }
+        match self.bootstrap_peers {
+            Some(bootstrap_peers) if bootstrap_peers >= self.n_members => {
+                return Err(ConfigError::TooManyBootstrapPeers {
+                    bootstrap_peers,
+                    n_members: self.n_members,
+                })
+            }
+            _ => {}
+        }
+        Ok(Config {
+            node_id,
+            n_members: self.n_members,
+            lag_strategy: self.lag_strategy,
+            parent_selector: self.parent_selector,
+            threshold_policy: self.threshold_policy,
+            max_parents: self.max_parents,
+            parent_preference: self.parent_preference,
+            min_parents: self.min_parents,
+            min_parents_grace: self.min_parents_grace,
+            weights: self.weights,
+            stall_timeout: self.stall_timeout,
+            own_unit_timeout: self.own_unit_timeout,
+            watchdog_interval: self.watchdog_interval,
+            watchdog_threshold: self.watchdog_threshold,
+            progress_log_interval: self.progress_log_interval,
+            candidate_ttl: self.candidate_ttl,
+            on_created: self.on_created,
+            parent_wait: self.parent_wait,
+            start_round: self.start_round,
+            max_round: self.max_round,
+            max_round_lead: self.max_round_lead,
+            max_units_per_creator: self.max_units_per_creator,
+            bootstrap_peers: self.bootstrap_peers,
+            initial_delay: self.initial_delay,
+            jitter: self.jitter,
+            rng_seed: self.rng_seed,
+            session_id: self.session_id,
+            parent_ordering: self.parent_ordering,
+            clock: self.clock,
+            metrics_sink: self.metrics_sink,
+            graceful_shutdown: self.graceful_shutdown,
+            data_provider: self.data_provider,
+            signer: self.signer,
+            dry_run: self.dry_run,
+            observer: self.observer,
+            include_parent_list: self.include_parent_list,
+            skip_own_hash_check: self.skip_own_hash_check,
+            log_target: self.log_target,
+            log_label: self.log_label,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<H: HashT, NI: NodeIdT> TryFrom<ConfigFile<NI>> for Config<H, NI> {
+    type Error = ConfigError;
+
+    fn try_from(file: ConfigFile<NI>) -> Result<Self, Self::Error> {
+        let mut builder = Config::builder()
+            .node_id(file.node_id)
+            .n_members(file.n_members);
+        if let Some(create_lag) = file.create_lag {
+            builder = builder.create_lag(create_lag);
+        }
+        if let Some(lag_strategy) = file.lag_strategy {
+            builder = builder.lag_strategy(lag_strategy);
+        }
+        if let Some(max_parents) = file.max_parents {
+            builder = builder.max_parents(max_parents);
+        }
+        if let Some(parent_preference) = file.parent_preference {
+            builder = builder.parent_preference(parent_preference);
+        }
+        if let Some(min_parents) = file.min_parents {
+            let grace = file.min_parents_grace.unwrap_or_default();
+            builder = builder.min_parents(min_parents, grace);
+        }
+        if let Some(weights) = file.weights {
+            builder = builder.weights(weights);
+        }
+        if let Some(stall_timeout) = file.stall_timeout {
+            builder = builder.stall_timeout(stall_timeout);
+        }
+        if let Some(own_unit_timeout) = file.own_unit_timeout {
+            builder = builder.own_unit_timeout(own_unit_timeout);
+        }
+        if let Some(interval) = file.watchdog_interval {
+            let threshold = file.watchdog_threshold.unwrap_or(interval);
+            builder = builder.watchdog(interval, threshold);
+        }
+        if let Some(interval) = file.progress_log_interval {
+            builder = builder.progress_log_interval(interval);
+        }
+        if let Some(ttl) = file.candidate_ttl {
+            builder = builder.candidate_ttl(ttl);
+        }
+        if let Some(parent_wait) = file.parent_wait {
+            builder = builder.parent_wait(parent_wait);
+        }
+        if let Some(start_round) = file.start_round {
+            builder = builder.start_round(start_round);
+        }
+        if let Some(max_round) = file.max_round {
+            builder = builder.max_round(max_round);
+        }
+        if let Some(max_round_lead) = file.max_round_lead {
+            builder = builder.max_round_lead(max_round_lead);
+        }
+        if let Some(max_units) = file.max_units_per_creator {
+            builder = builder.max_units_per_creator(max_units);
+        }
+        if let Some(bootstrap_peers) = file.bootstrap_peers {
+            builder = builder.bootstrap_peers(bootstrap_peers);
+        }
+        if let Some(initial_delay) = file.initial_delay {
+            builder = builder.initial_delay(initial_delay);
+        }
+        if let Some(jitter) = file.jitter {
+            builder = builder.jitter(jitter);
+        }
+        if let Some(seed) = file.rng_seed {
+            builder = builder.rng_seed(seed);
+        }
+        if let Some(session_id) = file.session_id {
+            builder = builder.session_id(session_id);
+        }
+        if let Some(parent_ordering) = file.parent_ordering {
+            builder = builder.parent_ordering(parent_ordering);
+        }
+        builder = builder.graceful_shutdown(file.graceful_shutdown);
+        builder = builder.observer(file.observer);
+        builder = builder.include_parent_list(file.include_parent_list);
+        builder = builder.skip_own_hash_check(file.skip_own_hash_check);
+        if let Some(log_target) = file.log_target {
+            builder = builder.log_target(log_target);
+        }
+        if let Some(log_label) = file.log_label {
+            builder = builder.log_label(log_label);
+        }
+        builder.build()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<H: HashT, NI: NodeIdT> From<Config<H, NI>> for ConfigFile<NI> {
+    fn from(conf: Config<H, NI>) -> Self {
+        let (create_lag, lag_strategy) = match conf.lag_strategy {
+            LagStrategy::Fixed(create_lag) => (Some(create_lag), None),
+            lag_strategy => (None, Some(lag_strategy)),
+        };
+        ConfigFile {
+            node_id: conf.node_id,
+            n_members: conf.n_members,
+            create_lag,
+            lag_strategy,
+            max_parents: conf.max_parents,
+            parent_preference: Some(conf.parent_preference)
+                .filter(|preference| *preference != ParentPreference::LowestIndex),
+            min_parents: conf.min_parents,
+            min_parents_grace: conf.min_parents.and(Some(conf.min_parents_grace)),
+            weights: conf.weights,
+            stall_timeout: conf.stall_timeout,
+            own_unit_timeout: conf.own_unit_timeout,
+            watchdog_interval: conf.watchdog_interval,
+            watchdog_threshold: conf.watchdog_interval.and(Some(conf.watchdog_threshold)),
+            progress_log_interval: conf.progress_log_interval,
+            candidate_ttl: conf.candidate_ttl,
+            parent_wait: conf.parent_wait,
+            start_round: Some(conf.start_round).filter(|round| *round > Round(0)),
+            max_round: conf.max_round,
+            max_round_lead: Some(conf.max_round_lead)
+                .filter(|lead| *lead != DEFAULT_MAX_ROUND_LEAD),
+            max_units_per_creator: conf.max_units_per_creator,
+            bootstrap_peers: conf.bootstrap_peers,
+            initial_delay: Some(conf.initial_delay).filter(|delay| *delay > Duration::default()),
+            jitter: Some(conf.jitter).filter(|jitter| *jitter > Duration::default()),
+            rng_seed: conf.rng_seed,
+            session_id: conf.session_id,
+            parent_ordering: Some(conf.parent_ordering)
+                .filter(|ordering| *ordering != ParentOrdering::ByIndex),
+            graceful_shutdown: conf.graceful_shutdown,
+            observer: conf.observer,
+            include_parent_list: conf.include_parent_list,
+            skip_own_hash_check: conf.skip_own_hash_check,
+            log_target: Some(conf.log_target).filter(|target| target != DEFAULT_LOG_TARGET),
+            log_label: conf.log_label,
+        }
+    }
+}
+
+// The serializable part of a Config. A fixed lag is stored as `create_lag`, any other strategy as
+// `lag_strategy` (which takes precedence when both are present).
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ConfigFile<NI> {
+    node_id: NI,
+    n_members: NodeCount,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    create_lag: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lag_strategy: Option<LagStrategy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_parents: Option<NodeCount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent_preference: Option<ParentPreference>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_parents: Option<NodeCount>,
+    // defaults to zero, i.e., to falling back to the threshold right away
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    min_parents_grace: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weights: Option<NodeMap<u64>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    stall_timeout: Option<Duration>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    own_unit_timeout: Option<Duration>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    watchdog_interval: Option<Duration>,
+    // defaults to the watchdog_interval
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    watchdog_threshold: Option<Duration>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    progress_log_interval: Option<Duration>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    candidate_ttl: Option<Duration>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    parent_wait: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_round: Option<Round>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_round: Option<Round>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_round_lead: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_units_per_creator: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bootstrap_peers: Option<NodeCount>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    initial_delay: Option<Duration>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "millis_option"
+    )]
+    jitter: Option<Duration>,
+    // formerly seeding the jitter only
+    #[serde(
+        default,
+        alias = "jitter_seed",
+        skip_serializing_if = "Option::is_none"
+    )]
+    rng_seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent_ordering: Option<ParentOrdering>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    graceful_shutdown: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    observer: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    include_parent_list: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    skip_own_hash_check: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_label: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// (De)serializes a [Duration] as an integer number of milliseconds.
+#[cfg(feature = "serde")]
+pub(crate) mod millis {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use tokio::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(lag: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        (lag.as_millis() as u64).serialize(s)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_millis(u64::deserialize(d)?))
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod millis_option {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use tokio::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(
+        lag: &Option<Duration>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        lag.map(|lag| lag.as_millis() as u64).serialize(s)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(d)?.map(Duration::from_millis))
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod millis_seq {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use tokio::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(lags: &[Duration], s: S) -> Result<S::Ok, S::Error> {
+        let lags: Vec<u64> = lags.iter().map(|lag| lag.as_millis() as u64).collect();
+        lags.serialize(s)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Duration>, D::Error> {
+        let lags = Vec::<u64>::deserialize(d)?;
+        Ok(lags.into_iter().map(Duration::from_millis).collect())
+    }
+}
+
+fn check_lag_strategy(lag_strategy: &LagStrategy) -> Result<(), ConfigError> {
+    let lags = match lag_strategy {
+        LagStrategy::Fixed(lag) | LagStrategy::FixedRate(lag) => vec![*lag],
+        LagStrategy::ExponentialBackoff { base, max, factor } => {
+            if *factor == 0 {
+                return Err(ConfigError::ZeroBackoffFactor);
+            }
+            vec![*base, *max]
+        }
+        LagStrategy::Schedule(lags) => lags.clone(),
+    };
+    match lags.into_iter().find(|lag| *lag > MAX_CREATE_LAG) {
+        Some(lag) => Err(ConfigError::CreateLagTooLarge(lag)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::mock::{Hash, NodeId},
+        MyIndex,
+    };
+    use codec::{Decode, Encode};
+    use derive_more::Display;
+
+    /// A node id of someone outside of the committee.
+    #[derive(Clone, Debug, Display, PartialEq, Eq, Hash, Encode, Decode)]
+    #[display(fmt = "Observer")]
+    struct Observer;
+
+    impl MyIndex for Observer {
+        fn my_index(&self) -> Option<NodeIndex> {
+            None
+        }
+    }
+
+    fn builder() -> ConfigBuilder<Hash, NodeId> {
+        Config::builder().node_id(1.into()).n_members(NodeCount(4))
+    }
+
+    #[test]
+    fn valid_config_is_built() {
+        let conf = builder()
+            .create_lag(Duration::from_millis(500))
+            .build()
+            .expect("the config is valid");
+        assert_eq!(conf.node_id, 1.into());
+        assert_eq!(conf.n_members, NodeCount(4));
+        assert_eq!(
+            conf.lag_strategy,
+            LagStrategy::Fixed(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn missing_node_id_is_rejected() {
+        let result = Config::<Hash, NodeId>::builder()
+            .n_members(NodeCount(4))
+            .build();
+        assert_eq!(result.err(), Some(ConfigError::MissingNodeId));
+    }
+
+    #[test]
+    fn node_id_without_index_is_rejected() {
+        let result = Config::<Hash, Observer>::builder()
+            .node_id(Observer)
+            .n_members(NodeCount(4))
+            .build();
+        assert_eq!(result.err(), Some(ConfigError::MissingNodeIndex));
+        let result = Config::<Hash, Observer>::builder()
+            .node_id(Observer)
+            .n_members(NodeCount(4))
+            .observer(true)
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn zero_members_are_rejected() {
+        let result = builder().n_members(NodeCount(0)).build();
+        assert_eq!(result.err(), Some(ConfigError::ZeroMembers));
+    }
+
+    #[test]
+    fn out_of_range_index_is_rejected() {
+        let result = builder().node_id(4.into()).build();
+        assert_eq!(
+            result.err(),
+            Some(ConfigError::NodeIndexOutOfRange {
+                index: NodeIndex(4),
+                n_members: NodeCount(4),
+            })
+        );
+    }
+
+    #[test]
+    fn absurd_create_lag_is_rejected() {
+        let lag = MAX_CREATE_LAG + Duration::from_secs(1);
+        let result = builder().create_lag(lag).build();
+        assert_eq!(result.err(), Some(ConfigError::CreateLagTooLarge(lag)));
+
+        let result = builder()
+            .lag_strategy(LagStrategy::Schedule(vec![Duration::from_millis(10), lag]))
+            .build();
+        assert_eq!(result.err(), Some(ConfigError::CreateLagTooLarge(lag)));
+    }
+
+    #[test]
+    fn zero_backoff_factor_is_rejected() {
+        let result = builder()
+            .lag_strategy(LagStrategy::ExponentialBackoff {
+                base: Duration::from_millis(10),
+                max: Duration::from_millis(100),
+                factor: 0,
+            })
+            .build();
+        assert_eq!(result.err(), Some(ConfigError::ZeroBackoffFactor));
+    }
+
+    #[test]
+    fn weights_are_validated() {
+        let result = builder().weights(vec![1, 2, 3].into()).build();
+        assert_eq!(
+            result.err(),
+            Some(ConfigError::WeightsLength {
+                n_weights: NodeCount(3),
+                n_members: NodeCount(4),
+            })
+        );
+        let result = builder().weights(vec![0; 4].into()).build();
+        assert_eq!(result.err(), Some(ConfigError::ZeroTotalWeight));
+        let conf = builder().weights(vec![0, 0, 0, 1].into()).build().unwrap();
+        assert_eq!(conf.weights, Some(vec![0, 0, 0, 1].into()));
+    }
+
+    #[test]
+    fn zero_units_per_creator_is_rejected() {
+        let result = builder().max_units_per_creator(0).build();
+        assert_eq!(result.err(), Some(ConfigError::ZeroUnitsPerCreator));
+    }
+
+    #[test]
+    fn min_parents_above_max_is_rejected() {
+        let result = builder()
+            .n_members(NodeCount(10))
+            .min_parents(NodeCount(9), Duration::from_millis(10))
+            .max_parents(NodeCount(8))
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(ConfigError::MinParentsAboveMax {
+                min_parents: NodeCount(9),
+                max_parents: NodeCount(8),
+            })
+        );
+        let result = builder()
+            .min_parents(NodeCount(9), Duration::from_millis(10))
+            .max_parents(NodeCount(4))
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn unreachable_bootstrap_is_rejected() {
+        let result = builder().bootstrap_peers(NodeCount(4)).build();
+        assert_eq!(
+            result.err(),
+            Some(ConfigError::TooManyBootstrapPeers {
+                bootstrap_peers: NodeCount(4),
+                n_members: NodeCount(4),
+            })
+        );
+        assert!(builder().bootstrap_peers(NodeCount(3)).build().is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_is_read_from_json() {
+        let json = r#"{"node_id": 2, "n_members": 7, "create_lag": 250, "stall_timeout": 1000}"#;
+        let conf: Config<Hash, NodeId> = serde_json::from_str(json).unwrap();
+        assert_eq!(conf.node_id, 2.into());
+        assert_eq!(conf.n_members, NodeCount(7));
+        assert_eq!(
+            conf.lag_strategy,
+            LagStrategy::Fixed(Duration::from_millis(250))
+        );
+        assert_eq!(conf.stall_timeout, Some(Duration::from_secs(1)));
+        assert_eq!(conf.max_parents, None);
+        assert_eq!(conf.parent_wait, None);
+
+        let encoded = serde_json::to_value(&conf).unwrap();
+        assert_eq!(
+            encoded,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn lag_strategy_round_trips_through_json() {
+        let conf = builder()
+            .lag_strategy(LagStrategy::ExponentialBackoff {
+                base: Duration::from_millis(10),
+                max: Duration::from_millis(500),
+                factor: 2,
+            })
+            .build()
+            .unwrap();
+        let encoded = serde_json::to_string(&conf).unwrap();
+        assert!(encoded.contains(r#""exponential_backoff":{"base":10,"max":500,"factor":2}"#));
+        let decoded: Config<Hash, NodeId> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded.lag_strategy, conf.lag_strategy);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn jitter_seed_is_read_as_rng_seed() {
+        let json = r#"{"node_id": 0, "n_members": 4, "jitter_seed": 7}"#;
+        let conf: Config<Hash, NodeId> = serde_json::from_str(json).unwrap();
+        assert_eq!(conf.rng_seed, Some(7));
+        let encoded = serde_json::to_string(&conf).unwrap();
+        assert!(encoded.contains(r#""rng_seed":7"#));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invalid_config_is_not_deserialized() {
+        let json = r#"{"node_id": 0, "n_members": 0}"#;
+        let result = serde_json::from_str::<Config<Hash, NodeId>>(json);
+        let error = result.err().expect("zero members are rejected").to_string();
+        assert!(error.contains(&ConfigError::ZeroMembers.to_string()));
+
+        let json = r#"{"node_id": 0, "n_members": 4, "min_parents": 4, "max_parents": 3}"#;
+        let result = serde_json::from_str::<Config<Hash, NodeId>>(json);
+        let error = result
+            .err()
+            .expect("too few max parents are rejected")
+            .to_string();
+        let expected = ConfigError::MinParentsAboveMax {
+            min_parents: NodeCount(4),
+            max_parents: NodeCount(3),
+        };
+        assert!(error.contains(&expected.to_string()));
+    }
+}
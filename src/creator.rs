@@ -1,137 +1,5144 @@
+#[cfg(feature = "tracing")]
+use crate::DEFAULT_LOG_TARGET;
 use crate::{
+    config::EncodedDataProvider,
+    creator_core::{
+        AddOutcome, CandidateStore, CreateReadiness, CreatorCore, CreatorDelta, CreatorState,
+        DeltaError, ReconfigureError, SnapshotError,
+    },
     nodes::{NodeCount, NodeIndex, NodeMap},
-    Config, HashT, NodeIdT, NotificationOut, PreUnit, Receiver, Round, Sender, Unit,
+    Config, FallibleHashing, HashError, HashT, Hasher, NodeIdT, NotificationOut, PreUnit, Receiver,
+    Round, Sender, Signer, Unit,
+};
+use codec::Encode;
+use futures::{future, stream, FutureExt, Stream, StreamExt};
+use log::{debug, error, warn};
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
 };
-use futures::{FutureExt, StreamExt};
-use log::{debug, error};
 use tokio::{
-    sync::oneshot,
-    time::{delay_for, Duration},
+    sync::{mpsc, oneshot, watch},
+    time::{delay_for, Duration, Instant},
 };
 
+/// Supplies the application data included in the units created by the Creator.
+pub trait DataProvider<D>: Send {
+    /// Returns the data for the unit of the given round. It is called exactly once for every
+    /// created unit, right before the unit is sent out, so it must not block: if there is
+    /// nothing to include, an empty payload should be returned.
+    fn get_data(&mut self, round: Round) -> D;
+}
+
+/// A source of time for the Creator: all its timers and lag measurements go through it.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Returns a future completing once the given amount of time passes.
+    fn delay(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// The default clock, backed by the timer of the tokio runtime.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn delay(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(delay_for(duration))
+    }
+}
+
+// Turns a panic of the wrapped hasher into a HashError, so that a faulty hasher only skips the
+// creation of a unit, like a failing one, rather than taking the whole Creator down with it. The
+// hasher is used again afterwards, so it is up to it to stay usable after panicking.
+struct CatchUnwind<'a, Hashing>(&'a Hashing);
+
+impl<'a, H: HashT, Hashing: Hasher<H>> Hasher<H> for CatchUnwind<'a, Hashing> {
+    fn hash(&self, data: &[u8]) -> H {
+        self.0.hash(data)
+    }
+
+    fn try_hash(&self, data: &[u8]) -> Result<H, HashError> {
+        panic::catch_unwind(AssertUnwindSafe(|| self.0.try_hash(data)))
+            .unwrap_or_else(|_| Err(HashError("the hasher panicked".into())))
+    }
+}
+
+/// Receives the events of the whole lifecycle of the Creator, e.g., to bridge them to a metrics
+/// system. The methods are called synchronously by the Creator, so they must not block.
+pub trait MetricsSink: Send + Sync {
+    /// Our unit of the given round was created with `parents` parents, right before it is sent
+    /// out.
+    fn unit_created(&self, round: Round, parents: NodeCount);
+
+    /// A unit was received as a parent candidate, whatever becomes of it.
+    fn candidate_received(&self);
+
+    /// A received unit was too old to become a parent of any of our next units.
+    fn candidate_stale(&self);
+
+    /// No unit was created at the given round for longer than the stall timeout.
+    fn stalled(&self, round: Round);
+}
+
+/// The default sink, dropping all the events.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopSink;
+
+impl MetricsSink for NoopSink {
+    fn unit_created(&self, _round: Round, _parents: NodeCount) {}
+
+    fn candidate_received(&self) {}
+
+    fn candidate_stale(&self) {}
+
+    fn stalled(&self, _round: Round) {}
+}
+
+/// Decides how long the Creator waits after creating a unit before it may create the next one.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum LagStrategy {
+    /// Always wait the same amount of time.
+    Fixed(#[cfg_attr(feature = "serde", serde(with = "crate::config::millis"))] Duration),
+    /// Start with `base` and multiply the lag by `factor` (up to `max`) whenever creating a unit
+    /// took at least `factor` times the current lag, i.e., we were mostly waiting for parents.
+    /// Otherwise the lag is divided by `factor`, but never drops below `base`.
+    ExponentialBackoff {
+        #[cfg_attr(feature = "serde", serde(with = "crate::config::millis"))]
+        base: Duration,
+        #[cfg_attr(feature = "serde", serde(with = "crate::config::millis"))]
+        max: Duration,
+        factor: u32,
+    },
+    /// Wait `schedule[r]` after creating the unit of round r, or the last element of the schedule
+    /// for rounds beyond its length (no lag at all if the schedule is empty).
+    Schedule(
+        #[cfg_attr(feature = "serde", serde(with = "crate::config::millis_seq"))] Vec<Duration>,
+    ),
+    /// Create units at a fixed rate, one per period, like a ticker: the period is measured from
+    /// the time the previous unit was due rather than from when it was created, so the time spent
+    /// waiting for parents is not waited for again. The tick is skipped if the previous unit was
+    /// late by more than a whole period.
+    FixedRate(#[cfg_attr(feature = "serde", serde(with = "crate::config::millis"))] Duration),
+}
+
+impl LagStrategy {
+    fn initial_lag(&self) -> Duration {
+        match self {
+            LagStrategy::Fixed(lag) | LagStrategy::FixedRate(lag) => *lag,
+            LagStrategy::ExponentialBackoff { base, .. } => *base,
+            LagStrategy::Schedule(_) => self.next_lag(Round(0), Duration::default(), None),
+        }
+    }
+
+    // computes the lag following `lag` after creating a unit of the given round, given the time
+    // that passed between the last two units (if there were two already)
+    fn next_lag(&self, round: Round, lag: Duration, interval: Option<Duration>) -> Duration {
+        match self {
+            LagStrategy::Fixed(lag) | LagStrategy::FixedRate(lag) => *lag,
+            LagStrategy::ExponentialBackoff { base, max, factor } => match interval {
+                Some(interval) if interval >= lag * *factor => (lag * *factor).min(*max),
+                Some(_) => (lag / *factor).max(*base),
+                None => lag,
+            },
+            LagStrategy::Schedule(schedule) => schedule
+                .get(round.0)
+                .or_else(|| schedule.last())
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The state of a Creator as a whole, see [Creator::health].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CreatorHealth {
+    /// The Creator is making progress, or has been waiting for its next unit only briefly.
+    Healthy,
+    /// The Creator has been waiting for too long for the parents of its next unit, having only
+    /// `have` of the `need` required ones.
+    ParentStarved { have: NodeCount, need: NodeCount },
+    /// The Creator has enough parents for its next unit, except for its own unit of `round`,
+    /// which has been missing for too long.
+    OwnUnitMissing { round: Round },
+    /// The notifications of the Creator do not fit into its bounded channel, so it waits for the
+    /// receiver instead of creating units.
+    SendBackpressured,
+}
+
+/// The values of the counters kept by a Creator at some point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CreatorStats {
+    /// The number of units created (and sent out).
+    pub created: u64,
+    /// The number of parent candidates received.
+    pub candidates_seen: u64,
+    /// The number of received parent candidates ignored for being too old.
+    pub candidates_stale: u64,
+    /// The round of the next unit to be created.
+    pub current_round: Round,
+    /// The number of times the watchdog found that no unit was created for too long.
+    pub watchdog_warnings: u64,
+    /// The number of periodic progress summaries logged, see
+    /// [crate::ConfigBuilder::progress_log_interval].
+    pub progress_logs: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    created: AtomicU64,
+    candidates_seen: AtomicU64,
+    candidates_stale: AtomicU64,
+    current_round: AtomicU64,
+    // whether we are ready to create the unit of the current round, but wait for the create_lag
+    lag_blocked: AtomicBool,
+    watchdog_warnings: AtomicU64,
+    progress_logs: AtomicU64,
+    // the numbers of parents of the most recently created units, the latest at the back
+    recent_parent_counts: Mutex<VecDeque<NodeCount>>,
+    // the times to ready of the most recent rounds, the latest at the back
+    recent_times_to_ready: Mutex<VecDeque<(Round, Duration)>>,
+}
+
+/// The number of the most recently created units taken into account by
+/// [CreatorStatsHandle::parent_histogram], and of the most recent rounds taken into account by
+/// [CreatorStatsHandle::times_to_ready].
+pub const PARENT_HISTOGRAM_WINDOW: usize = 100;
+
+/// Gives access to the counters of a Creator, also while it is running.
+#[derive(Clone, Default)]
+pub struct CreatorStatsHandle(Arc<Counters>);
+
+impl CreatorStatsHandle {
+    pub fn stats(&self) -> CreatorStats {
+        CreatorStats {
+            created: self.0.created.load(Ordering::Relaxed),
+            candidates_seen: self.0.candidates_seen.load(Ordering::Relaxed),
+            candidates_stale: self.0.candidates_stale.load(Ordering::Relaxed),
+            current_round: Round(self.0.current_round.load(Ordering::Relaxed) as usize),
+            watchdog_warnings: self.0.watchdog_warnings.load(Ordering::Relaxed),
+            progress_logs: self.0.progress_logs.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Tells how many of the last [PARENT_HISTOGRAM_WINDOW] created units had the given number
+    /// of parents, counting the parents actually included in the units.
+    pub fn parent_histogram(&self) -> BTreeMap<NodeCount, u64> {
+        let mut histogram = BTreeMap::new();
+        for n_parents in self.0.recent_parent_counts.lock().iter() {
+            *histogram.entry(*n_parents).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Tells, for each of the last [PARENT_HISTOGRAM_WINDOW] rounds the Creator was ready to
+    /// create a unit of, how long it took from the round becoming the current one until the
+    /// Creator was ready, the latest round last. Long times mean the Creator waits for parents,
+    /// short ones mean it is bound by the lag.
+    pub fn times_to_ready(&self) -> Vec<(Round, Duration)> {
+        self.0
+            .recent_times_to_ready
+            .lock()
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_parents(&self, n_parents: NodeCount) {
+        let mut recent_parent_counts = self.0.recent_parent_counts.lock();
+        if recent_parent_counts.len() == PARENT_HISTOGRAM_WINDOW {
+            recent_parent_counts.pop_front();
+        }
+        recent_parent_counts.push_back(n_parents);
+    }
+
+    fn clear(&self) {
+        for counter in [
+            &self.0.created,
+            &self.0.candidates_seen,
+            &self.0.candidates_stale,
+            &self.0.current_round,
+            &self.0.watchdog_warnings,
+            &self.0.progress_logs,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.0.recent_parent_counts.lock().clear();
+        self.0.recent_times_to_ready.lock().clear();
+    }
+
+    fn record_time_to_ready(&self, round: Round, time_to_ready: Duration) {
+        let mut recent_times_to_ready = self.0.recent_times_to_ready.lock();
+        if recent_times_to_ready.len() == PARENT_HISTOGRAM_WINDOW {
+            recent_times_to_ready.pop_front();
+        }
+        recent_times_to_ready.push_back((round, time_to_ready));
+    }
+}
+
+/// Gives access to the round of the next unit of a Creator, also while it is running, e.g., to
+/// display its progress.
+#[derive(Clone)]
+pub struct RoundWatch(Arc<Counters>);
+
+impl RoundWatch {
+    /// The round of the next unit to be created. It only grows, unless the Creator is reset.
+    pub fn current_round(&self) -> Round {
+        Round(self.0.current_round.load(Ordering::Relaxed) as usize)
+    }
+
+    /// Whether the Creator is ready to create the unit of the current round, but waits for the
+    /// create_lag to pass first. A trigger, see [Creator::with_trigger], cuts the wait short.
+    pub fn lag_blocked(&self) -> bool {
+        self.0.lag_blocked.load(Ordering::Relaxed)
+    }
+}
+
+/// The reasons for which a Creator cannot be constructed or has to stop.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CreatorError {
+    /// The node id does not have an index in the committee, so the Creator cannot create units.
+    /// Only an observer may run without an index.
+    NoIndex,
+    /// The index of the node id is not smaller than the member count.
+    IndexOutOfRange {
+        index: NodeIndex,
+        n_members: NodeCount,
+    },
+    /// The channel for sending out notifications is closed.
+    ChannelClosed,
+    /// The parents passed to [Creator::create_with_parents] are not one per member of the
+    /// committee.
+    ParentsLength {
+        len: NodeCount,
+        n_members: NodeCount,
+    },
+    /// The parents passed to [Creator::create_with_parents] do not make a valid unit, for the
+    /// given reason.
+    InvalidParents(CreateReadiness),
+    /// Hashing the unit with the parents passed to [Creator::create_with_parents] failed.
+    Hashing(HashError),
+    /// The capacity given to [CreatorBuilder::out_channel_capacity] is zero.
+    ZeroChannelCapacity,
+    /// The snapshot passed to [Creator::from_snapshot] cannot be restored.
+    Snapshot(SnapshotError),
+}
+
+impl Display for CreatorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CreatorError::NoIndex => write!(f, "the node id has no index"),
+            CreatorError::IndexOutOfRange { index, n_members } => write!(
+                f,
+                "node index {} out of range for {} members",
+                index.0, n_members.0
+            ),
+            CreatorError::ChannelClosed => write!(f, "the notifications channel is closed"),
+            CreatorError::ParentsLength { len, n_members } => {
+                write!(f, "{} parents for {} members", len.0, n_members.0)
+            }
+            CreatorError::InvalidParents(readiness) => {
+                write!(f, "the parents do not make a valid unit: {:?}", readiness)
+            }
+            CreatorError::Hashing(e) => write!(f, "{}", e),
+            CreatorError::ZeroChannelCapacity => {
+                write!(f, "the notifications channel has no capacity")
+            }
+            CreatorError::Snapshot(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CreatorError {}
+
+// here rather than next to the errors, as the core itself does not use std
+impl std::error::Error for ReconfigureError {}
+
+impl std::error::Error for DeltaError {}
+
+impl std::error::Error for SnapshotError {}
+
+/// A single input of a Creator, see [Creator::apply_events].
+#[derive(Clone, Debug, PartialEq)]
+pub enum CreatorEvent<H: HashT> {
+    /// A unit added to the Dag, as if it arrived through the parents channel.
+    AddUnit {
+        round: Round,
+        pid: NodeIndex,
+        hash: H,
+    },
+    /// An attempt to create the unit of the current round, which succeeds only if ready.
+    TryCreate,
+}
+
 /// A process responsible for creating new units. It receives all the units added locally to the Dag
 /// via the parents_rx channel endpoint. It creates units according to an internal strategy respecting
 /// always the following constraints: for a unit U of round r
 /// - all U's parents are from round (r-1),
 /// - all U's parents are created by different nodes,
 /// - one of U's parents is the (r-1)-round unit by U's creator,
-/// - U has > floor(2*N/3) parents.
+/// - U has as many parents as required by the [crate::ThresholdPolicy], by default > floor(2*N/3).
+///
 /// The currently implemented strategy creates the unit U at the very first moment when enough
-/// candidates for parents are available for all the above constraints to be satisfied.
-pub(crate) struct Creator<H: HashT, NI: NodeIdT> {
+/// candidates for parents are available for all the above constraints to be satisfied. Which of
+/// the candidates become parents is decided by the [crate::ParentSelector] passed in the [Config].
+///
+/// Note that in committees of at most 3 members floor(2*N/3) = N - 1, so every unit needs the
+/// units of all the members as parents and no member may be faulty. In particular a single member
+/// builds on its own units only and creates a unit as soon as its previous one is in the Dag.
+///
+/// The candidates and the decisions based on them are kept in a [CreatorCore], which the Creator
+/// drives with the units from the channel, its timers and its lag.
+pub struct Creator<H: HashT, NI: NodeIdT, Hashing: Hasher<H>> {
     node_id: NI,
     parents_rx: Receiver<Unit<H>>,
-    new_units_tx: Sender<NotificationOut<H>>,
-    n_members: NodeCount,
-    current_round: Round, // current_round is the round number of our next unit
-    candidates_by_round: Vec<NodeMap<Option<H>>>,
-    n_candidates_by_round: Vec<NodeCount>,
-    hashing: Box<dyn Fn(&[u8]) -> H + Send>,
+    new_units_tx: NotificationSender<H>,
+    // the candidates and the current round, our index in the committee changing only upon
+    // reconfiguration
+    core: CreatorCore<H>,
+    hashing: Hashing,
+    min_parents: Option<NodeCount>,
+    min_parents_grace: Duration,
+    lag_strategy: LagStrategy,
     create_lag: Duration,
+    last_created: Option<Instant>,
+    // when the next unit is due at a fixed rate
+    next_unit_due: Option<Instant>,
+    // when the current round became the current one
+    round_started: Instant,
+    // whether the time to ready of the current round was already recorded
+    ready_noted: bool,
+    // the last round at which we reported needing the units of banned members as parents
+    banned_needed_round: Option<Round>,
+    stall_timeout: Option<Duration>,
+    own_unit_timeout: Option<Duration>,
+    watchdog_interval: Option<Duration>,
+    watchdog_threshold: Duration,
+    progress_log_interval: Option<Duration>,
+    candidate_ttl: Option<Duration>,
+    // when the candidates were added, kept only with a candidate_ttl
+    candidates_added: BTreeMap<(Round, NodeIndex), Instant>,
+    // when the last unit was created, or when the creation started if no unit was created since
+    last_progress: Option<Instant>,
+    on_created: Option<Arc<dyn Fn(&PreUnit<H>) + Send + Sync>>,
+    parent_wait: Option<Duration>,
+    // the last round at which we waited for additional parents
+    parent_wait_round: Option<Round>,
+    stats: CreatorStatsHandle,
+    // the round of our next unit, for the futures returned by reached
+    round_tx: watch::Sender<Round>,
+    round_rx: watch::Receiver<Round>,
+    max_round: Option<Round>,
+    initial_delay: Duration,
+    jitter: Duration,
+    // the random source of all the randomized decisions, seeded with the configured seed if any
+    rng: StdRng,
+    clock: Arc<dyn Clock>,
+    metrics_sink: Arc<dyn MetricsSink>,
+    graceful_shutdown: bool,
+    data_provider: Option<EncodedDataProvider>,
+    signer: Option<Arc<dyn Signer>>,
+    // the created units, recorded instead of being sent out in the dry run mode
+    dry_run_units: Option<Vec<PreUnit<H>>>,
+    // an observer never creates units, it only follows the rounds of the Dag
+    observer: bool,
+    include_parent_list: bool,
+    log_target: String,
+    // prepended to every log message, empty unless a label is configured
+    log_prefix: String,
+    // signals to create the next unit right away, without waiting for the lag
+    trigger_rx: Option<Receiver<()>>,
+    // open from the moment the current round becomes current until our unit of it is sent
+    #[cfg(feature = "tracing")]
+    round_span: tracing::Span,
+}
+
+// Completes when a trigger arrives. Once the channel is closed it never completes.
+async fn next_trigger(trigger_rx: &mut Option<Receiver<()>>) {
+    if let Some(rx) = trigger_rx {
+        if rx.recv().await.is_some() {
+            return;
+        }
+    }
+    *trigger_rx = None;
+    future::pending::<()>().await
+}
+
+// The channel of the notifications of the Creator. A notification that does not fit into a full
+// bounded channel waits in the backlog, and the Creator does not create further units until the
+// backlog is delivered, so that no unit is ever dropped.
+enum NotificationSender<H: HashT> {
+    Unbounded(Sender<NotificationOut<H>>),
+    Bounded {
+        tx: mpsc::Sender<NotificationOut<H>>,
+        backlog: VecDeque<NotificationOut<H>>,
+    },
+}
+
+impl<H: HashT> NotificationSender<H> {
+    fn send(
+        &mut self,
+        notification: NotificationOut<H>,
+    ) -> Result<(), mpsc::error::SendError<NotificationOut<H>>> {
+        match self {
+            NotificationSender::Unbounded(tx) => tx.send(notification),
+            NotificationSender::Bounded { tx, backlog } => {
+                if !backlog.is_empty() {
+                    backlog.push_back(notification);
+                    return Ok(());
+                }
+                match tx.try_send(notification) {
+                    Ok(()) => Ok(()),
+                    Err(mpsc::error::TrySendError::Full(notification)) => {
+                        backlog.push_back(notification);
+                        Ok(())
+                    }
+                    Err(mpsc::error::TrySendError::Closed(notification)) => {
+                        Err(mpsc::error::SendError(notification))
+                    }
+                }
+            }
+        }
+    }
+
+    // Drops the notifications still waiting for room in a bounded channel.
+    fn clear_backlog(&mut self) {
+        if let NotificationSender::Bounded { backlog, .. } = self {
+            backlog.clear();
+        }
+    }
+
+    fn is_backlogged(&self) -> bool {
+        match self {
+            NotificationSender::Unbounded(_) => false,
+            NotificationSender::Bounded { backlog, .. } => !backlog.is_empty(),
+        }
+    }
+
+    // Waits until the whole backlog is in the channel. Nothing is lost if this is cancelled, as a
+    // notification leaves the backlog only once there is room for it.
+    async fn deliver(&mut self) -> Result<(), mpsc::error::ClosedError> {
+        if let NotificationSender::Bounded { tx, backlog } = self {
+            while !backlog.is_empty() {
+                future::poll_fn(|cx| tx.poll_ready(cx)).await?;
+                if let Some(notification) = backlog.pop_front() {
+                    if let Err(e) = tx.try_send(notification) {
+                        backlog.push_front(match e {
+                            mpsc::error::TrySendError::Full(notification) => notification,
+                            mpsc::error::TrySendError::Closed(notification) => notification,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The receiving end of the notifications of a Creator constructed with a [CreatorBuilder],
+/// bounded if the builder was given a capacity.
+pub struct NotificationReceiver<H: HashT>(NotificationReceiverKind<H>);
+
+enum NotificationReceiverKind<H: HashT> {
+    Unbounded(Receiver<NotificationOut<H>>),
+    Bounded(mpsc::Receiver<NotificationOut<H>>),
+}
+
+impl<H: HashT> NotificationReceiver<H> {
+    /// Receives the next notification, or None once the Creator is gone and all the
+    /// notifications were received.
+    pub async fn recv(&mut self) -> Option<NotificationOut<H>> {
+        match &mut self.0 {
+            NotificationReceiverKind::Unbounded(rx) => rx.recv().await,
+            NotificationReceiverKind::Bounded(rx) => rx.recv().await,
+        }
+    }
+
+    /// Receives the next notification if there is one already.
+    pub fn try_recv(&mut self) -> Result<NotificationOut<H>, mpsc::error::TryRecvError> {
+        match &mut self.0 {
+            NotificationReceiverKind::Unbounded(rx) => rx.try_recv(),
+            NotificationReceiverKind::Bounded(rx) => rx.try_recv(),
+        }
+    }
+}
+
+/// Constructs a [Creator] together with its channels, see [CreatorBuilder::build].
+pub struct CreatorBuilder<H: HashT, NI: NodeIdT, Hashing: Hasher<H>> {
+    conf: Config<H, NI>,
+    hashing: Hashing,
+    out_channel_capacity: Option<usize>,
+}
+
+impl<H: HashT, NI: NodeIdT, Hashing: Hasher<H>> CreatorBuilder<H, NI, Hashing> {
+    pub fn new(conf: Config<H, NI>, hashing: Hashing) -> Self {
+        CreatorBuilder {
+            conf,
+            hashing,
+            out_channel_capacity: None,
+        }
+    }
+
+    /// Bounds the channel of the notifications of the Creator to the given capacity, which has
+    /// to be positive, or [CreatorBuilder::build] fails. Once the channel is full, the Creator
+    /// waits for the receiver to catch up before creating its next unit, instead of letting the
+    /// channel grow. By default the channel is unbounded.
+    pub fn out_channel_capacity(mut self, capacity: usize) -> Self {
+        self.out_channel_capacity = Some(capacity);
+        self
+    }
+
+    /// Constructs the Creator, together with the sender through which the units added to the Dag
+    /// are to be passed to it and the receiver of its notifications.
+    #[allow(clippy::type_complexity)]
+    pub fn build(
+        self,
+    ) -> Result<
+        (
+            Creator<H, NI, Hashing>,
+            Sender<Unit<H>>,
+            NotificationReceiver<H>,
+        ),
+        CreatorError,
+    > {
+        let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, new_units_rx) = match self.out_channel_capacity {
+            Some(0) => return Err(CreatorError::ZeroChannelCapacity),
+            Some(capacity) => {
+                let (tx, rx) = mpsc::channel(capacity);
+                (
+                    NotificationSender::Bounded {
+                        tx,
+                        backlog: VecDeque::new(),
+                    },
+                    NotificationReceiverKind::Bounded(rx),
+                )
+            }
+            None => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                (
+                    NotificationSender::Unbounded(tx),
+                    NotificationReceiverKind::Unbounded(rx),
+                )
+            }
+        };
+        let creator = Creator::with_sender(self.conf, parents_rx, new_units_tx, self.hashing)?;
+        Ok((creator, parents_tx, NotificationReceiver(new_units_rx)))
+    }
+}
+
+impl<H: HashT, NI: NodeIdT, F: Fn(&[u8]) -> Result<H, HashError>>
+    Creator<H, NI, FallibleHashing<F>>
+{
+    /// Constructs a Creator with a hashing function that might fail. Creating a unit for which
+    /// hashing fails is skipped and retried the next time the Creator is ready.
+    pub fn new_fallible(
+        conf: Config<H, NI>,
+        parents_rx: Receiver<Unit<H>>,
+        new_units_tx: Sender<NotificationOut<H>>,
+        hashing: F,
+    ) -> Result<Self, CreatorError> {
+        Creator::new(conf, parents_rx, new_units_tx, FallibleHashing(hashing))
+    }
 }
 
-impl<H: HashT, NI: NodeIdT> Creator<H, NI> {
-    pub(crate) fn new(
-        conf: Config<NI>,
+impl<H: HashT, NI: NodeIdT, Hashing: Hasher<H>> Creator<H, NI, Hashing> {
+    pub fn builder(conf: Config<H, NI>, hashing: Hashing) -> CreatorBuilder<H, NI, Hashing> {
+        CreatorBuilder::new(conf, hashing)
+    }
+
+    pub fn new(
+        conf: Config<H, NI>,
         parents_rx: Receiver<Unit<H>>,
         new_units_tx: Sender<NotificationOut<H>>,
-        hashing: impl Fn(&[u8]) -> H + Send + 'static,
-    ) -> Self {
+        hashing: Hashing,
+    ) -> Result<Self, CreatorError> {
+        let new_units_tx = NotificationSender::Unbounded(new_units_tx);
+        Creator::with_sender(conf, parents_rx, new_units_tx, hashing)
+    }
+
+    fn with_sender(
+        conf: Config<H, NI>,
+        parents_rx: Receiver<Unit<H>>,
+        new_units_tx: NotificationSender<H>,
+        hashing: Hashing,
+    ) -> Result<Self, CreatorError> {
         let Config {
             node_id,
             n_members,
-            create_lag,
+            lag_strategy,
+            parent_selector,
+            threshold_policy,
+            max_parents,
+            parent_preference,
+            min_parents,
+            min_parents_grace,
+            weights,
+            stall_timeout,
+            own_unit_timeout,
+            watchdog_interval,
+            watchdog_threshold,
+            progress_log_interval,
+            candidate_ttl,
+            on_created,
+            parent_wait,
+            start_round,
+            max_round,
+            max_round_lead,
+            max_units_per_creator,
+            bootstrap_peers,
+            initial_delay,
+            jitter,
+            rng_seed,
+            session_id,
+            parent_ordering,
+            clock,
+            metrics_sink,
+            graceful_shutdown,
+            data_provider,
+            signer,
+            dry_run,
+            observer,
+            include_parent_list,
+            skip_own_hash_check,
+            log_target,
+            log_label,
         } = conf;
-        Creator {
+        let index = node_id.my_index();
+        // the same checks as in ConfigBuilder::build, for the configs constructed otherwise
+        match index {
+            Some(index) if index.0 >= n_members.0 => {
+                return Err(CreatorError::IndexOutOfRange { index, n_members })
+            }
+            Some(_) => {}
+            None if observer => {}
+            None => return Err(CreatorError::NoIndex),
+        }
+        let mut core = CreatorCore::new(n_members, index, start_round)
+            .with_threshold_policy(threshold_policy)
+            .with_max_round_lead(max_round_lead);
+        if let Some(parent_selector) = parent_selector {
+            core = core.with_parent_selector(parent_selector);
+        }
+        if let Some(max_parents) = max_parents {
+            core = core.with_max_parents(max_parents);
+        }
+        core = core.with_parent_preference(parent_preference);
+        if let Some(weights) = weights {
+            core = core.with_weights(weights);
+        }
+        if let Some(max_units) = max_units_per_creator {
+            core = core.with_max_units_per_creator(max_units);
+        }
+        if let Some(bootstrap_peers) = bootstrap_peers {
+            core = core.with_bootstrap_peers(bootstrap_peers);
+        }
+        if let Some(session_id) = session_id {
+            core = core.with_session_id(session_id);
+        }
+        core = core.with_parent_ordering(parent_ordering);
+        if skip_own_hash_check {
+            core = core.without_own_hash_check();
+        }
+        let (round_tx, round_rx) = watch::channel(start_round);
+        let mut creator = Creator {
             node_id,
             parents_rx,
             new_units_tx,
-            n_members,
-            current_round: 0,
-            candidates_by_round: vec![NodeMap::new_with_len(n_members)],
-            n_candidates_by_round: vec![NodeCount(0)],
-            hashing: Box::new(hashing),
-            create_lag,
+            core,
+            hashing,
+            min_parents,
+            min_parents_grace,
+            create_lag: lag_strategy.initial_lag(),
+            lag_strategy,
+            last_created: None,
+            next_unit_due: None,
+            round_started: clock.now(),
+            ready_noted: false,
+            banned_needed_round: None,
+            stall_timeout,
+            own_unit_timeout,
+            watchdog_interval,
+            watchdog_threshold,
+            progress_log_interval,
+            candidate_ttl,
+            candidates_added: BTreeMap::new(),
+            last_progress: None,
+            on_created,
+            parent_wait,
+            parent_wait_round: None,
+            stats: CreatorStatsHandle::default(),
+            round_tx,
+            round_rx,
+            max_round,
+            initial_delay,
+            jitter,
+            rng: match rng_seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            clock,
+            metrics_sink,
+            graceful_shutdown,
+            data_provider,
+            signer,
+            dry_run_units: if dry_run { Some(Vec::new()) } else { None },
+            observer,
+            include_parent_list,
+            log_target,
+            log_prefix: log_label
+                .map(|label| format!("[{}] ", label))
+                .unwrap_or_default(),
+            trigger_rx: None,
+            #[cfg(feature = "tracing")]
+            round_span: tracing::Span::none(),
+        };
+        creator.start_current_round();
+        Ok(creator)
+    }
+
+    /// Constructs a Creator that continues from the given snapshot, i.e., its next unit will be
+    /// of round `state.current_round()`. The snapshot has to be of a committee of the size in
+    /// the config.
+    pub fn from_snapshot(
+        conf: Config<H, NI>,
+        parents_rx: Receiver<Unit<H>>,
+        new_units_tx: Sender<NotificationOut<H>>,
+        hashing: Hashing,
+        state: CreatorState<H>,
+    ) -> Result<Self, CreatorError> {
+        let mut creator = Creator::new(conf, parents_rx, new_units_tx, hashing)?;
+        creator
+            .core
+            .restore(state)
+            .map_err(CreatorError::Snapshot)?;
+        creator.start_current_round();
+        Ok(creator)
+    }
+
+    /// Takes a snapshot of the current progress, from which the Creator can be restored.
+    pub fn snapshot(&self) -> CreatorState<H> {
+        self.core.snapshot()
+    }
+
+    /// Takes the part of a snapshot at and above the `base` round, for replicating the progress
+    /// with [Creator::apply_delta] without sending full snapshots. A replica stays in sync by
+    /// passing the round preceding its current one as the base, as the candidates of that round
+    /// may still change, while the older rounds cannot.
+    pub fn snapshot_since(&self, base: Round) -> CreatorDelta<H> {
+        self.core.snapshot_since(base)
+    }
+
+    /// Catches up with the progress taken with [Creator::snapshot_since]. A delta whose base is
+    /// after our current round is rejected, as the rounds in between were missed and a full
+    /// snapshot is needed, and so is one older than our progress.
+    pub fn apply_delta(&mut self, delta: CreatorDelta<H>) -> Result<(), DeltaError> {
+        let round = self.current_round();
+        self.core.apply_delta(delta)?;
+        if self.current_round() != round {
+            self.start_current_round();
         }
+        Ok(())
     }
 
-    // initializes the vectors corresponding to the given round (and all between if not there)
-    fn init_round(&mut self, round: Round) {
-        while self.candidates_by_round.len() <= round {
-            self.candidates_by_round
-                .push(NodeMap::new_with_len(self.n_members));
-            self.n_candidates_by_round.push(NodeCount(0));
+    /// Switches to a committee of `n_members` members in which we have the given index, e.g., at
+    /// an epoch transition. This is only possible at a round boundary, i.e., right after a unit
+    /// is created and before any candidates for the parents of the next unit arrive. All the
+    /// candidates received afterwards are interpreted in the new committee.
+    pub fn reconfigure(
+        &mut self,
+        n_members: NodeCount,
+        index: NodeIndex,
+    ) -> Result<(), ReconfigureError> {
+        self.core.reconfigure(n_members, index)?;
+        self.candidates_added.clear();
+        debug!(target: &self.log_target, "{}{} Reconfigured to index {} of {} members at round {}.", self.log_prefix, self.node_id, index, n_members, self.current_round());
+        Ok(())
+    }
+
+    /// Returns to the state of a fresh Creator starting a new session at round 0, with the same
+    /// committee and channels: all the candidates and bans are forgotten, the units still waiting
+    /// in the parents channel and the notifications still waiting for room in a bounded channel
+    /// are dropped as belonging to the old session, and the counters are cleared, also for the
+    /// existing [CreatorStatsHandle]s. As [Creator::create] holds the Creator
+    /// mutably borrowed, the reset can only happen before or after it runs, never in the middle of
+    /// creating a unit.
+    pub fn reset(&mut self) {
+        debug!(target: &self.log_target, "{}{} Reset at round {}.", self.log_prefix, self.node_id, self.current_round());
+        while self.parents_rx.try_recv().is_ok() {}
+        self.core.reset();
+        self.candidates_added.clear();
+        self.create_lag = self.lag_strategy.initial_lag();
+        self.last_created = None;
+        self.next_unit_due = None;
+        self.last_progress = None;
+        self.parent_wait_round = None;
+        self.banned_needed_round = None;
+        self.new_units_tx.clear_backlog();
+        if let Some(dry_run_units) = &mut self.dry_run_units {
+            dry_run_units.clear();
         }
+        self.stats.clear();
+        self.start_current_round();
     }
 
-    fn create_unit(&mut self) {
-        let round = self.current_round;
-        let parents = {
-            if round == 0 {
-                NodeMap::new_with_len(self.n_members)
-            } else {
-                self.candidates_by_round[round - 1].clone()
+    /// Makes [Creator::create] attempt to create a unit whenever `()` is sent through the given
+    /// channel, e.g., when the application has new data to include. If the Creator is ready, the
+    /// unit is created right away, also in the middle of waiting for the lag or for additional
+    /// parents. Otherwise the trigger is ignored.
+    pub fn with_trigger(mut self, trigger_rx: Receiver<()>) -> Self {
+        self.trigger_rx = Some(trigger_rx);
+        self
+    }
+
+    /// Keeps the candidates for parents in the given store instead of in memory, e.g., for
+    /// committees too large for that. The candidates collected so far, e.g., restored from a
+    /// snapshot, are moved into it.
+    pub fn with_candidate_store(mut self, store: impl CandidateStore<H> + 'static) -> Self {
+        self.core.set_candidate_store(Box::new(store));
+        self
+    }
+
+    /// Returns a handle through which the counters of this Creator can be read, also after
+    /// [Creator::create] was started.
+    pub fn stats_handle(&self) -> CreatorStatsHandle {
+        self.stats.clone()
+    }
+
+    /// Returns a handle through which the current round of this Creator can be read, also after
+    /// [Creator::create] was started.
+    pub fn round_watch(&self) -> RoundWatch {
+        RoundWatch(self.stats.0.clone())
+    }
+
+    pub fn stats(&self) -> CreatorStats {
+        self.stats.stats()
+    }
+
+    /// Returns a future resolving once the Creator is past the given round, i.e., once it created
+    /// (or skipped) its unit of that round, e.g., to wait for some progress after [Creator::create]
+    /// was started. The future never resolves if the Creator is dropped before that.
+    pub fn reached(&self, round: Round) -> impl Future<Output = ()> + Send + 'static {
+        let mut round_rx = self.round_rx.clone();
+        async move {
+            while let Some(current_round) = round_rx.recv().await {
+                if current_round > round {
+                    return;
+                }
             }
+            future::pending().await
+        }
+    }
+
+    // The round of our next unit.
+    fn current_round(&self) -> Round {
+        self.core.current_round()
+    }
+
+    // Called whenever the core moves to a new current round, or starts at one.
+    fn start_current_round(&mut self) {
+        let round = self.current_round();
+        self.stats
+            .0
+            .current_round
+            .store(round.0 as u64, Ordering::Relaxed);
+        // there is always a receiver, the one kept by us
+        let _ = self.round_tx.broadcast(round);
+        // replacing the span of the previous round closes it
+        #[cfg(feature = "tracing")]
+        {
+            self.round_span = tracing::debug_span!(
+                target: DEFAULT_LOG_TARGET,
+                "round",
+                round = round.0,
+                node_index = self.core.index().map(|index| index.0),
+                parent_count = tracing::field::Empty,
+            );
+        }
+        self.round_started = self.clock.now();
+        self.ready_noted = false;
+        self.note_readiness();
+        self.note_banned_parents_needed();
+    }
+
+    // Records how long it took until we were ready to create the unit of the current round, the
+    // first time we are.
+    fn note_readiness(&mut self) {
+        if self.ready_noted || !self.check_ready() {
+            return;
+        }
+        self.ready_noted = true;
+        self.stats
+            .record_time_to_ready(self.current_round(), self.clock.now() - self.round_started);
+    }
+
+    // Returns whether the unit was created and sent out, in which case the current round is
+    // advanced. Otherwise, creating the unit of this round can be retried later.
+    fn create_unit(&mut self) -> Result<bool, CreatorError> {
+        if self.session_complete() {
+            return Ok(false);
+        }
+        let own_index = match self.core.index() {
+            Some(index) => index,
+            None => return Ok(false),
         };
+        let new_unit = match self.core.parents() {
+            Some(parents) => self.new_unit(own_index, &parents),
+            None => return Ok(false),
+        };
+        match new_unit {
+            Ok((new_preunit, n_parents, parent_list)) => {
+                self.emit_unit(new_preunit, own_index, n_parents, parent_list)?;
+                Ok(true)
+            }
+            Err(e) => {
+                error!(target: &self.log_target, "{}{} Unable to create a unit at round {}: {}.", self.log_prefix, self.node_id, self.current_round(), e);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Creates our unit of the current round with exactly the given parents, instead of the
+    /// candidates chosen by the Creator, e.g., to reproduce a specific shape of the Dag in tests
+    /// or to replay a session. The parents need to satisfy the same constraints as the chosen
+    /// ones, i.e., the threshold and the presence of our own unit, but not to be candidates. The
+    /// unit is then sent out like any other, the current round advances, and the unit is
+    /// returned. Invalid parents are rejected with the Creator left unchanged.
+    pub fn create_with_parents(
+        &mut self,
+        parents: NodeMap<Option<H>>,
+    ) -> Result<PreUnit<H>, CreatorError> {
+        self.create_with(parents, true)
+    }
+
+    /// Like [Creator::create_with_parents], but without checking the parents at all, so that
+    /// invalid units can be created on purpose, e.g., to test how the others handle them.
+    pub fn force_create_with_parents(
+        &mut self,
+        parents: NodeMap<Option<H>>,
+    ) -> Result<PreUnit<H>, CreatorError> {
+        self.create_with(parents, false)
+    }
+
+    /// Creates our unit of the current round with whatever candidates there are for its parents,
+    /// even too few of them and without our own unit of the previous round, and moves on to the
+    /// next round. The unit is sent out like any other, followed by
+    /// [NotificationOut::ForcedUnit] to mark it as forced.
+    ///
+    /// This breaks the safety guarantees of the protocol: the unit does not prove that enough
+    /// members reached the previous round, and the others are right to reject it. It is an
+    /// escape hatch for recovery only, e.g., for a Creator stuck at a round that cannot be
+    /// satisfied anymore, as the members it depended on are gone for good.
+    pub fn force_advance(&mut self) -> Result<PreUnit<H>, CreatorError> {
+        let parents = self.core.available_parents();
+        let preunit = self.create_with(parents, false)?;
+        let (round, n_parents) = (preunit.round(), preunit.n_parents());
+        warn!(target: &self.log_target, "{}{} Forced a unit of round {} with {} parents.", self.log_prefix, self.node_id, round, n_parents);
+        let send_result = self
+            .new_units_tx
+            .send(NotificationOut::ForcedUnit { round, n_parents });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send a forced unit notification: {:?}.", self.log_prefix, self.node_id, e);
+            return Err(CreatorError::ChannelClosed);
+        }
+        Ok(preunit)
+    }
+
+    fn create_with(
+        &mut self,
+        parents: NodeMap<Option<H>>,
+        validate: bool,
+    ) -> Result<PreUnit<H>, CreatorError> {
+        let own_index = self.core.index().ok_or(CreatorError::NoIndex)?;
+        if validate {
+            let n_members = self.core.n_members();
+            if parents.len() != n_members.as_usize() {
+                return Err(CreatorError::ParentsLength {
+                    len: NodeCount::from_usize(parents.len()),
+                    n_members,
+                });
+            }
+            let readiness = self.core.check_parents(&parents);
+            if !readiness.is_ready() {
+                return Err(CreatorError::InvalidParents(readiness));
+            }
+        }
+        let (new_preunit, n_parents, parent_list) = self
+            .new_unit(own_index, &parents)
+            .map_err(CreatorError::Hashing)?;
+        debug!(target: &self.log_target, "{}{} Creating a unit of round {} with the given parents.", self.log_prefix, self.node_id, self.current_round());
+        self.emit_unit(new_preunit.clone(), own_index, n_parents, parent_list)?;
+        Ok(new_preunit)
+    }
 
-        let new_preunit = PreUnit::new_from_parents(
-            self.node_id.my_index().unwrap(),
+    // Our unit of the current round with the given parents, together with the number of the
+    // parents and their list if it is to be sent out.
+    #[allow(clippy::type_complexity)]
+    fn new_unit(
+        &self,
+        own_index: NodeIndex,
+        parents: &NodeMap<Option<H>>,
+    ) -> Result<(PreUnit<H>, NodeCount, Option<Vec<(NodeIndex, H)>>), HashError> {
+        let round = self.current_round();
+        let n_parents = parents.count_present();
+        let parent_list = match self.include_parent_list {
+            true => Some(parents.present().map(|(pid, hash)| (pid, *hash)).collect()),
+            false => None,
+        };
+        let preunit = PreUnit::try_new_from_parents(
+            own_index,
             round,
             parents,
-            &self.hashing,
-        );
-        debug!(target: "rush-creator", "{} Created a new unit {:?} at round {}.", self.node_id, new_preunit, self.current_round);
-        let send_result = self.new_units_tx.send(new_preunit.into());
+            self.core.session_id(),
+            self.core.parent_ordering(),
+            &CatchUnwind(&self.hashing),
+        )?;
+        #[cfg(feature = "tracing")]
+        self.round_span.record("parent_count", n_parents.0);
+        let preunit = match &self.data_provider {
+            Some(data_provider) => preunit.with_data((*data_provider.lock())(round)),
+            None => preunit,
+        };
+        Ok((preunit, n_parents, parent_list))
+    }
+
+    // Sends out our unit of the current round and moves on to the next round.
+    fn emit_unit(
+        &mut self,
+        new_preunit: PreUnit<H>,
+        own_index: NodeIndex,
+        n_parents: NodeCount,
+        parent_list: Option<Vec<(NodeIndex, H)>>,
+    ) -> Result<(), CreatorError> {
+        let round = self.current_round();
+        debug!(target: &self.log_target, "{}{} Created a new unit {:?} at round {}.", self.log_prefix, self.node_id, new_preunit, self.current_round());
+        if let Some(on_created) = &self.on_created {
+            on_created(&new_preunit);
+        }
+        self.metrics_sink.unit_created(round, n_parents);
+        let own_hash =
+            new_preunit.using_encoded(|bytes| CatchUnwind(&self.hashing).try_hash(bytes));
+        self.send_unit(new_preunit, own_index)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(target: DEFAULT_LOG_TARGET, parent: &self.round_span, "unit sent");
+        if let Some(parents) = parent_list {
+            self.send_parent_list(round, parents)?;
+        }
+
+        CreatorStatsHandle::increment(&self.stats.0.created);
+        self.last_progress = Some(self.clock.now());
+        self.stats.record_parents(n_parents);
+        self.core.unit_created(own_hash.ok());
+        self.start_current_round();
+        Ok(())
+    }
+
+    fn send_unit(&mut self, preunit: PreUnit<H>, own_index: NodeIndex) -> Result<(), CreatorError> {
+        if let Some(dry_run_units) = &mut self.dry_run_units {
+            dry_run_units.push(preunit);
+            return Ok(());
+        }
+        let notification = match &self.signer {
+            Some(signer) => NotificationOut::SignedUnit {
+                signature: signer.sign(&preunit.encode()),
+                preunit,
+                creator: own_index,
+            },
+            None => preunit.into(),
+        };
+        let send_result = self.new_units_tx.send(notification);
         if let Err(e) = send_result {
-            error!(target: "rush-creator", "{:?} Unable to send a newly created unit: {:?}.", self.node_id, e);
+            error!(target: &self.log_target, "{}{} Unable to send a newly created unit: {:?}.", self.log_prefix, self.node_id, e);
+            return Err(CreatorError::ChannelClosed);
         }
+        Ok(())
+    }
+
+    // Lists the parents of our unit of the given round, see the include_parent_list option.
+    // Nothing is sent in the dry run mode, just like the unit itself.
+    fn send_parent_list(
+        &mut self,
+        round: Round,
+        parents: Vec<(NodeIndex, H)>,
+    ) -> Result<(), CreatorError> {
+        if self.dry_run_units.is_some() {
+            return Ok(());
+        }
+        let send_result = self
+            .new_units_tx
+            .send(NotificationOut::CreatedUnitParents { round, parents });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send the parents of a newly created unit: {:?}.", self.log_prefix, self.node_id, e);
+            return Err(CreatorError::ChannelClosed);
+        }
+        Ok(())
+    }
+
+    /// Passes the units obtained in response to a [NotificationOut::CatchupRequest], which are
+    /// added like any other batch, see [Creator::add_units]. Returns the number of units created
+    /// thanks to them.
+    pub fn apply_catchup_response(&mut self, units: Vec<Unit<H>>) -> usize {
+        debug!(target: &self.log_target, "{}{} Applying a catch-up response of {} units at round {}.", self.log_prefix, self.node_id, units.len(), self.current_round());
+        self.add_units(units)
+    }
 
-        self.current_round += 1;
-        self.init_round(self.current_round);
+    /// Returns the units created so far in the dry run mode, in the order of creation, or None if
+    /// the Creator is not in the dry run mode.
+    pub fn dry_run_units(&self) -> Option<&[PreUnit<H>]> {
+        self.dry_run_units.as_deref()
     }
 
-    fn add_unit(&mut self, round: Round, pid: NodeIndex, hash: H) {
-        // units that are too old are of no interest to us
-        if round + 1 >= self.current_round {
-            self.init_round(round);
-            if self.candidates_by_round[round][pid].is_none() {
-                // passing the check above means that we do not have any unit for the pair (round, pid) yet
-                self.candidates_by_round[round][pid] = Some(hash);
-                self.n_candidates_by_round[round] += NodeCount(1);
+    /// Adds a batch of parent candidates at once, e.g., the units obtained during a catch-up, and
+    /// then creates as many units as the candidates allow, back-to-back. Units that are too old or
+    /// already known are ignored, exactly as when they arrive through the parents channel. Returns
+    /// the number of created units.
+    pub fn add_units(&mut self, units: impl IntoIterator<Item = Unit<H>>) -> usize {
+        for u in units {
+            self.add_unit(u.round(), u.creator(), u.hash());
+        }
+        let mut n_created = 0;
+        while self.check_ready() {
+            match self.create_unit() {
+                Ok(true) => n_created += 1,
+                Ok(false) | Err(_) => break,
             }
         }
+        n_created
     }
 
-    fn check_ready(&self) -> bool {
-        if self.current_round == 0 {
-            return true;
+    /// Applies the given events in order, synchronously and regardless of the lag and the timers,
+    /// e.g., to replay the inputs of a session or to feed the Creator from a fuzzer. Returns the
+    /// number of created units, which are sent out as usual.
+    pub fn apply_events(&mut self, events: &[CreatorEvent<H>]) -> usize {
+        let mut n_created = 0;
+        for event in events {
+            match event {
+                CreatorEvent::AddUnit { round, pid, hash } => {
+                    self.add_unit(*round, *pid, *hash);
+                }
+                CreatorEvent::TryCreate => {
+                    if let Ok(true) = self.try_create_unit() {
+                        n_created += 1;
+                    }
+                }
+            }
         }
-        // To create a new unit, we need to have at least >floor(2*N/3) parents available in previous round.
-        // Additionally, our unit from previous round must be available.
-        let prev_round = self.current_round - 1;
-        let threshold = (self.n_members * 2) / 3;
+        n_created
+    }
 
-        self.n_candidates_by_round[prev_round] > threshold
-            && self.candidates_by_round[prev_round][self.node_id.my_index().unwrap()].is_some()
+    fn try_create_unit(&mut self) -> Result<bool, CreatorError> {
+        if self.check_ready() {
+            self.create_unit()
+        } else {
+            Ok(false)
+        }
     }
 
-    pub(crate) async fn create(&mut self, exit: oneshot::Receiver<()>) {
-        self.create_unit();
-        let mut exit = exit.into_stream();
-        loop {
-            tokio::select! {
-                Some(u) = self.parents_rx.recv() => {
-                    self.add_unit(u.round(), u.creator(), u.hash());
-                    if self.check_ready() {
-                        self.create_unit();
-                        delay_for(self.create_lag).await;
-                    }
+    // Returns whether the unit became a new candidate.
+    fn add_unit(&mut self, round: Round, pid: NodeIndex, hash: H) -> bool {
+        CreatorStatsHandle::increment(&self.stats.0.candidates_seen);
+        self.metrics_sink.candidate_received();
+        match self.core.add_unit(round, pid, hash) {
+            AddOutcome::Added => {
+                if self.candidate_ttl.is_some() {
+                    self.candidates_added.insert((round, pid), self.clock.now());
                 }
-                _ = exit.next() => {
-                    debug!(target: "rush-creator", "{} received exit signal.", self.node_id);
-                    break
+                if round.next() == self.current_round() {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(target: DEFAULT_LOG_TARGET, parent: &self.round_span, creator = pid.0, "parent candidate");
+                    self.note_readiness();
+                    self.note_banned_parents_needed();
                 }
+                true
+            }
+            AddOutcome::UnknownCreator => {
+                debug!(target: &self.log_target, "{}{} Ignoring a unit {} by an unknown creator {}.", self.log_prefix, self.node_id, hash, pid);
+                false
+            }
+            AddOutcome::TooFarAhead => {
+                debug!(target: &self.log_target, "{}{} Ignoring a unit {} of round {} too far ahead of round {}.", self.log_prefix, self.node_id, hash, round, self.current_round());
+                false
+            }
+            AddOutcome::RateLimited => {
+                debug!(target: &self.log_target, "{}{} Dropping a unit {} of round {} by {}, who sent too many units at round {}.", self.log_prefix, self.node_id, hash, round, pid, self.current_round());
+                false
             }
+            AddOutcome::Stale => {
+                CreatorStatsHandle::increment(&self.stats.0.candidates_stale);
+                self.metrics_sink.candidate_stale();
+                false
+            }
+            AddOutcome::Fork { first } => {
+                self.report_fork(round, pid, first, hash);
+                false
+            }
+            AddOutcome::Impostor { expected } => {
+                self.report_impostor(round, expected, hash);
+                false
+            }
+            AddOutcome::Known => false,
+        }
+    }
+
+    fn report_impostor(&mut self, round: Round, expected: H, received: H) {
+        error!(target: &self.log_target, "{}{} Received a unit {} impersonating our unit {} of round {}.", self.log_prefix, self.node_id, received, expected, round);
+        let send_result = self.new_units_tx.send(NotificationOut::ImpostorUnit {
+            round,
+            expected,
+            received,
+        });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send an impostor notification: {:?}.", self.log_prefix, self.node_id, e);
         }
     }
+
+    /// Never chooses the units of the given member as parents from now on, e.g., once it was seen
+    /// forking. If the threshold cannot be met without them, the Creator waits for more parents
+    /// and sends [NotificationOut::BannedParentsNeeded]. Our own index and indices outside the
+    /// committee cannot be banned.
+    pub fn ban_node(&mut self, idx: NodeIndex) {
+        if !self.core.ban(idx) {
+            warn!(target: &self.log_target, "{}{} Not banning node {}, either ourselves or not a member.", self.log_prefix, self.node_id, idx);
+            return;
+        }
+        debug!(target: &self.log_target, "{}{} Banned node {}.", self.log_prefix, self.node_id, idx);
+        self.note_banned_parents_needed();
+    }
+
+    // Reports, once per round, that only the units of banned members would let us create the unit
+    // of the current round.
+    fn note_banned_parents_needed(&mut self) {
+        let round = self.current_round();
+        if self.banned_needed_round == Some(round)
+            || !self
+                .core
+                .needs_banned_parents(round, self.needed_parents(round))
+        {
+            return;
+        }
+        self.banned_needed_round = Some(round);
+        debug!(target: &self.log_target, "{}{} Enough parents at round {} only with banned nodes.", self.log_prefix, self.node_id, round);
+        let send_result = self
+            .new_units_tx
+            .send(NotificationOut::BannedParentsNeeded { round });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send a banned parents notification: {:?}.", self.log_prefix, self.node_id, e);
+        }
+    }
+
+    fn report_fork(&mut self, round: Round, creator: NodeIndex, hash_a: H, hash_b: H) {
+        debug!(target: &self.log_target, "{}{} Fork by {} at round {}: {} and {}.", self.log_prefix, self.node_id, creator, round, hash_a, hash_b);
+        let send_result = self.new_units_tx.send(NotificationOut::ForkDetected {
+            round,
+            creator,
+            hash_a,
+            hash_b,
+        });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send a fork notification: {:?}.", self.log_prefix, self.node_id, e);
+        }
+    }
+
+    /// Tells whether the candidates collected so far suffice for creating a unit of the given
+    /// round, and if not, why. Rounds whose candidates are not kept anymore are reported as not
+    /// having any parents.
+    pub fn can_create(&self, round: Round) -> CreateReadiness {
+        // the threshold may be raised to min_parents for a while
+        self.core.readiness(round, self.needed_parents(round))
+    }
+
+    /// Sums up whether the Creator is healthy, e.g., for a health check endpoint. Waiting for the
+    /// parents of the next unit counts as starving only after the stall timeout, or after the
+    /// create lag if no stall timeout is configured, and waiting for our own unit only after the
+    /// own unit timeout if configured, like for the other parents otherwise. An observer, or a
+    /// Creator past its last round, is always healthy unless backpressured.
+    pub fn health(&self) -> CreatorHealth {
+        if self.new_units_tx.is_backlogged() {
+            return CreatorHealth::SendBackpressured;
+        }
+        if self.observer || self.session_complete() {
+            return CreatorHealth::Healthy;
+        }
+        let waiting = self
+            .clock
+            .now()
+            .saturating_duration_since(self.round_started);
+        let starving_after = self.stall_timeout.unwrap_or(self.create_lag);
+        match self.can_create(self.current_round()) {
+            CreateReadiness::Ready => CreatorHealth::Healthy,
+            CreateReadiness::MissingOwnParent => match self.current_round().prev() {
+                Some(round) if waiting >= self.own_unit_timeout.unwrap_or(starving_after) => {
+                    CreatorHealth::OwnUnitMissing { round }
+                }
+                _ => CreatorHealth::Healthy,
+            },
+            CreateReadiness::NotEnoughParents { have, need } if waiting >= starving_after => {
+                CreatorHealth::ParentStarved { have, need }
+            }
+            CreateReadiness::NotEnoughWeight { .. } if waiting >= starving_after => {
+                let (have, need) = self.parents_progress();
+                CreatorHealth::ParentStarved { have, need }
+            }
+            _ => CreatorHealth::Healthy,
+        }
+    }
+
+    /// Returns the indices of the nodes whose units of the given round were not received yet,
+    /// e.g., to request exactly these units from other nodes. Rounds that are not tracked, i.e.,
+    /// pruned ones or ones without any candidates yet, yield no indices.
+    pub fn missing_parents(&self, round: Round) -> Vec<NodeIndex> {
+        self.core.missing_parents(round)
+    }
+
+    /// Tells whether a unit of the given round by the given creator is already a candidate, e.g.,
+    /// to avoid sending it to the Creator again. Rounds that are not kept anymore, or not yet,
+    /// have no candidates.
+    pub fn has_candidate(&self, round: Round, pid: NodeIndex) -> bool {
+        self.core.candidate(round, pid).is_some()
+    }
+
+    /// Tells whether the unit with the given hash is the candidate of its round and creator. A
+    /// different unit of the same round and creator, i.e., a fork, does not count.
+    pub fn has_exact(&self, round: Round, pid: NodeIndex, hash: &H) -> bool {
+        self.core.candidate(round, pid) == Some(*hash)
+    }
+
+    /// Returns a snapshot of the candidates of all the rounds currently kept by the Creator, e.g.,
+    /// to render which nodes contributed to which rounds. Pruned rounds are not included.
+    pub fn frontier(&self) -> BTreeMap<Round, NodeMap<Option<H>>> {
+        self.core.frontier()
+    }
+
+    /// The round and hash of the last unit created by the Creator, i.e., the tip of its own
+    /// units in the DAG, or `None` before it creates any. Units whose hash could not be
+    /// computed are not tracked.
+    pub fn own_head(&self) -> Option<(Round, H)> {
+        self.core.own_head()
+    }
+
+    fn check_ready(&self) -> bool {
+        self.can_create(self.current_round()).is_ready()
+    }
+
+    // Returns how long to wait for additional parents before creating the unit of the current
+    // round, unless we already waited at this round.
+    fn start_parent_wait(&mut self) -> Option<Duration> {
+        let parent_wait = self.parent_wait?;
+        if self.current_round() == Round(0) || self.parent_wait_round == Some(self.current_round())
+        {
+            return None;
+        }
+        self.parent_wait_round = Some(self.current_round());
+        Some(parent_wait)
+    }
+
+    // Creates a unit if ready, after which the create_lag is waited for by the caller. If the
+    // units already waiting in the channel let us create the next units right away, we are behind
+    // the others, so these are created back-to-back and the create_lag is waited for only once we
+    // are caught up. Nothing is created while some notifications wait for room in a bounded
+    // channel, the run loop delivers them first and then tries again.
+    fn create_unit_and_catch_up(&mut self) -> Result<bool, CreatorError> {
+        if self.new_units_tx.is_backlogged() {
+            return Ok(false);
+        }
+        let created = self.try_create_unit()?;
+        if created {
+            self.update_lag(self.clock.now());
+            loop {
+                self.add_waiting_units();
+                if self.session_complete()
+                    || self.new_units_tx.is_backlogged()
+                    || !self.check_ready()
+                {
+                    break;
+                }
+                debug!(target: &self.log_target, "{}{} Catching up at round {}.", self.log_prefix, self.node_id, self.current_round());
+                // the unit could not be hashed, it is retried later rather than spinning here
+                if !self.create_unit()? {
+                    break;
+                }
+                // the lag strategy should not learn from the intervals between these units
+                self.last_created = Some(self.clock.now());
+            }
+        }
+        Ok(created)
+    }
+
+    // Records for the round watch whether we are ready, but wait for the create_lag.
+    fn note_lag_blocked(&self, lagging: bool) {
+        let blocked = lagging && self.check_ready();
+        self.stats.0.lag_blocked.store(blocked, Ordering::Relaxed);
+    }
+
+    // Whether the unit of the max_round, if any, was already created.
+    fn session_complete(&self) -> bool {
+        matches!(self.max_round, Some(max_round) if self.current_round() > max_round)
+    }
+
+    fn complete_session(&mut self) -> Result<(), CreatorError> {
+        let last_round = match self.max_round {
+            Some(max_round) => max_round,
+            None => return Ok(()),
+        };
+        debug!(target: &self.log_target, "{}{} Session complete at round {}.", self.log_prefix, self.node_id, last_round);
+        let send_result = self
+            .new_units_tx
+            .send(NotificationOut::SessionComplete { last_round });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send a session complete notification: {:?}.", self.log_prefix, self.node_id, e);
+            return Err(CreatorError::ChannelClosed);
+        }
+        Ok(())
+    }
+
+    // The number of parents needed for a unit of the given round: min_parents (if set) until its
+    // grace period since the round became the current one is over, the threshold afterwards.
+    fn needed_parents(&self, round: Round) -> NodeCount {
+        let required = self.core.required_parents();
+        let min_parents = match self.min_parents {
+            Some(min_parents) => min_parents.min(self.core.n_members()),
+            None => return required,
+        };
+        let grace_over = round <= self.current_round()
+            && self.clock.now() >= self.round_started + self.min_parents_grace;
+        match grace_over {
+            true => required,
+            false => required.max(min_parents),
+        }
+    }
+
+    // How long until the min_parents grace period of the current round is over, if only the lack
+    // of min_parents parents keeps us from creating the unit.
+    fn min_parents_grace_left(&self) -> Option<Duration> {
+        self.min_parents?;
+        self.current_round().prev()?;
+        if self.check_ready() || !self.core.is_ready() {
+            return None;
+        }
+        let grace_over_at = self.round_started + self.min_parents_grace;
+        Some(grace_over_at.saturating_duration_since(self.clock.now()))
+    }
+
+    // The number of candidates for parents of the current round and the number needed.
+    fn parents_progress(&self) -> (NodeCount, NodeCount) {
+        let have_parents = match self.current_round().prev() {
+            None => NodeCount(0),
+            Some(prev_round) => self.core.n_candidates(prev_round),
+        };
+        (have_parents, self.core.required_parents())
+    }
+
+    fn report_own_unit_missing(&mut self) -> Result<(), CreatorError> {
+        let round = match self.current_round().prev() {
+            Some(round) => round,
+            None => return Ok(()),
+        };
+        warn!(target: &self.log_target, "{}{} Our own unit of round {} is missing.", self.log_prefix, self.node_id, round);
+        let send_result = self
+            .new_units_tx
+            .send(NotificationOut::OwnUnitMissing { round });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send an own unit missing notification: {:?}.", self.log_prefix, self.node_id, e);
+            return Err(CreatorError::ChannelClosed);
+        }
+        Ok(())
+    }
+
+    fn stalled_notification(&self) -> NotificationOut<H> {
+        let (have_parents, needed) = self.parents_progress();
+        NotificationOut::CreatorStalled {
+            round: self.current_round(),
+            have_parents,
+            needed,
+        }
+    }
+
+    // Asks for the units missing for our unit of the current round, see
+    // NotificationOut::CatchupRequest. At round 0 these are the units of round 0 itself, awaited
+    // with bootstrap_peers.
+    fn request_catchup(&mut self) -> Result<(), CreatorError> {
+        let round = self.current_round().prev().unwrap_or(Round(0));
+        let missing = self.missing_parents(round);
+        if missing.is_empty() {
+            return Ok(());
+        }
+        debug!(target: &self.log_target, "{}{} Requesting {} missing units of round {}.", self.log_prefix, self.node_id, missing.len(), round);
+        let send_result = self
+            .new_units_tx
+            .send(NotificationOut::CatchupRequest { round, missing });
+        if let Err(e) = send_result {
+            error!(target: &self.log_target, "{}{} Unable to send a catch-up request: {:?}.", self.log_prefix, self.node_id, e);
+            return Err(CreatorError::ChannelClosed);
+        }
+        Ok(())
+    }
+
+    // Warns if no unit was created for longer than the watchdog threshold.
+    fn check_progress(&self) {
+        let last_progress = match self.last_progress {
+            Some(last_progress) => last_progress,
+            None => return,
+        };
+        let elapsed = self.clock.now() - last_progress;
+        if elapsed <= self.watchdog_threshold {
+            return;
+        }
+        CreatorStatsHandle::increment(&self.stats.0.watchdog_warnings);
+        let (have_parents, needed) = self.parents_progress();
+        warn!(target: &self.log_target, "{}{} No unit created for {:?}, stuck at round {} with {} out of {} needed parents.", self.log_prefix, self.node_id, elapsed, self.current_round(), have_parents, needed);
+    }
+
+    // Drops the candidates older than the candidate_ttl, and returns how long until the next
+    // candidate expires, or the whole ttl if none is kept.
+    fn evict_expired_candidates(&mut self) -> Duration {
+        let ttl = match self.candidate_ttl {
+            Some(ttl) => ttl,
+            None => return Duration::default(),
+        };
+        let now = self.clock.now();
+        let core = &mut self.core;
+        let (log_target, log_prefix, node_id) = (&self.log_target, &self.log_prefix, &self.node_id);
+        self.candidates_added.retain(|(round, pid), added| {
+            // the pruned rounds and the candidates replaced since are forgotten along the way
+            if core.candidate(*round, *pid).is_none() {
+                return false;
+            }
+            if now - *added < ttl {
+                return true;
+            }
+            if core.evict(*round, *pid) {
+                debug!(target: log_target, "{}{} Evicted the candidate of round {} by {} unused for {:?}.", log_prefix, node_id, round, pid, now - *added);
+            }
+            false
+        });
+        self.candidates_added
+            .values()
+            .map(|added| ttl - (now - *added))
+            .min()
+            .unwrap_or(ttl)
+    }
+
+    fn log_progress(&self) {
+        CreatorStatsHandle::increment(&self.stats.0.progress_logs);
+        let (have_parents, _) = self.parents_progress();
+        debug!(target: &self.log_target, "{}{} At round {} with {} out of {} candidates, ready: {}.", self.log_prefix, self.node_id, self.current_round(), have_parents, self.core.n_members(), self.check_ready());
+    }
+
+    // The time to wait after creating a unit, including the jitter. At a fixed rate it is the
+    // time remaining until the next unit is due, which is a period after the previous one was due.
+    fn lag_until_next_unit(&mut self, now: Instant) -> Duration {
+        let lag = match self.lag_strategy {
+            LagStrategy::FixedRate(period) => {
+                let due = self.next_unit_due.unwrap_or(now);
+                let next_due = (due + period).max(now);
+                self.next_unit_due = Some(next_due);
+                next_due - now
+            }
+            _ => self.create_lag,
+        };
+        lag + self.sample_jitter()
+    }
+
+    fn sample_jitter(&mut self) -> Duration {
+        if self.jitter == Duration::default() {
+            return Duration::default();
+        }
+        let max_nanos = self.jitter.as_nanos().min(u64::MAX as u128) as u64;
+        Duration::from_nanos(self.rng.gen_range(0..=max_nanos))
+    }
+
+    // Updates the create_lag after creating the unit of the previous round, based on the time
+    // elapsed since the unit created before it.
+    fn update_lag(&mut self, now: Instant) {
+        let interval = self.last_created.map(|last_created| now - last_created);
+        self.create_lag = self.lag_strategy.next_lag(
+            self.current_round().saturating_sub(1),
+            self.create_lag,
+            interval,
+        );
+        self.last_created = Some(now);
+    }
+
+    // Adds the units waiting in the channel as candidates.
+    fn add_waiting_units(&mut self) {
+        while let Ok(u) = self.parents_rx.try_recv() {
+            self.add_unit(u.round(), u.creator(), u.hash());
+        }
+    }
+
+    // An observer has no units of its own, so it moves past a round as soon as the round has
+    // enough units for the other members to build on.
+    fn follow_rounds(&mut self) {
+        if self.core.follow_rounds() {
+            self.start_current_round();
+        }
+    }
+
+    fn advance_round(&mut self) {
+        self.core.advance_round();
+        self.start_current_round();
+    }
+
+    async fn observe(&mut self, exit: oneshot::Receiver<()>) {
+        let mut exit = exit.into_stream();
+        loop {
+            tokio::select! {
+                u = self.parents_rx.recv() => match u {
+                    Some(u) => {
+                        if self.add_unit(u.round(), u.creator(), u.hash()) {
+                            self.follow_rounds();
+                        }
+                    }
+                    None => {
+                        debug!(target: &self.log_target, "{}{} Parents channel closed.", self.log_prefix, self.node_id);
+                        break;
+                    }
+                },
+                _ = exit.next() => {
+                    debug!(target: &self.log_target, "{}{} received exit signal.", self.log_prefix, self.node_id);
+                    self.terminate();
+                    break;
+                }
+            }
+        }
+    }
+
+    // Adds the units waiting in the channel as candidates and creates a unit if ready. Used
+    // for a graceful shutdown, so that no progress already possible gets lost.
+    fn flush(&mut self) {
+        self.add_waiting_units();
+        if let Ok(true) = self.try_create_unit() {
+            debug!(target: &self.log_target, "{}{} Created a final unit before exiting.", self.log_prefix, self.node_id);
+        }
+    }
+
+    /// Tells the consumers that the Creator stopped on the exit signal. Nobody listening anymore is
+    /// fine at this point, so a failed send is not an error.
+    fn terminate(&mut self) {
+        if self.new_units_tx.send(NotificationOut::Terminated).is_err() {
+            debug!(target: &self.log_target, "{}{} Nobody to notify about the termination.", self.log_prefix, self.node_id);
+        }
+    }
+
+    /// Runs a Creator that sends its notifications to a stream instead of a channel. The stream
+    /// terminates once the Creator stops, i.e., when the session is complete, or when `parents_rx`
+    /// gets closed.
+    pub fn into_stream(
+        conf: Config<H, NI>,
+        parents_rx: Receiver<Unit<H>>,
+        hashing: Hashing,
+    ) -> Result<impl Stream<Item = NotificationOut<H>>, CreatorError> {
+        let (new_units_tx, new_units_rx) = mpsc::unbounded_channel();
+        let mut creator = Creator::new(conf, parents_rx, new_units_tx, hashing)?;
+        let run = async move {
+            // nobody sends the exit signal, the Creator stops on its own
+            let (_exit_tx, exit_rx) = oneshot::channel();
+            // the stream ends either way, there is nobody to report an error to
+            let _ = creator.create(exit_rx).await;
+        };
+        // dropping the Creator once it stops closes the channel, which ends the stream
+        let notifications = stream::unfold(new_units_rx, |mut rx| async move {
+            rx.recv().await.map(|notification| (Some(notification), rx))
+        });
+        Ok(
+            stream::select(notifications, run.into_stream().map(|_| None))
+                .filter_map(future::ready),
+        )
+    }
+
+    /// Runs the Creator until the exit signal, the end of the session or the closing of the
+    /// parents channel, all of which end it with `Ok`. An `Err` means that the Creator stopped
+    /// because it cannot continue, e.g., because nobody receives its notifications anymore.
+    pub async fn create(&mut self, exit: oneshot::Receiver<()>) -> Result<(), CreatorError> {
+        // the spans of the rounds are opened within the span of the whole run
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            target: DEFAULT_LOG_TARGET,
+            "create",
+            node_index = self.core.index().map(|index| index.0),
+        );
+        let run = self.run(exit);
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::instrument(run, span);
+        run.await
+    }
+
+    async fn run(&mut self, exit: oneshot::Receiver<()>) -> Result<(), CreatorError> {
+        if self.observer {
+            self.observe(exit).await;
+            return Ok(());
+        }
+        let mut exit = exit.into_stream();
+        tokio::select! {
+            _ = self.clock.delay(self.initial_delay) => {}
+            _ = exit.next() => {
+                debug!(target: &self.log_target, "{}{} received exit signal before creating the first unit.", self.log_prefix, self.node_id);
+                self.terminate();
+                return Ok(());
+            }
+        }
+        // The Creator is always ready at this point if it starts at round 0 without bootstrap
+        // peers, but not necessarily if restored or started at a later round. A restored Creator
+        // may have already sent its unit of the current round, and creating it again would fork,
+        // so it goes on with the next one.
+        if self.core.own_hash(self.current_round()).is_some() {
+            debug!(target: &self.log_target, "{}{} Own unit of round {} already created, not creating it again.", self.log_prefix, self.node_id, self.current_round());
+            self.advance_round();
+        } else if self.try_create_unit()? {
+            self.update_lag(self.clock.now());
+        }
+        if self.session_complete() {
+            return self.complete_session();
+        }
+        let stall_timeout = self.stall_timeout.unwrap_or_default();
+        let mut stall_timer = self.clock.delay(stall_timeout);
+        let mut parent_wait_timer = self.clock.delay(Duration::default());
+        let watchdog_interval = self.watchdog_interval.unwrap_or_default();
+        let mut watchdog_timer = self.clock.delay(watchdog_interval);
+        let progress_log_interval = self.progress_log_interval.unwrap_or_default();
+        let mut progress_log_timer = self.clock.delay(progress_log_interval);
+        let mut eviction_timer = self.clock.delay(self.candidate_ttl.unwrap_or_default());
+        if self.last_progress.is_none() {
+            self.last_progress = Some(self.clock.now());
+        }
+        let mut waiting_for_parents = false;
+        let mut min_parents_timer = self.clock.delay(Duration::default());
+        // the round for which the min_parents_timer runs
+        let mut min_parents_round = None;
+        let mut own_unit_timer = self.clock.delay(Duration::default());
+        // the round for which the own_unit_timer was started, at most once per round
+        let mut own_unit_round = None;
+        let mut own_unit_timer_running = false;
+        // the create_lag after our last unit, during which the candidates are still collected
+        let mut lag_timer = self.clock.delay(Duration::default());
+        let mut lagging = false;
+        loop {
+            if min_parents_round != Some(self.current_round()) {
+                if let Some(grace_left) = self.min_parents_grace_left() {
+                    min_parents_timer = self.clock.delay(grace_left);
+                    min_parents_round = Some(self.current_round());
+                }
+            }
+            if let Some(own_unit_timeout) = self.own_unit_timeout {
+                if own_unit_round != Some(self.current_round())
+                    && self.can_create(self.current_round()) == CreateReadiness::MissingOwnParent
+                {
+                    own_unit_timer = self.clock.delay(own_unit_timeout);
+                    own_unit_round = Some(self.current_round());
+                    own_unit_timer_running = true;
+                }
+            }
+            let created = tokio::select! {
+                u = self.parents_rx.recv() => {
+                    let u = match u {
+                        Some(u) => u,
+                        None => {
+                            debug!(target: &self.log_target, "{}{} Parents channel closed.", self.log_prefix, self.node_id);
+                            return Ok(());
+                        }
+                    };
+                    if self.add_unit(u.round(), u.creator(), u.hash()) {
+                        stall_timer = self.clock.delay(stall_timeout);
+                    }
+                    if lagging {
+                        self.note_lag_blocked(lagging);
+                        continue;
+                    }
+                    if waiting_for_parents || !self.check_ready() {
+                        continue;
+                    }
+                    if let Some(parent_wait) = self.start_parent_wait() {
+                        parent_wait_timer = self.clock.delay(parent_wait);
+                        waiting_for_parents = true;
+                        continue;
+                    }
+                    self.create_unit_and_catch_up()
+                }
+                _ = &mut lag_timer, if lagging => {
+                    lagging = false;
+                    self.note_lag_blocked(lagging);
+                    if !self.check_ready() {
+                        continue;
+                    }
+                    if let Some(parent_wait) = self.start_parent_wait() {
+                        parent_wait_timer = self.clock.delay(parent_wait);
+                        waiting_for_parents = true;
+                        continue;
+                    }
+                    self.create_unit_and_catch_up()
+                }
+                _ = &mut parent_wait_timer, if waiting_for_parents => {
+                    waiting_for_parents = false;
+                    self.create_unit_and_catch_up()
+                }
+                _ = &mut min_parents_timer, if !lagging && min_parents_round == Some(self.current_round()) => {
+                    min_parents_round = None;
+                    debug!(target: &self.log_target, "{}{} Falling back to the threshold at round {}.", self.log_prefix, self.node_id, self.current_round());
+                    self.create_unit_and_catch_up()
+                }
+                _ = next_trigger(&mut self.trigger_rx) => {
+                    self.add_waiting_units();
+                    if !self.check_ready() {
+                        continue;
+                    }
+                    match lagging {
+                        true => debug!(target: &self.log_target, "{}{} Triggered while waiting for the lag at round {}.", self.log_prefix, self.node_id, self.current_round()),
+                        false => debug!(target: &self.log_target, "{}{} Triggered at round {}.", self.log_prefix, self.node_id, self.current_round()),
+                    }
+                    lagging = false;
+                    self.note_lag_blocked(lagging);
+                    waiting_for_parents = false;
+                    self.create_unit_and_catch_up()
+                }
+                _ = &mut own_unit_timer, if own_unit_timer_running && own_unit_round == Some(self.current_round()) => {
+                    own_unit_timer_running = false;
+                    if self.can_create(self.current_round()) == CreateReadiness::MissingOwnParent {
+                        self.report_own_unit_missing()?;
+                    }
+                    continue;
+                }
+                _ = &mut stall_timer, if !lagging && self.stall_timeout.is_some() => {
+                    debug!(target: &self.log_target, "{}{} Stalled at round {}.", self.log_prefix, self.node_id, self.current_round());
+                    self.metrics_sink.stalled(self.current_round());
+                    let send_result = self.new_units_tx.send(self.stalled_notification());
+                    if let Err(e) = send_result {
+                        error!(target: &self.log_target, "{}{} Unable to send a stall notification: {:?}.", self.log_prefix, self.node_id, e);
+                        return Err(CreatorError::ChannelClosed);
+                    }
+                    self.request_catchup()?;
+                    stall_timer = self.clock.delay(stall_timeout);
+                    continue;
+                }
+                result = self.new_units_tx.deliver(), if self.new_units_tx.is_backlogged() => {
+                    if let Err(e) = result {
+                        error!(target: &self.log_target, "{}{} Unable to send the waiting notifications: {:?}.", self.log_prefix, self.node_id, e);
+                        return Err(CreatorError::ChannelClosed);
+                    }
+                    // the creation held back by the backlog may go on now
+                    if lagging || waiting_for_parents || !self.check_ready() {
+                        continue;
+                    }
+                    if let Some(parent_wait) = self.start_parent_wait() {
+                        parent_wait_timer = self.clock.delay(parent_wait);
+                        waiting_for_parents = true;
+                        continue;
+                    }
+                    self.create_unit_and_catch_up()
+                }
+                _ = &mut watchdog_timer, if self.watchdog_interval.is_some() => {
+                    self.check_progress();
+                    watchdog_timer = self.clock.delay(watchdog_interval);
+                    continue;
+                }
+                _ = &mut progress_log_timer, if self.progress_log_interval.is_some() => {
+                    self.log_progress();
+                    progress_log_timer = self.clock.delay(progress_log_interval);
+                    continue;
+                }
+                _ = &mut eviction_timer, if self.candidate_ttl.is_some() => {
+                    let next_eviction = self.evict_expired_candidates();
+                    eviction_timer = self.clock.delay(next_eviction);
+                    continue;
+                }
+                _ = exit.next() => {
+                    debug!(target: &self.log_target, "{}{} received exit signal.", self.log_prefix, self.node_id);
+                    if self.graceful_shutdown {
+                        self.flush();
+                    }
+                    self.terminate();
+                    return Ok(());
+                }
+            };
+            if created? {
+                if self.session_complete() {
+                    return self.complete_session();
+                }
+                stall_timer = self.clock.delay(stall_timeout);
+                let lag = self.lag_until_next_unit(self.clock.now());
+                lag_timer = self.clock.delay(lag);
+                lagging = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{
+            harness::CreatorHarness,
+            mock::{coord_hashing, hashing, Hash, NodeId},
+        },
+        ConfigBuilder, ControlHash, InMemoryStore, ParentOrdering, ParentPreference,
+        ParentSelector, Signature, ThresholdPolicy, DEFAULT_LOG_TARGET, DEFAULT_MAX_ROUND_LEAD,
+    };
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    type MockHashing = fn(&[u8]) -> Hash;
+
+    fn builder(n_members: usize) -> ConfigBuilder<Hash, NodeId> {
+        Config::builder()
+            .node_id(0.into())
+            .n_members(NodeCount::from_usize(n_members))
+    }
+
+    fn creator(
+        conf: Config<Hash, NodeId>,
+    ) -> (
+        Creator<Hash, NodeId, MockHashing>,
+        Receiver<NotificationOut<Hash>>,
+        Sender<Unit<Hash>>,
+    ) {
+        let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, new_units_rx) = mpsc::unbounded_channel();
+        let creator =
+            Creator::new(conf, parents_rx, new_units_tx, coord_hashing as MockHashing).unwrap();
+        (creator, new_units_rx, parents_tx)
+    }
+
+    fn feed_round<Hashing: Hasher<Hash>>(
+        creator: &mut Creator<Hash, NodeId, Hashing>,
+        round: usize,
+        creators: &[usize],
+    ) {
+        for &pid in creators {
+            creator.add_unit(
+                Round(round),
+                NodeIndex::from_usize(pid),
+                Hash((round * 1000 + pid) as u32),
+            );
+        }
+    }
+
+    fn created_preunit(rx: &mut Receiver<NotificationOut<Hash>>) -> PreUnit<Hash> {
+        match rx.try_recv() {
+            Ok(NotificationOut::CreatedPreUnit(pu)) => pu,
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+    }
+
+    struct OthersOnlySelector;
+
+    impl ParentSelector<Hash> for OthersOnlySelector {
+        fn select(
+            &self,
+            _round: Round,
+            candidates: &NodeMap<Option<Hash>>,
+            own_index: NodeIndex,
+        ) -> NodeMap<Option<Hash>> {
+            let mut parents = candidates.clone();
+            parents[own_index] = None;
+            parents
+        }
+    }
+
+    struct FirstThreeSelector;
+
+    impl ParentSelector<Hash> for FirstThreeSelector {
+        fn select(
+            &self,
+            _round: Round,
+            candidates: &NodeMap<Option<Hash>>,
+            _own_index: NodeIndex,
+        ) -> NodeMap<Option<Hash>> {
+            candidates
+                .enumerate()
+                .map(|(ix, h)| if ix.0 < 3 { *h } else { None })
+                .collect()
+        }
+    }
+
+    fn add_event(round: usize, pid: usize) -> CreatorEvent<Hash> {
+        CreatorEvent::AddUnit {
+            round: Round(round),
+            pid: NodeIndex::from_usize(pid),
+            hash: Hash((round * 1000 + pid) as u32),
+        }
+    }
+
+    #[test]
+    fn events_are_applied_in_order() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        let mut events = vec![CreatorEvent::TryCreate, CreatorEvent::TryCreate];
+        events.extend((0..3).map(|pid| add_event(0, pid)));
+        events.push(CreatorEvent::TryCreate);
+        // the candidates of round 1 are not there yet
+        events.push(CreatorEvent::TryCreate);
+        assert_eq!(creator.apply_events(&events), 2);
+        assert_eq!(created_preunit(&mut rx).round(), Round(0));
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(3));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn out_of_range_pid_is_ignored() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        let events = [
+            CreatorEvent::TryCreate,
+            add_event(0, 4),
+            add_event(0, usize::MAX),
+            add_event(0, 0),
+            add_event(0, 1),
+            CreatorEvent::TryCreate,
+        ];
+        assert_eq!(creator.apply_events(&events), 1);
+        created_preunit(&mut rx);
+        assert_eq!(creator.core.n_candidates(Round(0)), NodeCount(2));
+        assert!(!creator.has_candidate(Round(0), NodeIndex::from_usize(usize::MAX)));
+        assert_eq!(creator.missing_parents(Round(0)).len(), 2);
+    }
+
+    #[test]
+    fn banned_node_is_never_a_parent() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        creator.ban_node(NodeIndex(3));
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        for round in 0..3 {
+            feed_round(&mut creator, round, &[0, 1, 2, 3]);
+            assert_eq!(creator.try_create_unit(), Ok(true));
+            let parents = &created_preunit(&mut rx).control_hash.parents;
+            assert_eq!(parents, &vec![true, true, true, false].into());
+        }
+    }
+
+    #[test]
+    fn banned_parents_needed_are_reported_once() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 3]);
+        creator.ban_node(NodeIndex(3));
+        // neither ourselves nor a non-member can be banned
+        creator.ban_node(NodeIndex(0));
+        creator.ban_node(NodeIndex(4));
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(2),
+                need: NodeCount(3),
+            }
+        );
+        assert_eq!(
+            rx.try_recv(),
+            Ok(NotificationOut::BannedParentsNeeded { round: Round(1) })
+        );
+        creator.ban_node(NodeIndex(3));
+        assert!(rx.try_recv().is_err());
+        feed_round(&mut creator, 0, &[2]);
+        assert_eq!(creator.try_create_unit(), Ok(true));
+        let parents = &created_preunit(&mut rx).control_hash.parents;
+        assert_eq!(parents, &vec![true, true, true, false].into());
+    }
+
+    #[test]
+    fn burst_from_one_creator_is_limited() {
+        let conf = builder(4)
+            .max_units_per_creator(3)
+            .max_round_lead(10)
+            .build()
+            .unwrap();
+        let (mut creator, _rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        let burst: Vec<_> = (1..9).map(|round| unit(1, round, 4)).collect();
+        assert_eq!(creator.add_units(burst), 0);
+        let taken: Vec<_> = (1..9)
+            .filter(|round| creator.has_candidate(Round(*round), NodeIndex(1)))
+            .collect();
+        // the unit of the current round is not limited, three of the later ones are
+        assert_eq!(taken, vec![1, 2, 3, 4]);
+        // the others are not limited by the burst
+        feed_round(&mut creator, 0, &[0, 2, 3]);
+        // and the limit starts anew with the next round
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(creator.add_units(vec![unit(1, 5, 4)]), 0);
+        assert!(creator.has_candidate(Round(5), NodeIndex(1)));
+    }
+
+    #[test]
+    fn burst_ahead_does_not_block_the_current_round() {
+        let conf = builder(4)
+            .max_units_per_creator(2)
+            .max_round_lead(10)
+            .build()
+            .unwrap();
+        let (mut creator, _rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        // resent duplicates do not use up the limit
+        let resent: Vec<_> = (0..5).map(|_| unit(1, 2, 4)).collect();
+        assert_eq!(creator.add_units(resent), 0);
+        let burst: Vec<_> = (3..9).map(|round| unit(1, round, 4)).collect();
+        assert_eq!(creator.add_units(burst), 0);
+        let taken: Vec<_> = (2..9)
+            .filter(|round| creator.has_candidate(Round(*round), NodeIndex(1)))
+            .collect();
+        assert_eq!(taken, vec![2, 3]);
+        // the units we need are still taken, also the late one of the previous round
+        assert_eq!(creator.add_units(vec![unit(1, 1, 4), unit(1, 0, 4)]), 0);
+        assert!(creator.has_candidate(Round(1), NodeIndex(1)));
+        assert!(creator.has_candidate(Round(0), NodeIndex(1)));
+    }
+
+    #[test]
+    fn own_head_tracks_the_last_created_unit() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        assert_eq!(creator.own_head(), None);
+        for round in 0..3 {
+            if round > 0 {
+                feed_round(&mut creator, round - 1, &[0, 1, 2]);
+            }
+            assert_eq!(creator.try_create_unit(), Ok(true));
+            let pu = created_preunit(&mut rx);
+            let head = (Round(round), Hash(1000 * round as u32));
+            assert_eq!(creator.own_head(), Some(head));
+            assert_eq!(pu.using_encoded(coord_hashing), head.1);
+        }
+    }
+
+    #[test]
+    fn round_zero_waits_for_bootstrap_peers() {
+        let conf = builder(4).bootstrap_peers(NodeCount(2)).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(
+            creator.can_create(Round(0)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(0),
+                need: NodeCount(2),
+            }
+        );
+        assert_eq!(creator.try_create_unit(), Ok(false));
+        assert_eq!(creator.add_units(vec![unit(1, 0, 4)]), 0);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(creator.add_units(vec![unit(2, 0, 4)]), 1);
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.round(), Round(0));
+        assert_eq!(pu.n_parents(), NodeCount(0));
+    }
+
+    #[test]
+    fn forced_unit_advances_below_threshold() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 3]);
+        assert_eq!(creator.try_create_unit(), Ok(false));
+        let pu = creator.force_advance().unwrap();
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(
+            pu.control_hash.parents,
+            vec![true, false, false, true].into()
+        );
+        assert_eq!(created_preunit(&mut rx), pu);
+        assert_eq!(
+            rx.try_recv(),
+            Ok(NotificationOut::ForcedUnit {
+                round: Round(1),
+                n_parents: NodeCount(2),
+            })
+        );
+        assert_eq!(creator.current_round(), Round(2));
+    }
+
+    #[test]
+    fn parent_ordering_is_used_for_control_hash() {
+        let conf = builder(4)
+            .parent_ordering(ParentOrdering::ByHash)
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        let parents = vec![Some(Hash(0)), Some(Hash(1)), Some(Hash(2)), None].into();
+        let expected =
+            ControlHash::combine_hashes_in(&parents, None, ParentOrdering::ByHash, &coord_hashing);
+        assert_eq!(pu.control_hash.hash, expected);
+        assert_ne!(pu.control_hash, ControlHash::new(&parents, &coord_hashing));
+    }
+
+    #[test]
+    fn session_id_is_hashed_into_control_hash() {
+        let conf = builder(4).session_id(3).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        let parents = vec![Some(Hash(0)), Some(Hash(1)), Some(Hash(2)), None].into();
+        let expected = PreUnit::new_from_parents_in_session(
+            NodeIndex(0),
+            Round(1),
+            &parents,
+            3,
+            &coord_hashing,
+        );
+        assert_eq!(pu.control_hash, expected.control_hash);
+        assert_ne!(pu.control_hash, ControlHash::new(&parents, &coord_hashing));
+    }
+
+    #[test]
+    fn unit_is_created_with_given_parents() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3]);
+        // not all the candidates, and not only candidates
+        let parents: NodeMap<_> = vec![Some(Hash(0)), None, Some(Hash(7)), Some(Hash(3))].into();
+        let pu = creator.create_with_parents(parents.clone()).unwrap();
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(pu.control_hash, ControlHash::new(&parents, &coord_hashing));
+        assert_eq!(created_preunit(&mut rx), pu);
+        assert_eq!(creator.current_round(), Round(2));
+    }
+
+    #[test]
+    fn invalid_given_parents_are_rejected() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        let too_few = vec![Some(Hash(0)), Some(Hash(1)), None, None].into();
+        assert_eq!(
+            creator.create_with_parents(too_few),
+            Err(CreatorError::InvalidParents(
+                CreateReadiness::NotEnoughParents {
+                    have: NodeCount(2),
+                    need: NodeCount(3),
+                }
+            ))
+        );
+        let without_own = vec![None, Some(Hash(1)), Some(Hash(2)), Some(Hash(3))].into();
+        assert_eq!(
+            creator.create_with_parents(without_own),
+            Err(CreatorError::InvalidParents(
+                CreateReadiness::MissingOwnParent
+            ))
+        );
+        let too_long = vec![Some(Hash(0)); 5].into();
+        assert_eq!(
+            creator.create_with_parents(too_long),
+            Err(CreatorError::ParentsLength {
+                len: NodeCount(5),
+                n_members: NodeCount(4),
+            })
+        );
+        assert_eq!(creator.current_round(), Round(1));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn invalid_given_parents_are_forced() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        let parents = vec![None, Some(Hash(1)), None, None].into();
+        let pu = creator.force_create_with_parents(parents).unwrap();
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(1));
+        assert_eq!(created_preunit(&mut rx), pu);
+        assert_eq!(creator.current_round(), Round(2));
+    }
+
+    #[test]
+    fn greedy_selector_takes_all_candidates() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(4));
+    }
+
+    #[test]
+    fn custom_selector_is_used() {
+        let conf = builder(5)
+            .parent_selector(FirstThreeSelector)
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3, 4]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        let expected: Vec<bool> = vec![true, true, true, false, false];
+        assert_eq!(
+            pu.control_hash.parents.iter().cloned().collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    // ceil(3*N/4)
+    struct ThreeQuartersPolicy;
+
+    impl ThresholdPolicy for ThreeQuartersPolicy {
+        fn required(&self, n_members: NodeCount) -> NodeCount {
+            (n_members * 3 + NodeCount(3)) / 4
+        }
+    }
+
+    // ceil(N/2), which for an even N is not a strict majority
+    struct MajorityOnlyPolicy;
+
+    impl ThresholdPolicy for MajorityOnlyPolicy {
+        fn required(&self, n_members: NodeCount) -> NodeCount {
+            (n_members + NodeCount(1)) / 2
+        }
+    }
+
+    #[test]
+    fn default_threshold_is_two_thirds() {
+        let (mut creator, mut rx, _) = creator(builder(10).build().unwrap());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3, 4, 5]);
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(6),
+                need: NodeCount(7)
+            }
+        );
+        feed_round(&mut creator, 0, &[6]);
+        assert_eq!(creator.can_create(Round(1)), CreateReadiness::Ready);
+    }
+
+    #[test]
+    fn custom_threshold_policy_is_used() {
+        let conf = builder(10)
+            .threshold_policy(ThreeQuartersPolicy)
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(7),
+                need: NodeCount(8)
+            }
+        );
+        assert_eq!(creator.try_create_unit(), Ok(false));
+        feed_round(&mut creator, 0, &[7]);
+        assert_eq!(creator.try_create_unit(), Ok(true));
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.n_parents(),
+            NodeCount(8)
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not a strict majority")]
+    fn threshold_policy_below_strict_majority_is_rejected() {
+        let conf = builder(4)
+            .threshold_policy(MajorityOnlyPolicy)
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1]);
+        creator.can_create(Round(1));
+    }
+
+    #[test]
+    fn high_weight_minority_is_enough() {
+        // two nodes hold 70 out of 75, the threshold is 51
+        let conf = builder(7)
+            .weights(vec![40, 30, 1, 1, 1, 1, 1].into())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0]);
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::NotEnoughWeight { have: 40, need: 51 }
+        );
+        feed_round(&mut creator, 0, &[1]);
+        assert_eq!(creator.can_create(Round(1)), CreateReadiness::Ready);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.n_parents(),
+            NodeCount(2)
+        );
+    }
+
+    #[test]
+    fn low_weight_majority_is_not_enough() {
+        let conf = builder(7)
+            .weights(vec![40, 30, 1, 1, 1, 1, 1].into())
+            .build()
+            .unwrap();
+        let (mut light, mut rx, _) = creator(conf);
+        assert_eq!(light.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        // six out of seven nodes, but only 45 out of 75
+        feed_round(&mut light, 0, &[0, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            light.can_create(Round(1)),
+            CreateReadiness::NotEnoughWeight { have: 45, need: 51 }
+        );
+        assert_eq!(light.try_create_unit(), Ok(false));
+        // our own unit is still required, however heavy the others are
+        let conf = builder(7)
+            .weights(vec![1, 40, 30, 1, 1, 1, 1].into())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[1, 2]);
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::MissingOwnParent
+        );
+    }
+
+    #[test]
+    fn equal_weights_behave_like_counting() {
+        for n_members in 1..10 {
+            let conf = builder(n_members)
+                .weights(vec![5; n_members].into())
+                .build()
+                .unwrap();
+            let (mut weighted, mut weighted_rx, _) = creator(conf);
+            let (mut counted, mut counted_rx, _) = creator(builder(n_members).build().unwrap());
+            assert_eq!(weighted.create_unit(), Ok(true));
+            assert_eq!(counted.create_unit(), Ok(true));
+            created_preunit(&mut weighted_rx);
+            created_preunit(&mut counted_rx);
+            for pid in 0..n_members {
+                feed_round(&mut weighted, 0, &[pid]);
+                feed_round(&mut counted, 0, &[pid]);
+                assert_eq!(
+                    weighted.can_create(Round(1)).is_ready(),
+                    counted.can_create(Round(1)).is_ready()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn min_parents_raises_the_bar() {
+        let clock = ManualClock::new();
+        let conf = builder(7)
+            .min_parents(NodeCount(6), Duration::from_secs(10))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3, 4]);
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(5),
+                need: NodeCount(6)
+            }
+        );
+        clock.advance(Duration::from_secs(5));
+        feed_round(&mut creator, 0, &[5]);
+        assert_eq!(creator.try_create_unit(), Ok(true));
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.n_parents(),
+            NodeCount(6)
+        );
+    }
+
+    #[test]
+    fn min_parents_never_exceeds_members() {
+        let conf = builder(4)
+            .min_parents(NodeCount(10), Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3]);
+        assert_eq!(creator.can_create(Round(1)), CreateReadiness::Ready);
+    }
+
+    #[test]
+    fn max_parents_caps_deterministically() {
+        let n_members = 100;
+        let own_index = 80;
+        let create = || {
+            let conf = Config::builder()
+                .node_id(own_index.into())
+                .n_members(NodeCount::from_usize(n_members))
+                .max_parents(NodeCount(70))
+                .build()
+                .unwrap();
+            let (mut creator, mut rx, _) = creator(conf);
+            assert_eq!(creator.create_unit(), Ok(true));
+            created_preunit(&mut rx);
+            let all: Vec<usize> = (0..n_members).collect();
+            feed_round(&mut creator, 0, &all);
+            assert_eq!(creator.create_unit(), Ok(true));
+            created_preunit(&mut rx)
+        };
+        let pu = create();
+        let parents = &pu.control_hash.parents;
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(70));
+        assert!(
+            pu.control_hash.n_parents() > NodeCount::from_usize(n_members).threshold_two_thirds()
+        );
+        assert!(parents[NodeIndex::from_usize(own_index)]);
+        assert!((0..69).all(|pid| parents[NodeIndex(pid)]));
+        assert!((69..n_members).all(|pid| pid == own_index || !parents[NodeIndex::from_usize(pid)]));
+        assert_eq!(pu, create());
+    }
+
+    #[test]
+    fn max_parents_never_goes_below_threshold() {
+        let conf = builder(10).max_parents(NodeCount(2)).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.n_parents(),
+            NodeCount(7)
+        );
+    }
+
+    #[test]
+    fn most_recent_parents_are_preferred() {
+        let conf = builder(7)
+            .max_parents(NodeCount(5))
+            .parent_preference(ParentPreference::MostRecent)
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        // our own unit arrives first, but is kept anyway
+        feed_round(&mut creator, 0, &[0, 1, 6, 2, 5, 3, 4]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.parents,
+            vec![true, false, true, true, true, true, false].into()
+        );
+    }
+
+    #[test]
+    fn fixed_lag_stays_constant() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(10));
+        let (mut creator, _, _) = creator(conf);
+        let start = Instant::now();
+        for i in 0..5 {
+            creator.core.advance_round();
+            creator.update_lag(start + Duration::from_secs(i * i));
+            assert_eq!(creator.create_lag, Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn exponential_lag_backs_off_on_slow_parents() {
+        let base = Duration::from_millis(10);
+        let max = Duration::from_millis(100);
+        let conf = builder(4)
+            .lag_strategy(LagStrategy::ExponentialBackoff {
+                base,
+                max,
+                factor: 2,
+            })
+            .build()
+            .unwrap();
+        let (mut creator, _, _) = creator(conf);
+        let mut now = Instant::now();
+        creator.core.advance_round();
+        creator.update_lag(now);
+        assert_eq!(creator.create_lag, base);
+
+        // parents arrive much later than the lag would allow, so the lag grows up to max
+        let mut lags = vec![];
+        for _ in 0..5 {
+            now += Duration::from_millis(500);
+            creator.core.advance_round();
+            creator.update_lag(now);
+            lags.push(creator.create_lag.as_millis());
+        }
+        assert_eq!(lags, vec![20, 40, 80, 100, 100]);
+
+        // parents are available right after the lag ends, so the lag shrinks back to base
+        let mut lags = vec![];
+        for _ in 0..5 {
+            now += creator.create_lag;
+            creator.core.advance_round();
+            creator.update_lag(now);
+            lags.push(creator.create_lag.as_millis());
+        }
+        assert_eq!(lags, vec![50, 25, 12, 10, 10]);
+    }
+
+    #[test]
+    fn lag_schedule_speeds_up_first_rounds() {
+        let steady = Duration::from_millis(50);
+        let zero = Duration::from_millis(0);
+        let conf = builder(1)
+            .lag_strategy(LagStrategy::Schedule(vec![zero, zero, zero, steady]))
+            .build()
+            .unwrap();
+        let (mut creator, _rx, _) = creator(conf);
+        let now = Instant::now();
+        let mut lags = vec![];
+        for round in 0..6 {
+            if round > 0 {
+                feed_round(&mut creator, round - 1, &[0]);
+            }
+            assert_eq!(creator.create_unit(), Ok(true));
+            creator.update_lag(now);
+            lags.push(creator.create_lag);
+        }
+        assert_eq!(lags, vec![zero, zero, zero, steady, steady, steady]);
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn creator_started_later_waits_for_parents() {
+        let conf = builder(4).start_round(Round(10)).build().unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for pid in 1..4 {
+            let _ = parents_tx.send(unit(pid, 9, 4));
+        }
+        delay_for(Duration::from_millis(50)).await;
+        assert!(
+            rx.try_recv().is_err(),
+            "created a unit without our own parent"
+        );
+        let _ = parents_tx.send(unit(0, 9, 4));
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => {
+                assert_eq!(pu.round(), Round(10));
+                assert_eq!(pu.control_hash.n_parents(), NodeCount(4));
+            }
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn single_member_creates_contiguous_rounds() {
+        let conf = builder(1).build().unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for round in 0..5 {
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => {
+                    assert_eq!(pu.round(), Round(round));
+                    let expected_parents = if round == 0 { 0 } else { 1 };
+                    assert_eq!(pu.control_hash.n_parents(), NodeCount(expected_parents));
+                    let hash = Hash((1000 * round) as u32);
+                    let _ = parents_tx.send(Unit::new_from_preunit(pu, hash));
+                }
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[test]
+    fn two_members_need_both_parents() {
+        let conf = builder(2).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0]);
+        assert_eq!(creator.try_create_unit(), Ok(false));
+        feed_round(&mut creator, 0, &[1]);
+        assert_eq!(creator.try_create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(2));
+    }
+
+    async fn unit_created_before_exit(graceful_shutdown: bool) -> Option<Round> {
+        // the parent wait keeps the Creator from creating the unit before the exit signal
+        let conf = builder(4)
+            .parent_wait(Duration::from_secs(10))
+            .graceful_shutdown(graceful_shutdown)
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        for pid in 0..3 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        delay_for(Duration::from_millis(20)).await;
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+        match rx.try_recv() {
+            Ok(NotificationOut::CreatedPreUnit(pu)) => Some(pu.round()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn graceful_shutdown_creates_final_unit() {
+        assert_eq!(unit_created_before_exit(true).await, Some(Round(1)));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn creator_exits_immediately_by_default() {
+        assert_eq!(unit_created_before_exit(false).await, None);
+    }
+
+    #[test]
+    fn readiness_is_reported_per_round() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.can_create(Round(0)), CreateReadiness::Ready);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        let not_enough = |have| CreateReadiness::NotEnoughParents {
+            have: NodeCount(have),
+            need: NodeCount(3),
+        };
+        assert_eq!(creator.can_create(Round(1)), not_enough(0));
+        feed_round(&mut creator, 0, &[1, 2]);
+        assert_eq!(creator.can_create(Round(1)), not_enough(2));
+        feed_round(&mut creator, 0, &[3]);
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::MissingOwnParent
+        );
+        assert!(!creator.check_ready());
+        feed_round(&mut creator, 0, &[0]);
+        assert_eq!(creator.can_create(Round(1)), CreateReadiness::Ready);
+        assert!(creator.check_ready());
+        // nothing is known about rounds far ahead
+        assert_eq!(creator.can_create(Round(7)), not_enough(0));
+    }
+
+    #[test]
+    fn parent_list_matches_candidates() {
+        let conf = builder(4).include_parent_list(true).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        assert_eq!(
+            rx.try_recv(),
+            Ok(NotificationOut::CreatedUnitParents {
+                round: Round(0),
+                parents: vec![],
+            })
+        );
+        feed_round(&mut creator, 0, &[0, 1, 3]);
+        let candidates: Vec<_> = creator.frontier()[&Round(0)]
+            .present()
+            .map(|(pid, hash)| (pid, *hash))
+            .collect();
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        match rx.try_recv() {
+            Ok(NotificationOut::CreatedUnitParents { round, parents }) => {
+                assert_eq!(round, Round(1));
+                assert_eq!(parents, candidates);
+                let parents = NodeMap::from_parts(
+                    NodeCount(4),
+                    parents.into_iter().map(|(pid, hash)| (pid, Some(hash))),
+                )
+                .unwrap();
+                assert_eq!(pu.control_hash, ControlHash::new(&parents, &hashing));
+            }
+            other => panic!("expected a parent list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn batch_of_units_creates_as_many_units_as_possible() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        // round 3 lacks our own unit, so the batch lets us create the units of rounds 1 to 3
+        let units = (0..4)
+            .flat_map(|round| (0..4).map(move |pid| unit(pid, round, 4)))
+            .filter(|u| u.round() < Round(3) || u.creator() != NodeIndex(0));
+        assert_eq!(creator.add_units(units), 3);
+        for round in 1..4 {
+            assert_eq!(created_preunit(&mut rx).round(), Round(round));
+        }
+        assert!(rx.try_recv().is_err());
+        // duplicates and stale units are ignored
+        let stats = creator.stats();
+        assert_eq!(creator.add_units(vec![unit(1, 0, 4), unit(1, 3, 4)]), 0);
+        assert_eq!(creator.stats().candidates_stale, stats.candidates_stale + 1);
+        assert_eq!(creator.core.n_candidates(Round(3)), NodeCount(3));
+    }
+
+    #[test]
+    fn missing_parents_are_listed() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 2]);
+        assert_eq!(
+            creator.missing_parents(Round(0)),
+            vec![NodeIndex(1), NodeIndex(3)]
+        );
+        feed_round(&mut creator, 0, &[1, 3]);
+        assert!(creator.missing_parents(Round(0)).is_empty());
+        assert!(creator.missing_parents(Round(5)).is_empty());
+    }
+
+    #[test]
+    fn candidates_can_be_queried() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 2]);
+        assert!(creator.has_candidate(Round(0), NodeIndex(2)));
+        assert!(creator.has_exact(Round(0), NodeIndex(2), &Hash(2)));
+        // a fork is not the candidate
+        assert!(!creator.has_exact(Round(0), NodeIndex(2), &Hash(77)));
+        assert!(!creator.has_candidate(Round(0), NodeIndex(1)));
+        assert!(!creator.has_exact(Round(0), NodeIndex(1), &Hash(1)));
+        // an index outside of the committee
+        assert!(!creator.has_candidate(Round(0), NodeIndex(4)));
+
+        feed_round(&mut creator, 0, &[1]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 1, &[0, 1, 2]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 2, &[2]);
+        // rounds 0 and 1 are pruned, round 5 is not received yet
+        assert!(!creator.has_candidate(Round(0), NodeIndex(2)));
+        assert!(!creator.has_exact(Round(0), NodeIndex(2), &Hash(2)));
+        assert!(!creator.has_candidate(Round(1), NodeIndex(2)));
+        assert!(creator.has_exact(Round(2), NodeIndex(2), &Hash(2002)));
+        assert!(!creator.has_candidate(Round(5), NodeIndex(2)));
+    }
+
+    #[test]
+    fn frontier_reflects_fed_units() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 3]);
+        feed_round(&mut creator, 2, &[2]);
+        let frontier = creator.frontier();
+        assert_eq!(
+            frontier.keys().cloned().collect::<Vec<_>>(),
+            vec![Round(0), Round(1), Round(2)]
+        );
+        assert_eq!(
+            frontier[&Round(0)].iter().cloned().collect::<Vec<_>>(),
+            vec![Some(Hash(0)), Some(Hash(1)), None, Some(Hash(3))]
+        );
+        assert!(frontier[&Round(1)].iter().all(Option::is_none));
+        assert_eq!(frontier[&Round(2)][NodeIndex(2)], Some(Hash(2002)));
+    }
+
+    #[test]
+    fn reset_starts_a_new_session_at_round_zero() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let handle = creator.stats_handle();
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        for round in 0..3 {
+            feed_round(&mut creator, round, &[0, 1, 2, 3]);
+            assert_eq!(creator.create_unit(), Ok(true));
+            created_preunit(&mut rx);
+        }
+        assert_eq!(creator.current_round(), Round(4));
+        let _ = parents_tx.send(unit(1, 3, 4));
+
+        creator.reset();
+        assert_eq!(creator.current_round(), Round(0));
+        assert_eq!(creator.frontier().len(), 1);
+        assert_eq!(handle.stats(), CreatorStats::default());
+        assert!(handle.parent_histogram().is_empty());
+        // the unit of the old session waiting in the channel is gone
+        assert_eq!(creator.add_units(Vec::new()), 1);
+        assert_eq!(created_preunit(&mut rx).round(), Round(0));
+        feed_round(&mut creator, 0, &[0, 1, 2]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(created_preunit(&mut rx).round(), Round(1));
+        assert_eq!(handle.stats().created, 2);
+
+        creator.ban_node(NodeIndex(3));
+        feed_round(&mut creator, 1, &[0, 1, 3]);
+        let banned_needed = NotificationOut::BannedParentsNeeded { round: Round(2) };
+        assert_eq!(rx.try_recv(), Ok(banned_needed.clone()));
+        creator.reset();
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        // the ban is lifted
+        feed_round(&mut creator, 0, &[0, 1, 3]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(created_preunit(&mut rx).round(), Round(1));
+        // and needing the banned parents is reported again in the new session
+        creator.ban_node(NodeIndex(3));
+        feed_round(&mut creator, 1, &[0, 1, 3]);
+        assert_eq!(rx.try_recv(), Ok(banned_needed));
+    }
+
+    #[tokio::test]
+    async fn builder_wires_the_channels() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, parents_tx, mut rx) =
+            Creator::builder(conf, coord_hashing as MockHashing)
+                .build()
+                .unwrap();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        for pid in 0..4 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(1)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        assert_eq!(handle.await.unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn builder_rejects_node_without_index() {
+        let conf = Config::new(Outsider, NodeCount(4), Duration::from_millis(0));
+        assert_eq!(
+            Creator::builder(conf, hashing).build().err(),
+            Some(CreatorError::NoIndex)
+        );
+    }
+
+    #[test]
+    fn round_zero_has_no_previous_round() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        match creator.stalled_notification() {
+            NotificationOut::CreatorStalled {
+                round,
+                have_parents,
+                ..
+            } => assert_eq!((round, have_parents), (Round(0), NodeCount(0))),
+            other => panic!("expected a stall notification, got {:?}", other),
+        }
+        assert!(creator.add_unit(Round(0), NodeIndex(1), Hash(1)));
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.round(), Round(0));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(0));
+        // units of round 0 still serve as parents at round 1
+        assert!(creator.add_unit(Round(0), NodeIndex(2), Hash(2)));
+    }
+
+    #[test]
+    fn parent_histogram_counts_included_parents() {
+        // floor(2 * 7 / 3) + 2 = 6 parents out of the 7 members
+        let conf = builder(7).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        feed_round(&mut creator, 0, &[0, 1, 2, 3, 4, 5]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.n_parents(),
+            NodeCount(0)
+        );
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.n_parents(),
+            NodeCount(6)
+        );
+        let histogram = creator.stats_handle().parent_histogram();
+        assert_eq!(
+            histogram.into_iter().collect::<Vec<_>>(),
+            vec![(NodeCount(0), 1), (NodeCount(6), 1)]
+        );
+    }
+
+    #[test]
+    fn time_to_ready_is_measured_on_the_clock() {
+        let clock = ManualClock::new();
+        let conf = builder(4).clock(clock.clone()).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        clock.advance(Duration::from_secs(5));
+        feed_round(&mut creator, 0, &[0, 1]);
+        clock.advance(Duration::from_secs(2));
+        feed_round(&mut creator, 0, &[2]);
+        // arriving after we are ready does not change anything
+        clock.advance(Duration::from_secs(1));
+        feed_round(&mut creator, 0, &[3]);
+        assert_eq!(
+            creator.stats_handle().times_to_ready(),
+            vec![
+                (Round(0), Duration::from_secs(0)),
+                (Round(1), Duration::from_secs(7))
+            ]
+        );
+    }
+
+    #[test]
+    fn parent_histogram_reflects_capped_parents() {
+        let conf = builder(7).max_parents(NodeCount(5)).build().unwrap();
+        let (mut creator, _rx, _) = creator(conf);
+        let handle = creator.stats_handle();
+        for round in 0..PARENT_HISTOGRAM_WINDOW + 10 {
+            assert_eq!(creator.create_unit(), Ok(true));
+            feed_round(&mut creator, round, &[0, 1, 2, 3, 4, 5, 6]);
+        }
+        // the unit of round 0 without parents is out of the window by now
+        let histogram = handle.parent_histogram();
+        assert_eq!(
+            histogram.into_iter().collect::<Vec<_>>(),
+            vec![(NodeCount(5), PARENT_HISTOGRAM_WINDOW as u64)]
+        );
+    }
+
+    struct RoundPayloads(Arc<AtomicUsize>);
+
+    impl DataProvider<Vec<u32>> for RoundPayloads {
+        fn get_data(&mut self, round: Round) -> Vec<u32> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            vec![round.0 as u32; round.0]
+        }
+    }
+
+    #[test]
+    fn units_carry_provided_data() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let conf = builder(4)
+            .data_provider(RoundPayloads(calls.clone()))
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        for round in 0..4 {
+            if round > 0 {
+                assert_eq!(creator.try_create_unit(), Ok(false));
+                feed_round(&mut creator, round - 1, &[0, 1, 2]);
+            }
+            assert_eq!(creator.create_unit(), Ok(true));
+            let pu = created_preunit(&mut rx);
+            assert_eq!(pu.decode_data::<Vec<u32>>(), Ok(vec![round as u32; round]));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    // signs by prepending a key to the bytes, so only its owner can produce the signature
+    struct KeySigner(u8);
+
+    impl Signer for KeySigner {
+        fn sign(&self, bytes: &[u8]) -> Signature {
+            Signature(
+                std::iter::once(self.0)
+                    .chain(bytes.iter().copied())
+                    .collect(),
+            )
+        }
+    }
+
+    fn verify(key: u8, bytes: &[u8], signature: &Signature) -> bool {
+        signature.0.split_first() == Some((&key, bytes))
+    }
+
+    #[test]
+    fn created_units_are_signed() {
+        let conf = builder(4).signer(KeySigner(7)).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        for round in 0..3 {
+            if round > 0 {
+                feed_round(&mut creator, round - 1, &[0, 1, 2]);
+            }
+            assert_eq!(creator.create_unit(), Ok(true));
+            match rx.try_recv() {
+                Ok(NotificationOut::SignedUnit {
+                    preunit,
+                    creator,
+                    signature,
+                }) => {
+                    assert_eq!(preunit.round(), Round(round));
+                    assert_eq!(creator, NodeIndex(0));
+                    assert!(verify(7, &preunit.encode(), &signature));
+                    assert!(!verify(8, &preunit.encode(), &signature));
+                }
+                other => panic!("expected a signed unit, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn fork_is_reported_once() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2]);
+        // a duplicate is no fork, and a fork of a slot is reported only once
+        assert!(!creator.add_unit(Round(0), NodeIndex(1), Hash(1)));
+        assert!(!creator.add_unit(Round(0), NodeIndex(1), Hash(2)));
+        assert!(!creator.add_unit(Round(0), NodeIndex(1), Hash(3)));
+        match rx.try_recv() {
+            Ok(NotificationOut::ForkDetected {
+                round,
+                creator: forker,
+                hash_a,
+                hash_b,
+            }) => {
+                assert_eq!((round, forker), (Round(0), NodeIndex(1)));
+                assert_eq!((hash_a, hash_b), (Hash(1), Hash(2)));
+            }
+            other => panic!("expected a fork notification, got {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+        // the first seen unit remains the parent
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        let parents = NodeMap::from_parts(
+            NodeCount(4),
+            (0..3).map(|pid| (NodeIndex(pid), Some(Hash(pid as u32)))),
+        )
+        .unwrap();
+        assert_eq!(pu.control_hash, ControlHash::new(&parents, &hashing));
+    }
+
+    #[test]
+    fn creators_log_with_configured_targets() {
+        let conf = builder(4)
+            .log_target("session-a")
+            .log_label("a")
+            .build()
+            .unwrap();
+        let (creator_a, _rx_a, _) = creator(conf);
+        let (creator_b, _rx_b, _) = creator(builder(4).build().unwrap());
+        assert_eq!(
+            (creator_a.log_target.as_str(), creator_a.log_prefix.as_str()),
+            ("session-a", "[a] ")
+        );
+        assert_eq!(
+            (creator_b.log_target.as_str(), creator_b.log_prefix.as_str()),
+            (DEFAULT_LOG_TARGET, "")
+        );
+    }
+
+    #[test]
+    fn reconfigured_creator_uses_new_threshold() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        assert_eq!(creator.reconfigure(NodeCount(7), NodeIndex(2)), Ok(()));
+        // 4 units are enough among 4 members, but not among 7
+        feed_round(&mut creator, 0, &[0, 1, 2, 3]);
+        assert_eq!(
+            creator.can_create(Round(1)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(4),
+                need: NodeCount(5),
+            }
+        );
+        assert!(creator.add_unit(Round(0), NodeIndex(6), Hash(6)));
+        assert_eq!(creator.create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.creator(), NodeIndex(2));
+        assert_eq!(pu.control_hash.n_members(), NodeCount(7));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(5));
+    }
+
+    #[test]
+    fn reconfiguration_mid_round_is_rejected() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[1]);
+        assert_eq!(
+            creator.reconfigure(NodeCount(7), NodeIndex(0)),
+            Err(ReconfigureError::MidRound(Round(0)))
+        );
+        assert_eq!(
+            creator.reconfigure(NodeCount(7), NodeIndex(7)),
+            Err(ReconfigureError::NodeIndexOutOfRange {
+                index: NodeIndex(7),
+                n_members: NodeCount(7),
+            })
+        );
+        assert_eq!(creator.core.n_members(), NodeCount(4));
+        assert_eq!(creator.core.n_candidates(Round(0)), NodeCount(1));
+    }
+
+    #[test]
+    fn duplicates_are_rejected_without_forks() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        assert!(creator.add_unit(Round(0), NodeIndex(1), Hash(1)));
+        for _ in 0..10 {
+            assert!(!creator.add_unit(Round(0), NodeIndex(1), Hash(1)));
+        }
+        assert_eq!(creator.core.n_candidates(Round(0)), NodeCount(1));
+        assert!(rx.try_recv().is_err());
+        // a different unit in the same slot is still a fork
+        assert!(!creator.add_unit(Round(0), NodeIndex(1), Hash(2)));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NotificationOut::ForkDetected { .. })
+        ));
+    }
+
+    #[test]
+    fn impostor_of_own_unit_is_rejected() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        // our unit of round 0 hashes to Hash(0), anything else claiming its slot is an impostor
+        assert!(!creator.add_unit(Round(0), NodeIndex(0), Hash(999)));
+        assert!(!creator.add_unit(Round(0), NodeIndex(0), Hash(998)));
+        match rx.try_recv() {
+            Ok(NotificationOut::ImpostorUnit {
+                round,
+                expected,
+                received,
+            }) => {
+                assert_eq!(round, Round(0));
+                assert_eq!((expected, received), (Hash(0), Hash(999)));
+            }
+            other => panic!("expected an impostor notification, got {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+        assert_eq!(creator.core.n_candidates(Round(0)), NodeCount(0));
+        assert!(creator.add_unit(Round(0), NodeIndex(0), Hash(0)));
+    }
+
+    #[test]
+    fn own_units_hashed_otherwise_can_be_accepted() {
+        let conf = builder(4).skip_own_hash_check(true).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        // the environment hashes our unit of round 0 differently
+        assert!(creator.add_unit(Round(0), NodeIndex(0), Hash(999)));
+        assert!(rx.try_recv().is_err());
+        feed_round(&mut creator, 0, &[1, 2]);
+        assert!(creator.check_ready());
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn dry_run_records_units_instead_of_sending() {
+        let conf = builder(5)
+            .parent_selector(FirstThreeSelector)
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        for round in 0..3 {
+            for pid in 0..5 {
+                let _ = parents_tx.send(unit(pid, round, 5));
+            }
+        }
+        drop(parents_tx);
+        let (_exit_tx, exit_rx) = oneshot::channel();
+        let _ = tokio::time::timeout(Duration::from_millis(100), creator.create(exit_rx)).await;
+        assert!(rx.try_recv().is_err());
+        let units = creator.dry_run_units().expect("in the dry run mode");
+        let rounds: Vec<Round> = units.iter().map(|pu| pu.round()).collect();
+        assert_eq!(rounds, vec![Round(0), Round(1), Round(2), Round(3)]);
+        assert_eq!(units[0].control_hash.n_parents(), NodeCount(0));
+        for pu in &units[1..] {
+            let parents: Vec<bool> = pu.control_hash.parents.iter().cloned().collect();
+            assert_eq!(parents, vec![true, true, true, false, false]);
+        }
+    }
+
+    #[test]
+    fn stats_count_created_and_received_units() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        let handle = creator.stats_handle();
+        for round in 0..5 {
+            if round > 0 {
+                feed_round(&mut creator, round - 1, &[0, 1, 2]);
+            }
+            assert_eq!(creator.create_unit(), Ok(true));
+            created_preunit(&mut rx);
+        }
+        // a duplicate is seen but not stale, a unit of round 1 is stale at round 5
+        feed_round(&mut creator, 4, &[0, 0]);
+        feed_round(&mut creator, 1, &[3]);
+        let expected = CreatorStats {
+            created: 5,
+            candidates_seen: 15,
+            candidates_stale: 1,
+            current_round: Round(5),
+            watchdog_warnings: 0,
+            progress_logs: 0,
+        };
+        assert_eq!(handle.stats(), expected);
+        assert_eq!(creator.stats(), expected);
+    }
+
+    #[test]
+    fn old_rounds_are_pruned() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, mut rx, _) = creator(conf);
+        for round in 0..10_000 {
+            if round > 0 {
+                feed_round(&mut creator, round - 1, &[0, 1, 2]);
+            }
+            assert!(creator.check_ready());
+            assert_eq!(creator.create_unit(), Ok(true));
+            assert_eq!(created_preunit(&mut rx).round(), Round(round));
+            assert!(creator.frontier().len() <= 2);
+        }
+        assert_eq!(
+            creator.frontier().keys().next().copied().unwrap(),
+            Round(9_999)
+        );
+        // a late unit of a pruned round is ignored
+        feed_round(&mut creator, 5, &[3]);
+        assert!(creator.frontier().len() <= 2);
+    }
+
+    #[test]
+    fn candidates_can_be_kept_in_another_store() {
+        // keeps the candidates in memory, but does not lend them out, like an on-disk store
+        struct CountingStore(InMemoryStore<Hash>, Arc<AtomicUsize>);
+
+        impl CandidateStore<Hash> for CountingStore {
+            fn get(&self, round: Round, pid: NodeIndex) -> Option<Hash> {
+                self.0.get(round, pid)
+            }
+
+            fn put(&mut self, round: Round, pid: NodeIndex, hash: Hash) {
+                self.1.fetch_add(1, Ordering::Relaxed);
+                self.0.put(round, pid, hash)
+            }
+
+            fn remove(&mut self, round: Round, pid: NodeIndex) {
+                self.0.remove(round, pid)
+            }
+
+            fn count(&self, round: Round) -> NodeCount {
+                self.0.count(round)
+            }
+
+            fn prune(&mut self, before: Round) {
+                self.0.prune(before)
+            }
+
+            fn reset(&mut self, n_members: NodeCount) {
+                self.0.reset(n_members)
+            }
+        }
+
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut reference, mut reference_rx, _) = creator(conf.clone());
+        let (creator, mut rx, _) = creator(conf);
+        let puts = Arc::new(AtomicUsize::new(0));
+        let store = CountingStore(InMemoryStore::new(NodeCount(4)), puts.clone());
+        let mut creator = creator.with_candidate_store(store);
+        for round in 0..5 {
+            if round > 0 {
+                feed_round(&mut reference, round - 1, &[0, 1, 3]);
+                feed_round(&mut creator, round - 1, &[0, 1, 3]);
+            }
+            assert_eq!(reference.create_unit(), Ok(true));
+            assert_eq!(creator.create_unit(), Ok(true));
+            assert_eq!(created_preunit(&mut rx), created_preunit(&mut reference_rx));
+        }
+        assert_eq!(puts.load(Ordering::Relaxed), 12);
+        assert_eq!(creator.snapshot(), reference.snapshot());
+    }
+
+    #[test]
+    fn deltas_replicate_the_state() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut source, mut rx, _) = creator(conf.clone());
+        let (mut replica, _replica_rx, _) = creator(conf);
+        for round in 0..10 {
+            if round > 0 {
+                feed_round(&mut source, round - 1, &[0, 1, 2]);
+            }
+            assert_eq!(source.create_unit(), Ok(true));
+            created_preunit(&mut rx);
+            // arrives after the unit of the next round was created
+            feed_round(&mut source, round, &[3]);
+            if round % 3 == 0 {
+                let base = Round(replica.current_round().0.saturating_sub(1));
+                let delta = source.snapshot_since(base);
+                assert_eq!(delta.base(), base);
+                assert_eq!(replica.apply_delta(delta), Ok(()));
+                assert_eq!(replica.snapshot(), source.snapshot());
+            }
+        }
+        assert_eq!(replica.own_head(), source.own_head());
+
+        let delta = source.snapshot_since(Round(20));
+        assert_eq!(
+            replica.apply_delta(delta),
+            Err(DeltaError::Gap {
+                base: Round(20),
+                current_round: Round(10),
+            })
+        );
+        assert_eq!(replica.snapshot(), source.snapshot());
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn restored_creator_continues_from_snapshot() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, mut rx, _) = creator(conf.clone());
+        for round in 0..50 {
+            if round > 0 {
+                feed_round(&mut creator, round - 1, &[0, 1, 2]);
+            }
+            assert_eq!(creator.create_unit(), Ok(true));
+            created_preunit(&mut rx);
+        }
+        feed_round(&mut creator, 49, &[0, 1, 2]);
+        let state = creator.snapshot();
+        assert_eq!(state.current_round(), Round(50));
+
+        #[cfg(feature = "serde")]
+        {
+            let encoded = serde_json::to_string(&state).unwrap();
+            let decoded: CreatorState<Hash> = serde_json::from_str(&encoded).unwrap();
+            assert_eq!(decoded, state);
+        }
+
+        let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, mut rx) = mpsc::unbounded_channel();
+        let mut restored =
+            Creator::from_snapshot(conf, parents_rx, new_units_tx, hashing, state).unwrap();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { restored.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(50)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+        assert!(matches!(rx.try_recv(), Ok(NotificationOut::Terminated)));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn restored_creator_waits_for_parents() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, mut rx, _) = creator(conf.clone());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+
+        let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, mut rx) = mpsc::unbounded_channel();
+        let mut restored =
+            Creator::from_snapshot(conf, parents_rx, new_units_tx, hashing, creator.snapshot())
+                .unwrap();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { restored.create(exit_rx).await });
+        for pid in 0..3 {
+            let pu = PreUnit::new_from_parents(
+                NodeIndex(pid),
+                Round(1),
+                &NodeMap::new_with_len(NodeCount(4)),
+                &hashing,
+            );
+            // our own unit is recognized by the hash remembered in the snapshot
+            let hash = match pid {
+                0 => *creator.core.own_hash(Round(1)).unwrap(),
+                _ => Hash(pid as u32),
+            };
+            let _ = parents_tx.send(Unit::new_from_preunit(pu, hash));
+        }
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(2)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[test]
+    fn snapshot_of_another_committee_is_rejected() {
+        let (mut creator, mut rx, _) = creator(builder(4).build().unwrap());
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2]);
+        let mut state = creator.snapshot();
+        let restore = |n_members, state| {
+            let conf = Config::<Hash, NodeId>::new(
+                0.into(),
+                NodeCount(n_members),
+                Duration::from_millis(0),
+            );
+            let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+            let (new_units_tx, _rx) = mpsc::unbounded_channel();
+            Creator::from_snapshot(conf, parents_rx, new_units_tx, hashing, state).err()
+        };
+        assert_eq!(
+            restore(7, state.clone()),
+            Some(CreatorError::Snapshot(SnapshotError::MembersMismatch {
+                n_members: NodeCount(7),
+                snapshot_members: NodeCount(4),
+            }))
+        );
+        // a tampered snapshot with a round of a different size
+        state.candidates_by_round[0] = NodeMap::new_with_len(NodeCount(3));
+        assert_eq!(
+            restore(4, state),
+            Some(CreatorError::Snapshot(SnapshotError::MembersMismatch {
+                n_members: NodeCount(4),
+                snapshot_members: NodeCount(3),
+            }))
+        );
+        // a tampered snapshot without the candidates of the parents round
+        let mut state = creator.snapshot();
+        state.first_round = Round(2);
+        assert_eq!(
+            restore(4, state),
+            Some(CreatorError::Snapshot(SnapshotError::FirstRoundAhead {
+                first_round: Round(2),
+                current_round: Round(1),
+            }))
+        );
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn restored_creator_does_not_recreate_its_unit() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, mut rx, _) = creator(conf.clone());
+        // the snapshot was taken after sending the unit of round 0, but before moving to round 1
+        let mut state = creator.snapshot();
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        state.own_hashes = creator.snapshot().own_hashes;
+        assert_eq!(state.current_round(), Round(0));
+        assert_eq!(state.own_hashes.len(), 1);
+        let own_hash = *creator.core.own_hash(Round(0)).unwrap();
+
+        let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, mut rx) = mpsc::unbounded_channel();
+        let mut restored =
+            Creator::from_snapshot(conf, parents_rx, new_units_tx, hashing, state).unwrap();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { restored.create(exit_rx).await });
+        // our unit of round 0 comes back from the Dag as usual
+        let pu = PreUnit::new_from_parents(
+            NodeIndex(0),
+            Round(0),
+            &NodeMap::new_with_len(NodeCount(4)),
+            &hashing,
+        );
+        let _ = parents_tx.send(Unit::new_from_preunit(pu, own_hash));
+        for pid in 1..4 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        // no second unit of round 0, and the next one is created as soon as the parents arrive
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(1)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    struct SaltedHasher(u8);
+
+    impl Hasher<Hash> for SaltedHasher {
+        fn hash(&self, data: &[u8]) -> Hash {
+            let mut salted = vec![self.0];
+            salted.extend_from_slice(data);
+            hashing(&salted)
+        }
+    }
+
+    #[test]
+    fn stateful_hasher_is_used() {
+        let create = |salt| {
+            let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+            let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+            let (new_units_tx, mut rx) = mpsc::unbounded_channel();
+            let mut creator =
+                Creator::new(conf, parents_rx, new_units_tx, SaltedHasher(salt)).unwrap();
+            assert_eq!(creator.create_unit(), Ok(true));
+            feed_round(&mut creator, 0, &[1, 2, 3]);
+            // our own unit is known by its salted hash
+            let own_hash = *creator.core.own_hash(Round(0)).unwrap();
+            creator.add_unit(Round(0), NodeIndex(0), own_hash);
+            assert_eq!(creator.create_unit(), Ok(true));
+            created_preunit(&mut rx);
+            (created_preunit(&mut rx), own_hash)
+        };
+        let (pu, own_hash) = create(7);
+        let parents = (0..4)
+            .map(|pid| Some(if pid == 0 { own_hash } else { Hash(pid as u32) }))
+            .collect::<NodeMap<_>>();
+        assert_eq!(
+            pu.control_hash.hash,
+            ControlHash::combine_hashes(&parents, &SaltedHasher(7))
+        );
+        assert_ne!(pu.control_hash.hash, create(8).0.control_hash.hash);
+        assert_ne!(
+            pu.control_hash.hash,
+            ControlHash::combine_hashes(&parents, &hashing)
+        );
+    }
+
+    #[test]
+    fn failed_hashing_does_not_advance_round() {
+        let conf = Config::<Hash, NodeId>::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, mut rx) = mpsc::unbounded_channel();
+        let attempts = AtomicUsize::new(0);
+        let mut creator = Creator::new_fallible(conf, parents_rx, new_units_tx, |data: &[u8]| {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(HashError("backend unavailable".into()))
+            } else {
+                Ok(hashing(data))
+            }
+        })
+        .unwrap();
+        assert_eq!(creator.create_unit(), Ok(false));
+        assert_eq!(creator.current_round(), Round(0));
+        assert!(rx.try_recv().is_err());
+
+        assert!(creator.check_ready());
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(created_preunit(&mut rx).round(), Round(0));
+        assert_eq!(creator.current_round(), Round(1));
+    }
+
+    #[test]
+    fn failing_hasher_stops_the_catch_up() {
+        let mut conf =
+            Config::<Hash, NodeId>::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        // nothing can be hashed anymore once our unit of round 1 is created
+        let failing = Arc::new(AtomicBool::new(false));
+        let created_failing = failing.clone();
+        conf.on_created = Some(Arc::new(move |pu: &PreUnit<Hash>| {
+            if pu.round() == Round(1) {
+                created_failing.store(true, Ordering::SeqCst);
+            }
+        }));
+        let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, mut rx) = mpsc::unbounded_channel();
+        let mut creator =
+            Creator::new_fallible(conf, parents_rx, new_units_tx, move |data: &[u8]| {
+                if failing.load(Ordering::SeqCst) {
+                    return Err(HashError("backend unavailable".into()));
+                }
+                Ok(hashing(data))
+            })
+            .unwrap();
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(created_preunit(&mut rx).round(), Round(0));
+        let own_hash = *creator.core.own_hash(Round(0)).unwrap();
+        assert!(creator.add_unit(Round(0), NodeIndex(0), own_hash));
+        // the units of round 1 are already there, so the Creator is ready for round 2 right away
+        for pid in 1..4 {
+            assert!(creator.add_unit(Round(0), NodeIndex(pid), Hash(pid as u32)));
+        }
+        for pid in 0..4 {
+            assert!(creator.add_unit(Round(1), NodeIndex(pid), Hash(10 + pid as u32)));
+        }
+        assert_eq!(creator.create_unit_and_catch_up(), Ok(true));
+        assert_eq!(created_preunit(&mut rx).round(), Round(1));
+        assert!(creator.check_ready());
+        assert_eq!(creator.current_round(), Round(2));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn panicking_hasher_does_not_kill_the_task() {
+        let conf = Config::<Hash, NodeId>::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, mut rx) = mpsc::unbounded_channel();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let hasher_attempts = attempts.clone();
+        let mut creator = Creator::new(conf, parents_rx, new_units_tx, move |data: &[u8]| {
+            if hasher_attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("transient hasher fault");
+            }
+            hashing(data)
+        })
+        .unwrap();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        delay_for(Duration::from_millis(10)).await;
+        assert!(attempts.load(Ordering::SeqCst) > 0);
+        assert!(rx.try_recv().is_err());
+        // the next unit arriving makes the Creator retry the unit of round 0
+        let _ = parents_tx.send(unit(1, 0, 4));
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        assert_eq!(handle.await.unwrap(), Ok(()));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn stall_is_reported_without_parents() {
+        let conf = builder(4)
+            .stall_timeout(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let pu = PreUnit::new_from_parents(
+            NodeIndex(1),
+            Round(0),
+            &NodeMap::new_with_len(NodeCount(4)),
+            &hashing,
+        );
+        let _ = parents_tx.send(Unit::new_from_preunit(pu, Hash(1)));
+        assert_eq!(
+            rx.recv().await,
+            Some(NotificationOut::CreatorStalled {
+                round: Round(1),
+                have_parents: NodeCount(1),
+                needed: NodeCount(3),
+            })
+        );
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn stall_requests_catchup_and_response_unblocks() {
+        let conf = builder(4)
+            .stall_timeout(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let result = creator.create(exit_rx).await;
+            (creator, result)
+        });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = parents_tx.send(unit(0, 0, 4));
+        let _ = parents_tx.send(unit(1, 0, 4));
+        assert!(matches!(
+            rx.recv().await,
+            Some(NotificationOut::CreatorStalled { .. })
+        ));
+        let missing = match rx.recv().await {
+            Some(NotificationOut::CatchupRequest { round, missing }) => {
+                assert_eq!(round, Round(0));
+                missing
+            }
+            other => panic!("expected a catch-up request, got {:?}", other),
+        };
+        assert_eq!(missing, vec![NodeIndex(2), NodeIndex(3)]);
+        let _ = exit_tx.send(());
+        let (mut creator, result) = handle.await.unwrap();
+        assert_eq!(result, Ok(()));
+        assert!(matches!(rx.try_recv(), Ok(NotificationOut::Terminated)));
+
+        let response = missing
+            .into_iter()
+            .map(|pid| unit(pid.as_usize(), 0, 4))
+            .collect();
+        assert_eq!(creator.apply_catchup_response(response), 1);
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(pu.n_parents(), NodeCount(4));
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum SinkEvent {
+        UnitCreated(Round, NodeCount),
+        CandidateReceived,
+        CandidateStale,
+        Stalled(Round),
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingSink(Arc<Mutex<Vec<SinkEvent>>>);
+
+    impl MetricsSink for RecordingSink {
+        fn unit_created(&self, round: Round, parents: NodeCount) {
+            self.0.lock().push(SinkEvent::UnitCreated(round, parents));
+        }
+
+        fn candidate_received(&self) {
+            self.0.lock().push(SinkEvent::CandidateReceived);
+        }
+
+        fn candidate_stale(&self) {
+            self.0.lock().push(SinkEvent::CandidateStale);
+        }
+
+        fn stalled(&self, round: Round) {
+            self.0.lock().push(SinkEvent::Stalled(round));
+        }
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn lifecycle_is_reported_to_metrics_sink() {
+        let sink = RecordingSink::default();
+        let conf = builder(4)
+            .stall_timeout(Duration::from_millis(20))
+            .metrics_sink(sink.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        for pid in 0..3 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(1)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        // too old to become a parent at round 2
+        let _ = parents_tx.send(unit(3, 0, 4));
+        assert!(matches!(
+            rx.recv().await,
+            Some(NotificationOut::CreatorStalled { .. })
+        ));
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+        use SinkEvent::*;
+        assert_eq!(
+            sink.0.lock()[..],
+            [
+                UnitCreated(Round(0), NodeCount(0)),
+                CandidateReceived,
+                CandidateReceived,
+                CandidateReceived,
+                UnitCreated(Round(1), NodeCount(3)),
+                CandidateReceived,
+                CandidateStale,
+                Stalled(Round(2)),
+            ]
+        );
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn missing_own_unit_is_reported() {
+        let conf = builder(4)
+            .own_unit_timeout(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        let own_unit = match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => {
+                let hash = pu.using_encoded(coord_hashing);
+                Unit::new_from_preunit(pu, hash)
+            }
+            other => panic!("expected a created preunit, got {:?}", other),
+        };
+        // enough parents, but for our own one
+        for pid in 1..4 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        assert_eq!(
+            rx.recv().await,
+            Some(NotificationOut::OwnUnitMissing { round: Round(0) })
+        );
+        let _ = parents_tx.send(own_unit);
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(1)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[test]
+    fn on_created_reports_every_unit() {
+        let created = Arc::new(Mutex::new(vec![]));
+        let created_rounds = created.clone();
+        let conf = builder(4)
+            .on_created(move |pu: &PreUnit<Hash>| {
+                created_rounds
+                    .lock()
+                    .push((pu.round(), pu.control_hash.n_parents()))
+            })
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        for round in 0..10 {
+            if round > 0 {
+                feed_round(&mut creator, round - 1, &[0, 1, 2]);
+            }
+            assert_eq!(creator.create_unit(), Ok(true));
+            let pu = created_preunit(&mut rx);
+            assert_eq!(
+                created.lock().last(),
+                Some(&(pu.round(), pu.control_hash.n_parents()))
+            );
+        }
+        let rounds: Vec<_> = created.lock().iter().map(|(round, _)| *round).collect();
+        assert_eq!(rounds, (0..10).map(Round).collect::<Vec<_>>());
+    }
+
+    fn unit(creator: usize, round: usize, n_members: usize) -> Unit<Hash> {
+        let pu = PreUnit::new_from_parents(
+            NodeIndex::from_usize(creator),
+            Round(round),
+            &NodeMap::new_with_len(NodeCount::from_usize(n_members)),
+            &hashing,
+        );
+        Unit::new_from_preunit(pu, Hash((round * 1000 + creator) as u32))
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn slow_consumer_does_not_miss_rounds() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for round in 0..10 {
+            for pid in 0..3 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        for round in 0..11 {
+            delay_for(Duration::from_millis(2)).await;
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[test]
+    fn zero_channel_capacity_is_rejected() {
+        let conf = Config::<Hash, NodeId>::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let result = Creator::builder(conf, coord_hashing as MockHashing)
+            .out_channel_capacity(0)
+            .build();
+        assert_eq!(result.err(), Some(CreatorError::ZeroChannelCapacity));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn exit_is_noticed_with_a_full_bounded_channel() {
+        let conf = builder(4).graceful_shutdown(true).build().unwrap();
+        let (mut creator, parents_tx, _rx) = Creator::builder(conf, coord_hashing as MockHashing)
+            .out_channel_capacity(1)
+            .build()
+            .unwrap();
+        let stats = creator.stats_handle();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for round in 0..3 {
+            for pid in 0..3 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        delay_for(Duration::from_millis(20)).await;
+        // nobody reads the notifications, but the Creator still waits for the exit signal
+        assert_eq!(stats.stats().created, 2);
+        let _ = exit_tx.send(());
+        assert_eq!(handle.await.unwrap(), Ok(()));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn full_bounded_channel_blocks_the_creator() {
+        let conf = Config::<Hash, NodeId>::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, parents_tx, mut rx) =
+            Creator::builder(conf, coord_hashing as MockHashing)
+                .out_channel_capacity(1)
+                .build()
+                .unwrap();
+        let stats = creator.stats_handle();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for round in 0..10 {
+            for pid in 0..3 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        delay_for(Duration::from_millis(20)).await;
+        // one unit in the channel and one waiting for room, with no further units created
+        assert_eq!(stats.stats().created, 2);
+        assert_eq!(stats.stats().current_round, Round(2));
+        for round in 0..11 {
+            delay_for(Duration::from_millis(2)).await;
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        assert_eq!(stats.stats().created, 11);
+        let _ = exit_tx.send(());
+        assert_eq!(handle.await.unwrap(), Ok(()));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn reached_rounds_are_awaited() {
+        let conf = Config::<Hash, NodeId>::new(0.into(), NodeCount(4), Duration::from_millis(1));
+        let (mut creator, _rx, parents_tx) = creator(conf);
+        let stats = creator.stats_handle();
+        let (reached_1, reached_3) = (creator.reached(Round(1)), creator.reached(Round(3)));
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for round in 0..10 {
+            for pid in 0..3 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        future::join(reached_3, reached_1).await;
+        assert!(stats.stats().created >= 4);
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn creator_stops_after_max_round() {
+        let conf = builder(4).max_round(Round(5)).build().unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (_exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            assert_eq!(creator.create(exit_rx).await, Ok(()));
+            creator
+        });
+        for round in 0..10 {
+            for pid in 0..4 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        let creator = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("the creator should stop on its own")
+            .unwrap();
+        let mut rounds = vec![];
+        loop {
+            match rx.try_recv() {
+                Ok(NotificationOut::CreatedPreUnit(pu)) => rounds.push(pu.round()),
+                Ok(NotificationOut::SessionComplete { last_round }) => {
+                    assert_eq!(last_round, Round(5));
+                    break;
+                }
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        assert_eq!(rounds, (0..=5).map(Round).collect::<Vec<_>>());
+        assert!(rx.try_recv().is_err());
+        assert_eq!(creator.current_round(), Round(6));
+    }
+
+    #[test]
+    fn no_unit_is_created_beyond_max_round() {
+        let conf = builder(4).max_round(Round(0)).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3]);
+        assert!(creator.check_ready());
+        assert_eq!(creator.create_unit(), Ok(false));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn first_unit_waits_for_initial_delay() {
+        let initial_delay = Duration::from_millis(100);
+        let conf = builder(4).initial_delay(initial_delay).build().unwrap();
+        let (mut creator, mut rx, _parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let start = Instant::now();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        delay_for(Duration::from_millis(20)).await;
+        assert!(rx.try_recv().is_err());
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        assert!(start.elapsed() >= initial_delay);
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn termination_is_the_last_notification() {
+        let (mut creator, mut rx, _parents_tx) = creator(builder(4).build().unwrap());
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        assert_eq!(rx.recv().await, Some(NotificationOut::Terminated));
+        assert_eq!(handle.await.unwrap(), Ok(()));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn observer_reports_termination() {
+        let conf = builder(4).observer(true).build().unwrap();
+        let (mut observer, mut rx, _parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { observer.create(exit_rx).await });
+        let _ = exit_tx.send(());
+        assert_eq!(rx.recv().await, Some(NotificationOut::Terminated));
+        let _ = handle.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn exit_cancels_initial_delay() {
+        let conf = builder(4)
+            .initial_delay(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        let _ = exit_tx.send(());
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("the creator should exit during the initial delay")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(rx.try_recv(), Ok(NotificationOut::Terminated)));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn create_lag_is_waited_for_between_units() {
+        let conf = builder(4)
+            .create_lag(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let mut harness = CreatorHarness::new(conf).await;
+        assert!(harness.step().await);
+        let rounds = |units: Vec<PreUnit<Hash>>| -> Vec<Round> {
+            units.iter().map(|pu| pu.round()).collect()
+        };
+        assert_eq!(rounds(harness.drain_created()), vec![Round(0)]);
+        let feed_round = |harness: &CreatorHarness, round: usize| {
+            for pid in 0..4 {
+                harness.feed_unit(
+                    Round(round),
+                    NodeIndex::from_usize(pid),
+                    Hash((1000 * round + pid) as u32),
+                );
+            }
+        };
+        feed_round(&harness, 0);
+        harness.step().await;
+        assert_eq!(rounds(harness.drain_created()), vec![Round(1)]);
+        feed_round(&harness, 1);
+        harness.advance(Duration::from_millis(99)).await;
+        assert!(harness.drain_created().is_empty());
+        harness.advance(Duration::from_millis(1)).await;
+        assert_eq!(rounds(harness.drain_created()), vec![Round(2)]);
+        feed_round(&harness, 2);
+        harness.advance(Duration::from_millis(100)).await;
+        assert_eq!(rounds(harness.drain_created()), vec![Round(3)]);
+        // the lag after the last unit does not hold up the exit signal
+        assert!(!harness.exit().await);
+        assert!(harness.drain_created().is_empty());
+    }
+
+    // A clock whose time only moves in advance.
+    #[derive(Clone)]
+    struct ManualClock(Arc<Mutex<ManualClockState>>);
+
+    struct ManualClockState {
+        now: Instant,
+        timers: Vec<(Instant, oneshot::Sender<()>)>,
+    }
+
+    impl ManualClock {
+        fn new() -> Self {
+            ManualClock(Arc::new(Mutex::new(ManualClockState {
+                now: Instant::now(),
+                timers: Vec::new(),
+            })))
+        }
+
+        fn advance(&self, duration: Duration) {
+            let mut state = self.0.lock();
+            state.now += duration;
+            let now = state.now;
+            let (due, pending) = state.timers.drain(..).partition(|(when, _)| *when <= now);
+            state.timers = pending;
+            for (_, tx) in due {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.0.lock().now
+        }
+
+        fn delay(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            let mut state = self.0.lock();
+            let (tx, rx) = oneshot::channel();
+            if duration == Duration::default() {
+                let _ = tx.send(());
+            } else {
+                let when = state.now + duration;
+                state.timers.push((when, tx));
+            }
+            Box::pin(rx.map(|_| ()))
+        }
+    }
+
+    #[tokio::test]
+    async fn create_lag_is_measured_on_the_clock() {
+        let clock = ManualClock::new();
+        let conf = builder(4)
+            .create_lag(Duration::from_secs(60))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for pid in 0..4 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        for round in 0..2 {
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        for pid in 0..4 {
+            let _ = parents_tx.send(unit(pid, 1, 4));
+        }
+        let _ = tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+        clock.advance(Duration::from_secs(59));
+        let _ = tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+        clock.advance(Duration::from_secs(1));
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(2)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        clock.advance(Duration::from_secs(60));
+        let _ = handle.await;
+    }
+
+    #[test]
+    fn health_reports_waiting_for_too_long() {
+        let clock = ManualClock::new();
+        let conf = builder(4)
+            .clock(clock.clone())
+            .stall_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        assert_eq!(creator.health(), CreatorHealth::Healthy);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(
+            creator.health(),
+            CreatorHealth::ParentStarved {
+                have: NodeCount(0),
+                need: NodeCount(3),
+            }
+        );
+        feed_round(&mut creator, 0, &[1, 2, 3]);
+        assert_eq!(
+            creator.health(),
+            CreatorHealth::OwnUnitMissing { round: Round(0) }
+        );
+        feed_round(&mut creator, 0, &[0]);
+        assert_eq!(creator.health(), CreatorHealth::Healthy);
+    }
+
+    #[test]
+    fn health_reports_backpressure() {
+        let (mut creator, _parents_tx, mut rx) =
+            Creator::builder(builder(4).build().unwrap(), coord_hashing as MockHashing)
+                .out_channel_capacity(1)
+                .build()
+                .unwrap();
+        assert_eq!(creator.create_unit(), Ok(true));
+        feed_round(&mut creator, 0, &[0, 1, 2]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(creator.health(), CreatorHealth::SendBackpressured);
+        // the waiting unit gets into the channel only once the running Creator delivers it
+        assert!(rx.try_recv().is_ok());
+        assert_eq!(creator.health(), CreatorHealth::SendBackpressured);
+    }
+
+    #[test]
+    fn jitter_keeps_lag_within_bounds() {
+        let create_lag = Duration::from_millis(100);
+        let jitter = Duration::from_millis(50);
+        let lags = || {
+            let conf = builder(4)
+                .create_lag(create_lag)
+                .jitter(jitter)
+                .rng_seed(7)
+                .build()
+                .unwrap();
+            let (mut creator, _, _) = creator(conf);
+            let now = Instant::now();
+            (0..100)
+                .map(|_| creator.lag_until_next_unit(now))
+                .collect::<Vec<_>>()
+        };
+        let sampled = lags();
+        assert!(sampled
+            .iter()
+            .all(|lag| *lag >= create_lag && *lag <= create_lag + jitter));
+        assert!(sampled.iter().any(|lag| *lag != sampled[0]));
+        assert_eq!(sampled, lags());
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_session() {
+        let session = |seed| {
+            let conf = builder(4)
+                .jitter(Duration::from_millis(50))
+                .rng_seed(seed)
+                .build()
+                .unwrap();
+            let (mut creator, mut rx, _) = creator(conf);
+            let now = Instant::now();
+            let mut encoded = Vec::new();
+            for round in 0..5 {
+                if round > 0 {
+                    feed_round(&mut creator, round - 1, &[0, 1, 2, 3]);
+                }
+                assert_eq!(creator.create_unit(), Ok(true));
+                encoded.push(created_preunit(&mut rx).encode());
+                encoded.push(creator.lag_until_next_unit(now).as_nanos().encode());
+            }
+            encoded
+        };
+        assert_eq!(session(3), session(3));
+        assert_ne!(session(3), session(4));
+    }
+
+    #[tokio::test]
+    async fn fixed_rate_keeps_cadence_despite_late_parents() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let times = Arc::new(Mutex::new(Vec::new()));
+        let (on_created_clock, on_created_times) = (clock.clone(), times.clone());
+        let conf = builder(4)
+            .lag_strategy(LagStrategy::FixedRate(Duration::from_secs(10)))
+            .clock(clock.clone())
+            .on_created(move |_| {
+                let elapsed = on_created_clock.now() - start;
+                on_created_times.lock().push(elapsed.as_secs());
+            })
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        let send_round = |round| {
+            for pid in 0..4 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        };
+        // as usual, there is no lag after the first unit
+        send_round(0);
+        for _ in 0..2 {
+            assert!(matches!(
+                rx.recv().await,
+                Some(NotificationOut::CreatedPreUnit(_))
+            ));
+        }
+        for round in 2..6 {
+            if round == 4 {
+                // the parents arrive 3 seconds after the unit is due
+                clock.advance(Duration::from_secs(10));
+                let _ = tokio::task::yield_now().await;
+                clock.advance(Duration::from_secs(3));
+                send_round(round - 1);
+            } else {
+                send_round(round - 1);
+                let _ = tokio::task::yield_now().await;
+                let due = start + Duration::from_secs(10 * (round as u64 - 1));
+                clock.advance(due - clock.now());
+            }
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        // a late unit does not shift the units after it
+        assert_eq!(*times.lock(), vec![0, 0, 10, 20, 33, 40]);
+        let _ = exit_tx.send(());
+        clock.advance(Duration::from_secs(60));
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn trigger_cuts_create_lag_short() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let conf = builder(4)
+            .create_lag(Duration::from_secs(60))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (creator, mut rx, parents_tx) = creator(conf);
+        let (trigger_tx, trigger_rx) = mpsc::unbounded_channel();
+        let mut creator = creator.with_trigger(trigger_rx);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        let send_round = |round| {
+            for pid in 0..4 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        };
+        send_round(0);
+        for round in 0..2 {
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        // waiting for the lag after the unit of round 1, but the parents are not there yet
+        let _ = trigger_tx.send(());
+        for _ in 0..3 {
+            let _ = tokio::task::yield_now().await;
+        }
+        assert!(rx.try_recv().is_err());
+        send_round(1);
+        for _ in 0..3 {
+            let _ = tokio::task::yield_now().await;
+        }
+        assert!(rx.try_recv().is_err());
+        let _ = trigger_tx.send(());
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(2)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        assert_eq!(clock.now(), start);
+        let _ = exit_tx.send(());
+        clock.advance(Duration::from_secs(60));
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn candidates_are_collected_while_waiting_for_the_lag() {
+        let clock = ManualClock::new();
+        let conf = builder(4)
+            .create_lag(Duration::from_secs(60))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (creator, mut rx, parents_tx) = creator(conf);
+        let (trigger_tx, trigger_rx) = mpsc::unbounded_channel();
+        let mut creator = creator.with_trigger(trigger_rx);
+        let stats = creator.stats_handle();
+        let watch = creator.round_watch();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for pid in 0..4 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        for round in 0..2 {
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        assert!(!watch.lag_blocked());
+        for pid in 0..4 {
+            let _ = parents_tx.send(unit(pid, 1, 4));
+        }
+        for _ in 0..3 {
+            let _ = tokio::task::yield_now().await;
+        }
+        // taken from the channel right away, though the unit waits for the lag
+        assert_eq!(stats.stats().candidates_seen, 8);
+        assert!(watch.lag_blocked());
+        clock.advance(Duration::from_secs(30));
+        for _ in 0..3 {
+            let _ = tokio::task::yield_now().await;
+        }
+        assert!(rx.try_recv().is_err());
+        let _ = trigger_tx.send(());
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => {
+                assert_eq!(pu.round(), Round(2));
+                assert_eq!(pu.n_parents(), NodeCount(4));
+            }
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        assert!(!watch.lag_blocked());
+        assert_eq!(watch.current_round(), Round(3));
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn min_parents_fall_back_to_threshold_after_grace() {
+        let clock = ManualClock::new();
+        let conf = builder(7)
+            .min_parents(NodeCount(6), Duration::from_secs(10))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        // only five members are around, one short of min_parents
+        for pid in 0..5 {
+            let _ = parents_tx.send(unit(pid, 0, 7));
+        }
+        for _ in 0..3 {
+            let _ = tokio::task::yield_now().await;
+        }
+        clock.advance(Duration::from_secs(9));
+        for _ in 0..3 {
+            let _ = tokio::task::yield_now().await;
+        }
+        assert!(rx.try_recv().is_err());
+        clock.advance(Duration::from_secs(1));
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => {
+                assert_eq!(pu.round(), Round(1));
+                assert_eq!(pu.control_hash.n_parents(), NodeCount(5));
+            }
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn round_watch_follows_running_creator() {
+        let (mut creator, mut rx, parents_tx) = creator(builder(4).build().unwrap());
+        let watch = creator.round_watch();
+        assert_eq!(watch.current_round(), Round(0));
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        let mut rounds = vec![];
+        while let Some(notification) = rx.recv().await {
+            if let NotificationOut::CreatedPreUnit(pu) = notification {
+                rounds.push(watch.current_round());
+                if pu.round() == Round(3) {
+                    break;
+                }
+                for pid in 0..4 {
+                    let _ = parents_tx.send(unit(pid, pu.round().0, 4));
+                }
+            }
+        }
+        assert_eq!(rounds, vec![Round(1), Round(2), Round(3), Round(4)]);
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn catching_up_skips_create_lag() {
+        let clock = ManualClock::new();
+        let conf = builder(4)
+            .create_lag(Duration::from_secs(60))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        for round in 0..20 {
+            for pid in 0..4 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let creation = tokio::spawn(async move { creator.create(exit_rx).await });
+        // all the units up to round 20 are created without the clock moving
+        for round in 0..=20 {
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        // caught up, so the create_lag applies again
+        for pid in 0..4 {
+            let _ = parents_tx.send(unit(pid, 20, 4));
+        }
+        let _ = tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+        clock.advance(Duration::from_secs(60));
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(21)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        clock.advance(Duration::from_secs(60));
+        let _ = creation.await;
+    }
+
+    #[tokio::test]
+    async fn watchdog_warns_when_no_unit_is_created() {
+        let clock = ManualClock::new();
+        let conf = builder(4)
+            .watchdog(Duration::from_secs(1), Duration::from_secs(5))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, _parents_tx) = creator(conf);
+        let handle = creator.stats_handle();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let creation = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        for _ in 0..5 {
+            clock.advance(Duration::from_secs(1));
+            let _ = tokio::task::yield_now().await;
+        }
+        assert_eq!(handle.stats().watchdog_warnings, 0);
+        clock.advance(Duration::from_secs(1));
+        let _ = tokio::task::yield_now().await;
+        assert_eq!(handle.stats().watchdog_warnings, 1);
+        let _ = exit_tx.send(());
+        let _ = creation.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn progress_is_logged_periodically() {
+        let clock = ManualClock::new();
+        let conf = builder(4)
+            .progress_log_interval(Duration::from_secs(2))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let handle = creator.stats_handle();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let creation = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let mut expected = 0;
+        for second in 1..=6 {
+            if second == 3 {
+                // progress does not change the cadence
+                for pid in 0..4 {
+                    let _ = parents_tx.send(unit(pid, 0, 4));
+                }
+            }
+            clock.advance(Duration::from_secs(1));
+            let _ = tokio::task::yield_now().await;
+            expected += (second % 2 == 0) as u64;
+            assert_eq!(handle.stats().progress_logs, expected);
+        }
+        assert_eq!(expected, 3);
+        let _ = exit_tx.send(());
+        let _ = creation.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn unused_candidates_are_evicted_after_ttl() {
+        let clock = ManualClock::new();
+        let conf = builder(4)
+            .candidate_ttl(Duration::from_secs(10))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let creation = tokio::spawn(async move { creator.create(exit_rx).await });
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(0)),
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = parents_tx.send(unit(0, 0, 4));
+        let _ = parents_tx.send(unit(1, 0, 4));
+        let _ = tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(11));
+        let _ = tokio::task::yield_now().await;
+        // the unit by 1 is gone, so these two are not enough on their own, but our own unit stays
+        let _ = parents_tx.send(unit(2, 0, 4));
+        let _ = tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+        let _ = parents_tx.send(unit(3, 0, 4));
+        match rx.recv().await {
+            Some(NotificationOut::CreatedPreUnit(pu)) => {
+                assert_eq!(
+                    pu.control_hash.parents,
+                    vec![true, false, true, true].into()
+                )
+            }
+            other => panic!("expected a created preunit, got {:?}", other),
+        }
+        let _ = exit_tx.send(());
+        let _ = creation.await;
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn notifications_can_be_streamed() {
+        let conf = builder(4).max_round(Round(2)).build().unwrap();
+        let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+        for round in 0..2 {
+            for pid in 0..4 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        let notifications: Vec<_> =
+            Creator::into_stream(conf, parents_rx, coord_hashing as MockHashing)
+                .unwrap()
+                .collect()
+                .await;
+        let rounds: Vec<_> = notifications
+            .iter()
+            .filter_map(|notification| match notification {
+                NotificationOut::CreatedPreUnit(pu) => Some(pu.round()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(rounds, vec![Round(0), Round(1), Round(2)]);
+        assert!(matches!(
+            notifications.last(),
+            Some(NotificationOut::SessionComplete {
+                last_round: Round(2)
+            })
+        ));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn stream_ends_when_parents_channel_closed() {
+        let conf = Config::<Hash, NodeId>::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+        for pid in 0..3 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        drop(parents_tx);
+        let stream = Creator::into_stream(conf, parents_rx, coord_hashing as MockHashing).unwrap();
+        let rounds: Vec<_> =
+            tokio::time::timeout(Duration::from_secs(5), stream.collect::<Vec<_>>())
+                .await
+                .expect("the stream should end on its own")
+                .into_iter()
+                .map(|notification| match notification {
+                    NotificationOut::CreatedPreUnit(pu) => pu.round(),
+                    other => panic!("expected a created preunit, got {:?}", other),
+                })
+                .collect();
+        assert_eq!(rounds, vec![Round(0), Round(1)]);
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn observer_follows_rounds_without_creating() {
+        let conf = builder(4).observer(true).build().unwrap();
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            assert_eq!(creator.create(exit_rx).await, Ok(()));
+            creator
+        });
+        for round in 0..5 {
+            for pid in 1..4 {
+                let _ = parents_tx.send(unit(pid, round, 4));
+            }
+        }
+        let _ = parents_tx.send(unit(1, 5, 4));
+        drop(parents_tx);
+        let creator = handle.await.unwrap();
+        drop(exit_tx);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(creator.stats().created, 0);
+        assert_eq!(creator.current_round(), Round(5));
+        assert_eq!(creator.core.n_candidates(Round(4)), NodeCount(3));
+        assert_eq!(
+            creator.frontier()[&Round(5)][NodeIndex(1)],
+            Some(Hash(5001))
+        );
+        assert_eq!(creator.frontier().keys().next().copied().unwrap(), Round(4));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn exit_signal_ends_creation_cleanly() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for pid in 0..4 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        for round in 0..2 {
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) => assert_eq!(pu.round(), Round(round)),
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        let _ = exit_tx.send(());
+        assert_eq!(handle.await.unwrap(), Ok(()));
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn creator_stops_when_channel_closed() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, rx, parents_tx) = creator(conf);
+        drop(rx);
+        let (_exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            assert_eq!(
+                creator.create(exit_rx).await,
+                Err(CreatorError::ChannelClosed)
+            );
+            creator
+        });
+        let creator = handle.await.unwrap();
+        assert_eq!(creator.current_round(), Round(0));
+        drop(parents_tx);
+    }
+
+    async fn n_parents_at_round_one(conf: Config<Hash, NodeId>) -> NodeCount {
+        let (mut creator, mut rx, parents_tx) = creator(conf);
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move { creator.create(exit_rx).await });
+        for pid in 0..3 {
+            let _ = parents_tx.send(unit(pid, 0, 4));
+        }
+        delay_for(Duration::from_millis(20)).await;
+        let _ = parents_tx.send(unit(3, 0, 4));
+        let mut n_parents = None;
+        while n_parents.is_none() {
+            match rx.recv().await {
+                Some(NotificationOut::CreatedPreUnit(pu)) if pu.round() == Round(1) => {
+                    n_parents = Some(pu.control_hash.n_parents())
+                }
+                Some(NotificationOut::CreatedPreUnit(_)) => {}
+                other => panic!("expected a created preunit, got {:?}", other),
+            }
+        }
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+        n_parents.unwrap()
+    }
+
+    #[tokio::test(max_threads = 1)]
+    async fn parent_wait_captures_more_parents() {
+        let conf = builder(4).build().unwrap();
+        assert_eq!(n_parents_at_round_one(conf).await, NodeCount(3));
+        let conf = builder(4)
+            .parent_wait(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        assert_eq!(n_parents_at_round_one(conf).await, NodeCount(4));
+    }
+
+    #[test]
+    fn parent_wait_happens_once_per_round() {
+        let conf = builder(4)
+            .parent_wait(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let (mut creator, _rx, _) = creator(conf);
+        assert_eq!(creator.start_parent_wait(), None);
+        assert_eq!(creator.create_unit(), Ok(true));
+        assert_eq!(creator.start_parent_wait(), Some(Duration::from_millis(10)));
+        assert_eq!(creator.start_parent_wait(), None);
+    }
+
+    /// A node id of someone outside of the committee.
+    #[derive(
+        Clone, Debug, derive_more::Display, PartialEq, Eq, Hash, codec::Encode, codec::Decode,
+    )]
+    #[display(fmt = "Outsider")]
+    struct Outsider;
+
+    impl crate::MyIndex for Outsider {
+        fn my_index(&self) -> Option<NodeIndex> {
+            None
+        }
+    }
+
+    #[test]
+    fn creator_without_index_is_rejected() {
+        let mut conf = Config::new(Outsider, NodeCount(4), Duration::from_millis(0));
+        let (_parents_tx, parents_rx) = mpsc::unbounded_channel::<Unit<Hash>>();
+        let (new_units_tx, _rx) = mpsc::unbounded_channel();
+        let result = Creator::new(conf.clone(), parents_rx, new_units_tx.clone(), hashing);
+        assert_eq!(result.err(), Some(CreatorError::NoIndex));
+        // an observer does not need an index
+        conf.observer = true;
+        let (_parents_tx, parents_rx) = mpsc::unbounded_channel::<Unit<Hash>>();
+        let mut observer = Creator::new(conf, parents_rx, new_units_tx, hashing).unwrap();
+        assert!(observer.add_unit(Round(0), NodeIndex(0), Hash(0)));
+        assert_eq!(observer.create_unit(), Ok(false));
+    }
+
+    #[test]
+    fn creator_with_index_out_of_range_is_rejected() {
+        let conf = Config::<Hash, NodeId>::new(4.into(), NodeCount(4), Duration::from_millis(0));
+        let (_parents_tx, parents_rx) = mpsc::unbounded_channel();
+        let (new_units_tx, _rx) = mpsc::unbounded_channel();
+        let result = Creator::new(conf, parents_rx, new_units_tx, hashing);
+        assert_eq!(
+            result.err(),
+            Some(CreatorError::IndexOutOfRange {
+                index: NodeIndex(4),
+                n_members: NodeCount(4),
+            })
+        );
+    }
+
+    #[test]
+    fn unit_by_unknown_creator_is_ignored() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, _rx, _) = creator(conf);
+        assert!(!creator.add_unit(Round(0), NodeIndex(4), Hash(4)));
+        assert!(!creator.add_unit(Round(0), NodeIndex::from_usize(usize::MAX), Hash(5)));
+        assert_eq!(creator.core.n_candidates(Round(0)), NodeCount(0));
+        assert!(creator.add_unit(Round(0), NodeIndex(3), Hash(3)));
+        assert_eq!(creator.core.n_candidates(Round(0)), NodeCount(1));
+    }
+
+    #[test]
+    fn unit_of_absurd_round_is_ignored() {
+        let conf = Config::new(0.into(), NodeCount(4), Duration::from_millis(0));
+        let (mut creator, _rx, _) = creator(conf);
+        assert!(!creator.add_unit(Round(usize::MAX), NodeIndex(1), Hash(1)));
+        assert!(!creator.add_unit(Round(DEFAULT_MAX_ROUND_LEAD + 1), NodeIndex(1), Hash(1)));
+        assert_eq!(creator.frontier().len(), 1);
+        assert!(creator.add_unit(Round(DEFAULT_MAX_ROUND_LEAD), NodeIndex(1), Hash(1)));
+        assert_eq!(creator.frontier().len(), DEFAULT_MAX_ROUND_LEAD + 1);
+    }
+
+    #[test]
+    fn early_units_are_kept_until_needed() {
+        let conf = builder(4).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 0, &[0, 1, 2, 3]);
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        assert_eq!(creator.current_round(), Round(2));
+        // the units of the others arrive well before we need them
+        feed_round(&mut creator, 3, &[1, 2, 3]);
+        feed_round(&mut creator, 5, &[1, 2, 3]);
+        for round in 1..3 {
+            feed_round(&mut creator, round, &[0, 1, 2, 3]);
+            assert_eq!(creator.create_unit(), Ok(true));
+            created_preunit(&mut rx);
+        }
+        assert_eq!(creator.current_round(), Round(4));
+        // only our own unit of round 3 is missing
+        assert_eq!(creator.missing_parents(Round(3)), vec![NodeIndex(0)]);
+        feed_round(&mut creator, 3, &[0]);
+        assert_eq!(creator.try_create_unit(), Ok(true));
+        assert_eq!(
+            created_preunit(&mut rx).control_hash.n_parents(),
+            NodeCount(4)
+        );
+        feed_round(&mut creator, 4, &[0, 1, 2]);
+        assert_eq!(creator.try_create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        feed_round(&mut creator, 5, &[0]);
+        assert_eq!(creator.try_create_unit(), Ok(true));
+        let pu = created_preunit(&mut rx);
+        assert_eq!(pu.round(), Round(6));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(4));
+    }
+
+    #[test]
+    fn round_lead_is_configurable() {
+        let conf = builder(4).max_round_lead(10).build().unwrap();
+        let (mut creator, mut rx, _) = creator(conf);
+        assert!(!creator.add_unit(Round(u32::MAX as usize), NodeIndex(1), Hash(1)));
+        assert!(!creator.add_unit(Round(11), NodeIndex(1), Hash(1)));
+        assert_eq!(creator.frontier().len(), 1);
+        assert!(creator.add_unit(Round(10), NodeIndex(1), Hash(1)));
+        assert_eq!(creator.frontier().len(), 11);
+        // the window moves along with the current round
+        assert_eq!(creator.create_unit(), Ok(true));
+        created_preunit(&mut rx);
+        assert!(creator.add_unit(Round(11), NodeIndex(1), Hash(1)));
+        assert!(!creator.add_unit(Round(12), NodeIndex(1), Hash(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "dropped our own unit")]
+    fn selector_cannot_drop_own_unit() {
+        let conf = builder(4)
+            .parent_selector(OthersOnlySelector)
+            .build()
+            .unwrap();
+        let (mut creator, _rx, _) = creator(conf);
+        assert_eq!(creator.create_unit(), Ok(true));
+        feed_round(&mut creator, 0, &[0, 1, 2, 3]);
+        assert_eq!(creator.create_unit(), Ok(true));
+    }
 }
@@ -0,0 +1,1625 @@
+//! The bookkeeping of the Creator without any runtime: the candidates for parents, the readiness
+//! to create a unit and the assembly of its parents. Nothing here waits or spawns anything, so
+//! that [CreatorCore] can be driven by explicit calls wherever the async runtime of the
+//! [crate::Creator], the tokio driver around it, is not wanted. The crate as a whole still needs
+//! `std`.
+use crate::{
+    nodes::{NodeCount, NodeIndex, NodeMap},
+    rounds::RoundWindow,
+    HashT, Hasher, ParentOrdering, PreUnit, Round, DEFAULT_MAX_ROUND_LEAD,
+};
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    vec::Vec,
+};
+use codec::Encode;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A strategy for choosing the parents of a new unit of the given round out of the candidates
+/// available from the previous round. The returned map must contain the candidate created by
+/// `own_index` -- the Creator asserts this after every selection.
+pub trait ParentSelector<H: HashT>: Send + Sync {
+    fn select(
+        &self,
+        round: Round,
+        candidates: &NodeMap<Option<H>>,
+        own_index: NodeIndex,
+    ) -> NodeMap<Option<H>>;
+}
+
+/// The default strategy: take every candidate that is available.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GreedySelector;
+
+impl<H: HashT> ParentSelector<H> for GreedySelector {
+    fn select(
+        &self,
+        _round: Round,
+        candidates: &NodeMap<Option<H>>,
+        _own_index: NodeIndex,
+    ) -> NodeMap<Option<H>> {
+        candidates.clone()
+    }
+}
+
+/// Where the hashes of the candidates for parents are kept, see
+/// [crate::Creator::with_candidate_store], e.g., on disk for committees too large for keeping the
+/// candidates of all the kept rounds in memory. The core keeps only their weights and arrivals
+/// per round, and asks the store for the candidates themselves. The rounds the core drops are
+/// pruned from the store as well, so it never has to keep more rounds than the core does.
+pub trait CandidateStore<H: HashT>: Send + Sync {
+    /// The candidate of the given round created by `pid`, if any.
+    fn get(&self, round: Round, pid: NodeIndex) -> Option<H>;
+
+    /// Keeps the candidate of the given round created by `pid`, replacing the previous one.
+    fn put(&mut self, round: Round, pid: NodeIndex, hash: H);
+
+    /// Drops the candidate of the given round created by `pid`, if any.
+    fn remove(&mut self, round: Round, pid: NodeIndex);
+
+    /// The number of candidates of the given round.
+    fn count(&self, round: Round) -> NodeCount;
+
+    /// Drops the candidates of the rounds before the given one.
+    fn prune(&mut self, before: Round);
+
+    /// Drops all the candidates. The next ones are of a committee of `n_members` members.
+    fn reset(&mut self, n_members: NodeCount);
+
+    /// The candidates of the given round, one entry for every member of the committee of
+    /// `n_members` members.
+    fn round(&self, round: Round, n_members: NodeCount) -> NodeMap<Option<H>> {
+        (0..n_members.as_usize())
+            .map(NodeIndex::from_usize)
+            .map(|pid| (pid, self.get(round, pid)))
+            .collect()
+    }
+
+    /// The candidates of the given round, if the store keeps them in memory as they are, so that
+    /// they can be borrowed rather than assembled with [CandidateStore::round].
+    fn borrow_round(&self, _round: Round) -> Option<&NodeMap<Option<H>>> {
+        None
+    }
+}
+
+/// The default store, keeping the candidates in memory.
+#[derive(Clone, Debug)]
+pub struct InMemoryStore<H: HashT> {
+    n_members: NodeCount,
+    rounds: BTreeMap<Round, (NodeMap<Option<H>>, NodeCount)>,
+}
+
+impl<H: HashT> InMemoryStore<H> {
+    pub fn new(n_members: NodeCount) -> Self {
+        InMemoryStore {
+            n_members,
+            rounds: BTreeMap::new(),
+        }
+    }
+}
+
+impl<H: HashT> CandidateStore<H> for InMemoryStore<H> {
+    fn get(&self, round: Round, pid: NodeIndex) -> Option<H> {
+        if pid.0 >= self.n_members.0 {
+            return None;
+        }
+        self.rounds.get(&round).and_then(|(hashes, _)| hashes[pid])
+    }
+
+    fn put(&mut self, round: Round, pid: NodeIndex, hash: H) {
+        let n_members = self.n_members;
+        let (hashes, count) = self
+            .rounds
+            .entry(round)
+            .or_insert_with(|| (NodeMap::new_with_len(n_members), NodeCount(0)));
+        if hashes[pid].replace(hash).is_none() {
+            *count += NodeCount(1);
+        }
+    }
+
+    fn remove(&mut self, round: Round, pid: NodeIndex) {
+        if let Some((hashes, count)) = self.rounds.get_mut(&round) {
+            if hashes[pid].take().is_some() {
+                *count -= NodeCount(1);
+            }
+        }
+    }
+
+    fn count(&self, round: Round) -> NodeCount {
+        self.rounds
+            .get(&round)
+            .map_or(NodeCount(0), |(_, count)| *count)
+    }
+
+    fn prune(&mut self, before: Round) {
+        self.rounds = self.rounds.split_off(&before);
+    }
+
+    fn reset(&mut self, n_members: NodeCount) {
+        self.n_members = n_members;
+        self.rounds.clear();
+    }
+
+    fn round(&self, round: Round, n_members: NodeCount) -> NodeMap<Option<H>> {
+        self.borrow_round(round)
+            .cloned()
+            .unwrap_or_else(|| NodeMap::new_with_len(n_members))
+    }
+
+    fn borrow_round(&self, round: Round) -> Option<&NodeMap<Option<H>>> {
+        self.rounds.get(&round).map(|(hashes, _)| hashes)
+    }
+}
+
+/// Which candidates the max_parents limit keeps when there are more of them than allowed, see
+/// [crate::ConfigBuilder::parent_preference]. Our own unit is kept either way.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ParentPreference {
+    /// The candidates created by the members of the lowest indices.
+    #[default]
+    LowestIndex,
+    /// The candidates that arrived most recently, likely created by the healthiest members.
+    MostRecent,
+}
+
+/// Decides how many parents a unit of a committee of `n_members` members needs. Every policy
+/// must require a strict majority of the committee, i.e., more than N/2 parents, otherwise two
+/// disjoint sets of members could build on their own units only -- the Creator asserts this in
+/// debug builds.
+pub trait ThresholdPolicy: Send + Sync {
+    fn required(&self, n_members: NodeCount) -> NodeCount;
+}
+
+/// The default policy: a unit needs more than floor(2*N/3) parents.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TwoThirdsPolicy;
+
+impl ThresholdPolicy for TwoThirdsPolicy {
+    fn required(&self, n_members: NodeCount) -> NodeCount {
+        n_members.threshold_two_thirds() + NodeCount(1)
+    }
+}
+
+/// A snapshot of the progress of a [crate::Creator], allowing to restore it after a restart
+/// without creating again units for rounds that were already created.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CreatorState<H: HashT> {
+    pub(crate) current_round: Round,
+    pub(crate) first_round: Round,
+    pub(crate) candidates_by_round: Vec<NodeMap<Option<H>>>,
+    pub(crate) n_candidates_by_round: Vec<NodeCount>,
+    /// The hashes of our own units of the kept rounds, absent from snapshots of older versions.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) own_hashes: Vec<(Round, H)>,
+}
+
+impl<H: HashT> CreatorState<H> {
+    /// The round of the next unit to be created.
+    pub fn current_round(&self) -> Round {
+        self.current_round
+    }
+}
+
+/// The progress of a [crate::Creator] at and above the `base` round, see
+/// [crate::Creator::snapshot_since], e.g., to keep a standby replica in sync without sending
+/// full snapshots. The candidates of the rounds at and above the base are carried in full, the
+/// older ones are left as the replica has them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CreatorDelta<H: HashT> {
+    pub(crate) base: Round,
+    pub(crate) current_round: Round,
+    pub(crate) first_round: Round,
+    pub(crate) candidates_by_round: Vec<(Round, NodeMap<Option<H>>)>,
+    pub(crate) own_hashes: Vec<(Round, H)>,
+}
+
+impl<H: HashT> CreatorDelta<H> {
+    /// The first round whose candidates the delta carries.
+    pub fn base(&self) -> Round {
+        self.base
+    }
+
+    /// The round of the next unit to be created.
+    pub fn current_round(&self) -> Round {
+        self.current_round
+    }
+}
+
+// What we know of the units of some round apart from their hashes, which are kept in the
+// CandidateStore, at most one per creator. These are the candidates for the parents of our unit of
+// the next round.
+#[derive(Clone, Debug, PartialEq)]
+struct RoundCandidates {
+    // the summed weight of the candidates, kept only with weights
+    weight: u128,
+    // when the candidates arrived, as the values of a counter increasing with every arrival, zero
+    // for the candidates of a restored snapshot
+    arrivals: NodeMap<u64>,
+}
+
+impl RoundCandidates {
+    fn new(n_members: NodeCount) -> Self {
+        RoundCandidates {
+            weight: 0,
+            arrivals: NodeMap::new_with_len(n_members),
+        }
+    }
+}
+
+// The summed weight of the present candidates.
+fn weight_of<H>(weights: &NodeMap<u64>, hashes: &NodeMap<Option<H>>) -> u128 {
+    hashes.present().map(|(pid, _)| weights[pid] as u128).sum()
+}
+
+// The smallest weight that is more than 2/3 of the total, i.e., floor(2*W/3) + 1.
+fn needed_weight(weights: &NodeMap<u64>) -> u128 {
+    let total: u128 = weights.iter().map(|weight| *weight as u128).sum();
+    total * 2 / 3 + 1
+}
+
+/// Whether a Creator can create a unit of some round, see [crate::Creator::can_create].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CreateReadiness {
+    Ready,
+    /// Fewer than `need` units of the previous round are available.
+    NotEnoughParents {
+        have: NodeCount,
+        need: NodeCount,
+    },
+    /// The units of the previous round that are available weigh less than `need` in total, see
+    /// [crate::ConfigBuilder::weights].
+    NotEnoughWeight {
+        have: u128,
+        need: u128,
+    },
+    /// There are enough units of the previous round, but our own one is not among them.
+    MissingOwnParent,
+}
+
+impl CreateReadiness {
+    pub fn is_ready(&self) -> bool {
+        *self == CreateReadiness::Ready
+    }
+}
+
+/// What became of a unit passed to [CreatorCore::add_unit].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddOutcome<H: HashT> {
+    /// The unit became a candidate.
+    Added,
+    /// The unit is already a candidate, or it is a fork or an impostor already reported.
+    Known,
+    /// The unit is too old to become a parent of any of our next units.
+    Stale,
+    /// The unit is too far ahead of the current round, see
+    /// [crate::ConfigBuilder::max_round_lead].
+    TooFarAhead,
+    /// The creator of the unit is not a member of the committee.
+    UnknownCreator,
+    /// The unit is of a round after the current one, and its creator already gave us as many
+    /// such candidates as allowed while our next unit is of the current round, see
+    /// [crate::ConfigBuilder::max_units_per_creator].
+    RateLimited,
+    /// The creator already produced a different unit of this round, the one with hash `first`,
+    /// which remains the candidate. Reported once for every round and creator.
+    Fork { first: H },
+    /// The unit claims to be ours, but we created the one with hash `expected`. Reported once for
+    /// every round.
+    Impostor { expected: H },
+}
+
+/// The reasons for which [crate::Creator::reconfigure] may fail. The Creator is left unchanged
+/// then.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReconfigureError {
+    /// The new member count is zero.
+    ZeroMembers,
+    /// The new index is not smaller than the new member count.
+    NodeIndexOutOfRange {
+        index: NodeIndex,
+        n_members: NodeCount,
+    },
+    /// Candidates for the parents of the next unit were already received, the earliest of them
+    /// at the given round.
+    MidRound(Round),
+    /// The Creator weighs the members, but the number of weights differs from the new member
+    /// count.
+    WeightsLength {
+        n_weights: NodeCount,
+        n_members: NodeCount,
+    },
+}
+
+impl Display for ReconfigureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ReconfigureError::ZeroMembers => write!(f, "the member count is zero"),
+            ReconfigureError::NodeIndexOutOfRange { index, n_members } => write!(
+                f,
+                "node index {} out of range for {} members",
+                index.0, n_members.0
+            ),
+            ReconfigureError::MidRound(round) => {
+                write!(f, "candidates of round {} were already received", round)
+            }
+            ReconfigureError::WeightsLength {
+                n_weights,
+                n_members,
+            } => write!(f, "{} weights for {} members", n_weights.0, n_members.0),
+        }
+    }
+}
+
+/// The reasons for which [crate::Creator::apply_delta] may reject a delta. The Creator is left
+/// unchanged then, and has to be restored from a full snapshot instead if the delta is not
+/// followed by a fitting one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeltaError {
+    /// The delta starts after the current round, so the rounds in between were missed.
+    Gap { base: Round, current_round: Round },
+    /// The delta is older than the current progress, whose round it would take back.
+    Outdated {
+        current_round: Round,
+        delta_round: Round,
+    },
+    /// The candidates of the delta are of a committee of a different size.
+    MembersMismatch {
+        n_members: NodeCount,
+        delta_members: NodeCount,
+    },
+}
+
+impl Display for DeltaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            DeltaError::Gap {
+                base,
+                current_round,
+            } => write!(
+                f,
+                "delta since round {} does not follow round {}",
+                base, current_round
+            ),
+            DeltaError::Outdated {
+                current_round,
+                delta_round,
+            } => write!(
+                f,
+                "delta at round {} is older than round {}",
+                delta_round, current_round
+            ),
+            DeltaError::MembersMismatch {
+                n_members,
+                delta_members,
+            } => write!(
+                f,
+                "delta for {} members applied to {} members",
+                delta_members, n_members
+            ),
+        }
+    }
+}
+
+/// Why a snapshot could not be restored with [CreatorCore::restore].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    /// The candidates of the snapshot are of a committee of a different size.
+    MembersMismatch {
+        n_members: NodeCount,
+        snapshot_members: NodeCount,
+    },
+    /// The candidates of the snapshot start after the round of the parents of its next unit.
+    FirstRoundAhead {
+        first_round: Round,
+        current_round: Round,
+    },
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SnapshotError::MembersMismatch {
+                n_members,
+                snapshot_members,
+            } => write!(
+                f,
+                "snapshot for {} members restored for {} members",
+                snapshot_members, n_members
+            ),
+            SnapshotError::FirstRoundAhead {
+                first_round,
+                current_round,
+            } => write!(
+                f,
+                "snapshot candidates from round {} do not cover the parents of round {}",
+                first_round, current_round
+            ),
+        }
+    }
+}
+
+/// The candidates for parents collected by a Creator, together with the round of its next unit.
+/// It decides when a unit can be created and out of which parents, but it neither waits nor
+/// sends anything: units are passed to it with [CreatorCore::add_unit] and the next unit is
+/// created with [CreatorCore::step], by whoever drives it.
+pub struct CreatorCore<H: HashT> {
+    n_members: NodeCount,
+    // absent only for observers outside of the committee
+    index: Option<NodeIndex>,
+    current_round: Round, // current_round is the round number of our next unit
+    // Only the rounds starting from the base of the window are kept. Older rounds are of no use
+    // and get pruned.
+    candidates_by_round: RoundWindow<RoundCandidates>,
+    // the hashes of the candidates of the rounds above
+    store: Box<dyn CandidateStore<H>>,
+    // without a selector all the candidates become parents, like with the GreedySelector, but
+    // without copying them
+    parent_selector: Option<Arc<dyn ParentSelector<H>>>,
+    threshold_policy: Arc<dyn ThresholdPolicy>,
+    max_parents: Option<NodeCount>,
+    parent_preference: ParentPreference,
+    // the number of candidates that arrived so far, ordering their arrivals
+    n_arrivals: u64,
+    weights: Option<NodeMap<u64>>,
+    // more than 2/3 of the total weight, zero without weights
+    needed_weight: u128,
+    max_round_lead: usize,
+    max_units_per_creator: Option<usize>,
+    // whether our units coming back are checked against the hashes we computed for them
+    check_own_hashes: bool,
+    // the numbers of candidates of the rounds after the current one taken from every creator
+    // since the current round started
+    units_taken: NodeMap<usize>,
+    session_id: Option<u64>,
+    parent_ordering: ParentOrdering,
+    // the (round, creator) slots of the kept rounds for which a fork was already reported
+    reported_forks: BTreeSet<(Round, NodeIndex)>,
+    // the hashes of the units we created at the kept rounds
+    own_hashes: BTreeMap<Round, H>,
+    // the round and hash of the last unit we created, kept even once its round is pruned
+    own_head: Option<(Round, H)>,
+    // the members whose candidates are never chosen as parents
+    banned: BTreeSet<NodeIndex>,
+    // the number of units of round 0 by others needed before creating our own one
+    bootstrap_peers: Option<NodeCount>,
+}
+
+impl<H: HashT> CreatorCore<H> {
+    /// A core of the member with the given index, or of an observer without one, whose next unit
+    /// is of `start_round`. All the candidates become parents and a unit needs more than
+    /// floor(2*N/3) of them, unless configured otherwise with the methods below.
+    pub fn new(n_members: NodeCount, index: Option<NodeIndex>, start_round: Round) -> Self {
+        let mut core = CreatorCore {
+            n_members,
+            index,
+            current_round: start_round,
+            // the candidates for parents are taken from the round preceding start_round
+            candidates_by_round: RoundWindow::new(start_round.saturating_sub(1)),
+            store: Box::new(InMemoryStore::new(n_members)),
+            parent_selector: None,
+            threshold_policy: Arc::new(TwoThirdsPolicy),
+            max_parents: None,
+            parent_preference: ParentPreference::LowestIndex,
+            n_arrivals: 0,
+            weights: None,
+            needed_weight: 0,
+            max_round_lead: DEFAULT_MAX_ROUND_LEAD,
+            max_units_per_creator: None,
+            check_own_hashes: true,
+            units_taken: NodeMap::new_with_len(n_members),
+            session_id: None,
+            parent_ordering: ParentOrdering::ByIndex,
+            reported_forks: BTreeSet::new(),
+            own_hashes: BTreeMap::new(),
+            own_head: None,
+            banned: BTreeSet::new(),
+            bootstrap_peers: None,
+        };
+        core.init_round(start_round);
+        core
+    }
+
+    /// See [crate::ConfigBuilder::parent_selector].
+    pub fn with_parent_selector(mut self, parent_selector: Arc<dyn ParentSelector<H>>) -> Self {
+        self.parent_selector = Some(parent_selector);
+        self
+    }
+
+    /// Keeps the candidates in the given store from now on, see
+    /// [crate::Creator::with_candidate_store]. The candidates collected so far are moved into it.
+    pub fn set_candidate_store(&mut self, mut store: Box<dyn CandidateStore<H>>) {
+        store.reset(self.n_members);
+        for (round, _) in self.candidates_by_round.iter() {
+            for (pid, hash) in self.round_hashes(round).present() {
+                store.put(round, pid, *hash);
+            }
+        }
+        self.store = store;
+        self.verify_invariants();
+    }
+
+    /// See [crate::ConfigBuilder::threshold_policy].
+    pub fn with_threshold_policy(mut self, threshold_policy: Arc<dyn ThresholdPolicy>) -> Self {
+        self.threshold_policy = threshold_policy;
+        self
+    }
+
+    /// See [crate::ConfigBuilder::max_parents].
+    pub fn with_max_parents(mut self, max_parents: NodeCount) -> Self {
+        self.max_parents = Some(max_parents);
+        self
+    }
+
+    /// See [crate::ConfigBuilder::parent_preference].
+    pub fn with_parent_preference(mut self, parent_preference: ParentPreference) -> Self {
+        self.parent_preference = parent_preference;
+        self
+    }
+
+    /// See [crate::ConfigBuilder::weights]. There has to be a weight for every member, with a
+    /// positive total, as the builder checks.
+    pub fn with_weights(mut self, weights: NodeMap<u64>) -> Self {
+        self.needed_weight = needed_weight(&weights);
+        self.weights = Some(weights);
+        self
+    }
+
+    /// See [crate::ConfigBuilder::max_round_lead].
+    pub fn with_max_round_lead(mut self, max_round_lead: usize) -> Self {
+        self.max_round_lead = max_round_lead;
+        self
+    }
+
+    /// See [crate::ConfigBuilder::max_units_per_creator].
+    pub fn with_max_units_per_creator(mut self, max_units: usize) -> Self {
+        self.max_units_per_creator = Some(max_units);
+        self
+    }
+
+    /// See [crate::ConfigBuilder::skip_own_hash_check].
+    pub fn without_own_hash_check(mut self) -> Self {
+        self.check_own_hashes = false;
+        self
+    }
+
+    /// See [crate::ConfigBuilder::bootstrap_peers].
+    pub fn with_bootstrap_peers(mut self, bootstrap_peers: NodeCount) -> Self {
+        self.bootstrap_peers = Some(bootstrap_peers);
+        self
+    }
+
+    /// See [crate::ConfigBuilder::session_id].
+    pub fn with_session_id(mut self, session_id: u64) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// See [crate::ConfigBuilder::parent_ordering].
+    pub fn with_parent_ordering(mut self, parent_ordering: ParentOrdering) -> Self {
+        self.parent_ordering = parent_ordering;
+        self
+    }
+
+    /// The round of the next unit to be created.
+    pub fn current_round(&self) -> Round {
+        self.current_round
+    }
+
+    pub fn n_members(&self) -> NodeCount {
+        self.n_members
+    }
+
+    pub fn index(&self) -> Option<NodeIndex> {
+        self.index
+    }
+
+    pub fn session_id(&self) -> Option<u64> {
+        self.session_id
+    }
+
+    pub fn parent_ordering(&self) -> ParentOrdering {
+        self.parent_ordering
+    }
+
+    // initializes the candidates of the given round (and all between if not there)
+    fn init_round(&mut self, round: Round) {
+        while self.candidates_by_round.end() <= round && !self.candidates_by_round.contains(round) {
+            let end = self.candidates_by_round.end();
+            let candidates = RoundCandidates::new(self.n_members);
+            self.candidates_by_round.insert(end, candidates);
+        }
+    }
+
+    // drops the candidates of rounds older than the previous one
+    fn prune_rounds(&mut self) {
+        if let Some(parents_round) = self.current_round.prev() {
+            self.candidates_by_round.advance_base(parents_round);
+        }
+        let first_round = self.candidates_by_round.base();
+        self.store.prune(first_round);
+        self.reported_forks
+            .retain(|(round, _)| *round >= first_round);
+        self.own_hashes.retain(|round, _| *round >= first_round);
+    }
+
+    /// Moves on to the next round without creating a unit of the current one.
+    pub fn advance_round(&mut self) {
+        self.current_round = self.current_round.next();
+        self.units_taken = NodeMap::new_with_len(self.n_members);
+        self.init_round(self.current_round);
+        self.prune_rounds();
+    }
+
+    /// Passes the unit of the given round created by `pid`, which becomes a candidate for the
+    /// parents of our unit of the next round unless the outcome says otherwise.
+    pub fn add_unit(&mut self, round: Round, pid: NodeIndex, hash: H) -> AddOutcome<H> {
+        if pid.0 >= self.n_members.0 {
+            return AddOutcome::UnknownCreator;
+        }
+        if round > self.current_round.saturating_add(self.max_round_lead) {
+            return AddOutcome::TooFarAhead;
+        }
+        // units that are too old are of no interest to us
+        if round.next() < self.current_round {
+            return AddOutcome::Stale;
+        }
+        if self.check_own_hashes && Some(pid) == self.index {
+            if let Some(own_hash) = self.own_hashes.get(&round).copied() {
+                if own_hash != hash {
+                    return match self.reported_forks.insert((round, pid)) {
+                        true => AddOutcome::Impostor { expected: own_hash },
+                        false => AddOutcome::Known,
+                    };
+                }
+            }
+        }
+        // This is also where duplicates are rejected. A separate set of recently seen units would
+        // only slow this down, as benches/duplicates.rs shows.
+        if let Some(first) = self.store.get(round, pid) {
+            if first != hash && self.reported_forks.insert((round, pid)) {
+                return AddOutcome::Fork { first };
+            }
+            return AddOutcome::Known;
+        }
+        // the units of the rounds we need now are never limited, only the ones ahead of them
+        let ahead = round > self.current_round;
+        if let Some(max_units) = self.max_units_per_creator {
+            if ahead && self.units_taken[pid] >= max_units {
+                return AddOutcome::RateLimited;
+            }
+        }
+        // Units of future rounds are kept right away, so none arriving early is lost. The memory
+        // this takes is bounded by the maximal round lead checked above.
+        self.init_round(round);
+        let weight = self
+            .weights
+            .as_ref()
+            .map_or(0, |weights| weights[pid] as u128);
+        // passing the check above means that we do not have any unit for the pair (round, pid) yet
+        self.n_arrivals += 1;
+        self.store.put(round, pid, hash);
+        let candidates = &mut self.candidates_by_round[round];
+        candidates.weight += weight;
+        candidates.arrivals[pid] = self.n_arrivals;
+        if ahead {
+            self.units_taken[pid] += 1;
+        }
+        self.verify_invariants();
+        AddOutcome::Added
+    }
+
+    /// Drops the candidate of the given round created by `pid`, e.g., as it was not used for too
+    /// long to be trusted anymore, so that it does not count towards the threshold until it
+    /// arrives again. Our own units are never dropped. Returns whether there was a candidate to
+    /// drop.
+    pub fn evict(&mut self, round: Round, pid: NodeIndex) -> bool {
+        if Some(pid) == self.index || self.candidate(round, pid).is_none() {
+            return false;
+        }
+        let weight = self
+            .weights
+            .as_ref()
+            .map_or(0, |weights| weights[pid] as u128);
+        self.store.remove(round, pid);
+        self.candidates_by_round[round].weight -= weight;
+        self.verify_invariants();
+        true
+    }
+
+    /// Recomputes the counts and weights of the candidates of every kept round and checks them
+    /// against the ones maintained along the way, panicking on any mismatch. This is done after
+    /// every change of the candidates, but only in debug builds.
+    pub fn verify_invariants(&self) {
+        // the store is asked for every kept round, which is not free even if nothing is checked
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        for (round, candidates) in self.candidates_by_round.iter() {
+            let hashes = self.round_hashes(round);
+            debug_assert_eq!(
+                hashes.len(),
+                self.n_members.as_usize(),
+                "candidates of round {:?} for a wrong number of members",
+                round
+            );
+            debug_assert_eq!(
+                self.store.count(round),
+                hashes.count_present(),
+                "candidates of round {:?} miscounted",
+                round
+            );
+            if let Some(weights) = &self.weights {
+                debug_assert_eq!(
+                    candidates.weight,
+                    weight_of(weights, &hashes),
+                    "weight of the candidates of round {:?} miscounted",
+                    round
+                );
+            }
+        }
+    }
+
+    /// The number of parents a unit needs according to the threshold policy. With weights, it is
+    /// their weight that matters instead.
+    pub fn required_parents(&self) -> NodeCount {
+        if self.weights.is_some() {
+            return NodeCount(0);
+        }
+        let required = self.threshold_policy.required(self.n_members);
+        debug_assert!(
+            required > self.n_members / 2,
+            "threshold policy requires {:?} parents out of {:?}, not a strict majority",
+            required,
+            self.n_members
+        );
+        required
+    }
+
+    /// Tells whether the candidates collected so far suffice for creating a unit of the given
+    /// round according to the threshold policy, and if not, why.
+    pub fn can_create(&self, round: Round) -> CreateReadiness {
+        self.readiness(round, self.required_parents())
+    }
+
+    /// Like [CreatorCore::can_create], but with `need` parents needed, e.g., more than the
+    /// threshold for a while. Rounds whose candidates are not kept anymore are reported as not
+    /// having any parents.
+    pub fn readiness(&self, round: Round, need: NodeCount) -> CreateReadiness {
+        self.readiness_with(round, need, true)
+    }
+
+    /// The candidates for the parents of our unit of the current round, apart from the banned
+    /// members, whether they suffice or not. There are none at round 0, and none if the
+    /// previous round is not kept.
+    pub fn available_parents(&self) -> NodeMap<Option<H>> {
+        let prev_round = self
+            .current_round
+            .prev()
+            .filter(|prev_round| self.candidates_by_round.contains(*prev_round));
+        match prev_round {
+            Some(prev_round) => self.without_banned(&self.round_hashes(prev_round)),
+            None => NodeMap::new_with_len(self.n_members),
+        }
+    }
+
+    /// Whether we have enough candidates for creating a unit of the given round, with `need`
+    /// parents needed, but only when counting the candidates of the banned members.
+    pub fn needs_banned_parents(&self, round: Round, need: NodeCount) -> bool {
+        !self.banned.is_empty()
+            && !self.readiness_with(round, need, true).is_ready()
+            && self.readiness_with(round, need, false).is_ready()
+    }
+
+    fn readiness_with(&self, round: Round, need: NodeCount, skip_banned: bool) -> CreateReadiness {
+        let prev_round = match round.prev() {
+            Some(prev_round) => prev_round,
+            None => return self.bootstrap_readiness(),
+        };
+        // To create a new unit, we need to have `need` parents available in previous round, by
+        // default >floor(2*N/3). With weights, the parents need to weigh more than 2/3 of the
+        // total instead. Additionally, our unit from previous round must be available. For N <= 3
+        // this means (by default) all the units of the previous round.
+        let candidates = match self.candidates_by_round.get(prev_round) {
+            Some(candidates) => candidates,
+            None => {
+                return CreateReadiness::NotEnoughParents {
+                    have: NodeCount(0),
+                    need,
+                }
+            }
+        };
+        if skip_banned && !self.banned.is_empty() {
+            let eligible = self.without_banned(&self.round_hashes(prev_round));
+            let weight = self
+                .weights
+                .as_ref()
+                .map_or(0, |weights| weight_of(weights, &eligible));
+            let own_parent = self.has_own(&eligible);
+            return self.judge(own_parent, eligible.count_present(), weight, need);
+        }
+        let own_parent = self
+            .index
+            .is_some_and(|own_index| self.store.get(prev_round, own_index).is_some());
+        self.judge(
+            own_parent,
+            self.store.count(prev_round),
+            candidates.weight,
+            need,
+        )
+    }
+
+    // Round 0 units have no parents, but with bootstrap_peers we still wait for that many units of
+    // round 0 by others, so as not to build rounds while nobody else is online.
+    fn bootstrap_readiness(&self) -> CreateReadiness {
+        let need = match self.bootstrap_peers {
+            Some(need) => need,
+            None => return CreateReadiness::Ready,
+        };
+        let mut have = self.n_candidates(Round(0));
+        if let Some(own_index) = self.index {
+            if self.candidate(Round(0), own_index).is_some() {
+                have -= NodeCount(1);
+            }
+        }
+        if have < need {
+            CreateReadiness::NotEnoughParents { have, need }
+        } else {
+            CreateReadiness::Ready
+        }
+    }
+
+    fn without_banned(&self, candidates: &NodeMap<Option<H>>) -> NodeMap<Option<H>> {
+        let mut eligible = candidates.clone();
+        for pid in &self.banned {
+            eligible[*pid] = None;
+        }
+        eligible
+    }
+
+    /// Never chooses the candidates of the given member as parents from now on, e.g., once it
+    /// was seen forking. A unit is then created only if there are enough parents without them.
+    /// Returns false, banning nobody, for our own index and for indices outside the committee.
+    pub fn ban(&mut self, pid: NodeIndex) -> bool {
+        if pid.0 >= self.n_members.0 || Some(pid) == self.index {
+            return false;
+        }
+        self.banned.insert(pid);
+        true
+    }
+
+    pub fn is_banned(&self, pid: NodeIndex) -> bool {
+        self.banned.contains(&pid)
+    }
+
+    /// Tells whether exactly the given parents, one for every member of the committee, would do
+    /// for our unit of the current round, by the same rules as the candidates do.
+    pub fn check_parents(&self, parents: &NodeMap<Option<H>>) -> CreateReadiness {
+        if self.current_round == Round(0) {
+            return CreateReadiness::Ready;
+        }
+        let weight = self
+            .weights
+            .as_ref()
+            .map_or(0, |weights| weight_of(weights, parents));
+        let need = self.required_parents();
+        self.judge(self.has_own(parents), parents.count_present(), weight, need)
+    }
+
+    // Whether the given parents include our own unit.
+    fn has_own(&self, parents: &NodeMap<Option<H>>) -> bool {
+        self.index
+            .is_some_and(|own_index| parents[own_index].is_some())
+    }
+
+    fn judge(
+        &self,
+        own_parent: bool,
+        have: NodeCount,
+        weight: u128,
+        need: NodeCount,
+    ) -> CreateReadiness {
+        if have < need {
+            CreateReadiness::NotEnoughParents { have, need }
+        } else if weight < self.needed_weight {
+            CreateReadiness::NotEnoughWeight {
+                have: weight,
+                need: self.needed_weight,
+            }
+        } else if !own_parent {
+            CreateReadiness::MissingOwnParent
+        } else {
+            CreateReadiness::Ready
+        }
+    }
+
+    /// Whether the unit of the current round can be created according to the threshold policy.
+    pub fn is_ready(&self) -> bool {
+        self.can_create(self.current_round).is_ready()
+    }
+
+    /// The parents of our unit of the current round, absent for observers. These are borrowed
+    /// straight from the candidates if the store keeps them in memory, unless a selector or the
+    /// max_parents limit picks only some of them. Whether there are enough of them is up to the
+    /// caller to check first.
+    pub fn parents(&self) -> Option<Cow<'_, NodeMap<Option<H>>>> {
+        let own_index = self.index?;
+        let prev_round = match self.current_round.prev() {
+            Some(prev_round) => prev_round,
+            None => return Some(Cow::Owned(NodeMap::new_with_len(self.n_members))),
+        };
+        let mut candidates = self.round_hashes(prev_round);
+        if !self.banned.is_empty() {
+            candidates = Cow::Owned(self.without_banned(&candidates));
+        }
+        let parents = match &self.parent_selector {
+            Some(selector) => {
+                Cow::Owned(selector.select(self.current_round, &candidates, own_index))
+            }
+            None => candidates,
+        };
+        assert!(
+            parents[own_index].is_some(),
+            "the parent selector dropped our own unit from round {}",
+            prev_round
+        );
+        Some(self.cap_parents(parents, own_index))
+    }
+
+    // Drops parents above the max_parents limit, keeping our own unit and the lowest indices, or
+    // the most recent arrivals if preferred.
+    fn cap_parents<'a>(
+        &self,
+        parents: Cow<'a, NodeMap<Option<H>>>,
+        own_index: NodeIndex,
+    ) -> Cow<'a, NodeMap<Option<H>>> {
+        let max_parents = match self.max_parents {
+            // dropping parents could leave too little weight
+            Some(_) if self.weights.is_some() => return parents,
+            Some(max_parents) => max_parents.max(self.required_parents()),
+            None => return parents,
+        };
+        let mut capped = NodeMap::new_with_len(self.n_members);
+        capped[own_index] = parents[own_index];
+        // our own unit is always kept, so it takes one of the slots
+        let mut others: Vec<_> = parents
+            .present()
+            .filter(|(pid, _)| *pid != own_index)
+            .collect();
+        if self.parent_preference == ParentPreference::MostRecent {
+            let arrivals = self
+                .current_round
+                .prev()
+                .and_then(|prev_round| self.candidates_by_round.get(prev_round))
+                .map(|candidates| &candidates.arrivals);
+            // the sort is stable, so the lower indices go first among equal arrivals
+            others.sort_by_key(|(pid, _)| {
+                core::cmp::Reverse(arrivals.map_or(0, |arrivals| arrivals[*pid]))
+            });
+        }
+        for (pid, parent) in others.into_iter().take(max_parents.as_usize() - 1) {
+            capped[pid] = Some(*parent);
+        }
+        Cow::Owned(capped)
+    }
+
+    /// Records that our unit of the current round, with the given hash if it could be computed,
+    /// was created, and moves on to the next round.
+    pub fn unit_created(&mut self, own_hash: Option<H>) {
+        if let Some(own_hash) = own_hash {
+            self.own_hashes.insert(self.current_round, own_hash);
+            self.own_head = Some((self.current_round, own_hash));
+        }
+        self.advance_round();
+        self.verify_invariants();
+    }
+
+    /// Creates our unit of the current round if ready, and moves on to the next round. Nothing is
+    /// created if we are not ready, if we are an observer, or if hashing fails, in which case the
+    /// step can be retried later.
+    pub fn step(&mut self, hashing: &impl Hasher<H>) -> Option<PreUnit<H>> {
+        if !self.is_ready() {
+            return None;
+        }
+        let own_index = self.index?;
+        let preunit = {
+            let parents = self.parents()?;
+            PreUnit::try_new_from_parents(
+                own_index,
+                self.current_round,
+                &parents,
+                self.session_id,
+                self.parent_ordering,
+                hashing,
+            )
+            .ok()?
+        };
+        let own_hash = preunit.using_encoded(|bytes| hashing.try_hash(bytes)).ok();
+        self.unit_created(own_hash);
+        Some(preunit)
+    }
+
+    /// Moves past the rounds with enough units for the members to build on, as an observer does,
+    /// having no units of its own. Returns whether the current round changed.
+    pub fn follow_rounds(&mut self) -> bool {
+        let start_round = self.current_round;
+        let need = self.required_parents();
+        while self.n_candidates(self.current_round) >= need
+            && self.candidates_by_round[self.current_round].weight >= self.needed_weight
+        {
+            self.advance_round();
+        }
+        self.current_round != start_round
+    }
+
+    /// The number of candidates of the given round, zero for the rounds that are not kept.
+    pub fn n_candidates(&self, round: Round) -> NodeCount {
+        match self.candidates_by_round.contains(round) {
+            true => self.store.count(round),
+            false => NodeCount(0),
+        }
+    }
+
+    /// See [crate::Creator::missing_parents].
+    pub fn missing_parents(&self, round: Round) -> Vec<NodeIndex> {
+        if !self.candidates_by_round.contains(round) {
+            return Vec::new();
+        }
+        self.round_hashes(round)
+            .enumerate()
+            .filter(|(_, candidate)| candidate.is_none())
+            .map(|(pid, _)| pid)
+            .collect()
+    }
+
+    /// The candidate of the given round by the given creator, if any.
+    pub fn candidate(&self, round: Round, pid: NodeIndex) -> Option<H> {
+        if pid.0 >= self.n_members.0 || !self.candidates_by_round.contains(round) {
+            return None;
+        }
+        self.store.get(round, pid)
+    }
+
+    // The candidates of the given round, borrowed from the store if it keeps them in memory.
+    fn round_hashes(&self, round: Round) -> Cow<'_, NodeMap<Option<H>>> {
+        match self.store.borrow_round(round) {
+            Some(hashes) => Cow::Borrowed(hashes),
+            None => Cow::Owned(self.store.round(round, self.n_members)),
+        }
+    }
+
+    /// See [crate::Creator::frontier].
+    pub fn frontier(&self) -> BTreeMap<Round, NodeMap<Option<H>>> {
+        self.candidates_by_round
+            .iter()
+            .map(|(round, _)| (round, self.round_hashes(round).into_owned()))
+            .collect()
+    }
+
+    /// The hash of our unit of the given round, if we created it and the round is still kept.
+    pub fn own_hash(&self, round: Round) -> Option<&H> {
+        self.own_hashes.get(&round)
+    }
+
+    /// The round and hash of the last unit we created, if any.
+    pub fn own_head(&self) -> Option<(Round, H)> {
+        self.own_head
+    }
+
+    /// Takes a snapshot of the current progress, from which the core can be restored.
+    pub fn snapshot(&self) -> CreatorState<H> {
+        CreatorState {
+            current_round: self.current_round,
+            first_round: self.candidates_by_round.base(),
+            candidates_by_round: self
+                .candidates_by_round
+                .iter()
+                .map(|(round, _)| self.round_hashes(round).into_owned())
+                .collect(),
+            n_candidates_by_round: self
+                .candidates_by_round
+                .iter()
+                .map(|(round, _)| self.store.count(round))
+                .collect(),
+            own_hashes: self
+                .own_hashes
+                .iter()
+                .map(|(round, hash)| (*round, *hash))
+                .collect(),
+        }
+    }
+
+    /// The progress at and above the `base` round, see [crate::Creator::snapshot_since].
+    pub fn snapshot_since(&self, base: Round) -> CreatorDelta<H> {
+        CreatorDelta {
+            base,
+            current_round: self.current_round,
+            first_round: self.candidates_by_round.base(),
+            candidates_by_round: self
+                .candidates_by_round
+                .iter()
+                .filter(|(round, _)| *round >= base)
+                .map(|(round, _)| (round, self.round_hashes(round).into_owned()))
+                .collect(),
+            own_hashes: self
+                .own_hashes
+                .range(base..)
+                .map(|(round, hash)| (*round, *hash))
+                .collect(),
+        }
+    }
+
+    /// See [crate::Creator::apply_delta].
+    pub fn apply_delta(&mut self, delta: CreatorDelta<H>) -> Result<(), DeltaError> {
+        if delta.base > self.current_round {
+            return Err(DeltaError::Gap {
+                base: delta.base,
+                current_round: self.current_round,
+            });
+        }
+        if delta.current_round < self.current_round {
+            return Err(DeltaError::Outdated {
+                current_round: self.current_round,
+                delta_round: delta.current_round,
+            });
+        }
+        let mismatch = delta
+            .candidates_by_round
+            .iter()
+            .find(|(_, hashes)| hashes.len() != self.n_members.as_usize());
+        if let Some((_, hashes)) = mismatch {
+            return Err(DeltaError::MembersMismatch {
+                n_members: self.n_members,
+                delta_members: NodeCount::from_usize(hashes.len()),
+            });
+        }
+        let CreatorDelta {
+            base,
+            current_round,
+            first_round,
+            candidates_by_round,
+            own_hashes,
+        } = delta;
+        // the rounds below the base stay as they are, apart from the ones the source pruned
+        let mut kept = BTreeMap::new();
+        for (round, candidates) in self.candidates_by_round.iter() {
+            if round >= first_round && round < base {
+                let hashes = self.round_hashes(round).into_owned();
+                kept.insert(round, (hashes, candidates.clone()));
+            }
+        }
+        let weights = self.weights.as_ref();
+        for (round, hashes) in candidates_by_round {
+            if round >= first_round {
+                let candidates = RoundCandidates {
+                    weight: weights.map_or(0, |weights| weight_of(weights, &hashes)),
+                    arrivals: NodeMap::new_with_len(self.n_members),
+                };
+                kept.insert(round, (hashes, candidates));
+            }
+        }
+        let end = kept.keys().next_back().copied().unwrap_or(first_round);
+        let n_members = self.n_members;
+        self.store.reset(n_members);
+        let mut rounds = Vec::new();
+        let mut round = first_round;
+        while round <= end {
+            match kept.remove(&round) {
+                Some((hashes, candidates)) => {
+                    for (pid, hash) in hashes.present() {
+                        self.store.put(round, pid, *hash);
+                    }
+                    rounds.push(candidates);
+                }
+                None => rounds.push(RoundCandidates::new(n_members)),
+            }
+            if round == Round(usize::MAX) {
+                break;
+            }
+            round = round.next();
+        }
+        self.candidates_by_round = RoundWindow::from_values(first_round, rounds);
+        self.own_hashes
+            .retain(|round, _| *round >= first_round && *round < base);
+        self.own_hashes.extend(own_hashes);
+        if let Some((round, hash)) = self.own_hashes.iter().next_back() {
+            self.own_head = Some((*round, *hash));
+        }
+        self.reported_forks
+            .retain(|(round, _)| *round >= first_round);
+        if current_round != self.current_round {
+            self.units_taken = NodeMap::new_with_len(self.n_members);
+        }
+        self.current_round = current_round;
+        self.init_round(current_round);
+        self.verify_invariants();
+        Ok(())
+    }
+
+    /// Continues from the given snapshot, i.e., the next unit will be of round
+    /// `state.current_round()`. A snapshot of a committee of a different size, or one missing
+    /// the round of the parents of its next unit, is rejected, with the core left unchanged.
+    pub fn restore(&mut self, state: CreatorState<H>) -> Result<(), SnapshotError> {
+        let mismatch = state
+            .candidates_by_round
+            .iter()
+            .find(|hashes| hashes.len() != self.n_members.as_usize());
+        if let Some(hashes) = mismatch {
+            return Err(SnapshotError::MembersMismatch {
+                n_members: self.n_members,
+                snapshot_members: NodeCount::from_usize(hashes.len()),
+            });
+        }
+        let parents_round = state.current_round.prev().unwrap_or(state.current_round);
+        if state.first_round > parents_round {
+            return Err(SnapshotError::FirstRoundAhead {
+                first_round: state.first_round,
+                current_round: state.current_round,
+            });
+        }
+        let CreatorState {
+            current_round,
+            first_round,
+            candidates_by_round,
+            // the store counts the candidates on its own
+            n_candidates_by_round: _,
+            own_hashes,
+        } = state;
+        let weights = self.weights.as_ref();
+        self.store.reset(self.n_members);
+        let mut rounds = Vec::new();
+        for (round, hashes) in (first_round.0..).map(Round).zip(candidates_by_round) {
+            for (pid, hash) in hashes.present() {
+                self.store.put(round, pid, *hash);
+            }
+            rounds.push(RoundCandidates {
+                weight: weights.map_or(0, |weights| weight_of(weights, &hashes)),
+                arrivals: NodeMap::new_with_len(self.n_members),
+            });
+        }
+        self.candidates_by_round = RoundWindow::from_values(first_round, rounds);
+        self.own_hashes = own_hashes.into_iter().collect();
+        self.own_head = self
+            .own_hashes
+            .iter()
+            .next_back()
+            .map(|(round, hash)| (*round, *hash));
+        self.reported_forks.clear();
+        self.current_round = current_round;
+        self.units_taken = NodeMap::new_with_len(self.n_members);
+        self.init_round(current_round);
+        Ok(())
+    }
+
+    /// See [crate::Creator::reconfigure].
+    pub fn reconfigure(
+        &mut self,
+        n_members: NodeCount,
+        index: NodeIndex,
+    ) -> Result<(), ReconfigureError> {
+        if n_members == NodeCount(0) {
+            return Err(ReconfigureError::ZeroMembers);
+        }
+        if index.0 >= n_members.0 {
+            return Err(ReconfigureError::NodeIndexOutOfRange { index, n_members });
+        }
+        if let Some(weights) = &self.weights {
+            if weights.len() != n_members.as_usize() {
+                return Err(ReconfigureError::WeightsLength {
+                    n_weights: NodeCount::from_usize(weights.len()),
+                    n_members,
+                });
+            }
+        }
+        let parents_round = self.current_round.prev().unwrap_or(self.current_round);
+        let received = self
+            .candidates_by_round
+            .iter()
+            .find(|(round, _)| *round >= parents_round && self.store.count(*round) > NodeCount(0));
+        if let Some((round, _)) = received {
+            return Err(ReconfigureError::MidRound(round));
+        }
+        self.n_members = n_members;
+        self.units_taken = NodeMap::new_with_len(n_members);
+        self.index = Some(index);
+        self.candidates_by_round.reset(parents_round);
+        self.store.reset(n_members);
+        self.init_round(self.current_round);
+        // the units of the old committee are of no use anymore, and its indices mean other nodes
+        self.reported_forks.clear();
+        self.own_hashes.clear();
+        self.own_head = None;
+        self.banned.clear();
+        Ok(())
+    }
+
+    /// Forgets all the candidates and the bans, and starts anew at round 0.
+    pub fn reset(&mut self) {
+        self.current_round = Round(0);
+        self.n_arrivals = 0;
+        self.banned.clear();
+        self.units_taken = NodeMap::new_with_len(self.n_members);
+        self.candidates_by_round.reset(Round(0));
+        self.store.reset(self.n_members);
+        self.init_round(Round(0));
+        self.reported_forks.clear();
+        self.own_hashes.clear();
+        self.own_head = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock::{coord_hashing, Hash};
+
+    type MockHashing = fn(&[u8]) -> Hash;
+
+    fn core(n_members: usize) -> CreatorCore<Hash> {
+        CreatorCore::new(
+            NodeCount::from_usize(n_members),
+            Some(NodeIndex(0)),
+            Round(0),
+        )
+    }
+
+    fn feed_round(core: &mut CreatorCore<Hash>, round: usize, creators: &[usize]) {
+        for &pid in creators {
+            let hash = Hash((round * 1000 + pid) as u32);
+            assert_eq!(
+                core.add_unit(Round(round), NodeIndex::from_usize(pid), hash),
+                AddOutcome::Added
+            );
+        }
+    }
+
+    #[test]
+    fn step_creates_units_round_by_round() {
+        let mut core = core(4);
+        let hashing = coord_hashing as MockHashing;
+        let pu = core
+            .step(&hashing)
+            .expect("the unit of round 0 needs no parents");
+        assert_eq!(pu.round(), Round(0));
+        assert_eq!(core.current_round(), Round(0).next());
+        assert_eq!(core.own_hash(Round(0)), Some(&Hash(0)));
+        assert!(core.step(&hashing).is_none());
+        feed_round(&mut core, 0, &[0, 1]);
+        assert!(core.step(&hashing).is_none());
+        feed_round(&mut core, 0, &[3]);
+        let pu = core.step(&hashing).expect("three parents are enough");
+        assert_eq!(pu.round(), Round(1));
+        assert_eq!(pu.control_hash.n_parents(), NodeCount(3));
+        assert_eq!(core.current_round(), Round(2));
+    }
+
+    #[test]
+    fn readiness_tells_what_is_missing() {
+        let mut core = core(4);
+        core.unit_created(None);
+        assert_eq!(
+            core.can_create(Round(1)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(0),
+                need: NodeCount(3),
+            }
+        );
+        feed_round(&mut core, 0, &[1, 2, 3]);
+        assert_eq!(core.can_create(Round(1)), CreateReadiness::MissingOwnParent);
+        feed_round(&mut core, 0, &[0]);
+        assert!(core.is_ready());
+        assert_eq!(
+            core.readiness(Round(1), NodeCount(5)),
+            CreateReadiness::NotEnoughParents {
+                have: NodeCount(4),
+                need: NodeCount(5),
+            }
+        );
+        assert_eq!(core.missing_parents(Round(0)), vec![]);
+        assert_eq!(core.n_candidates(Round(0)), NodeCount(4));
+    }
+
+    #[test]
+    fn added_units_are_classified() {
+        let mut core = core(4).with_max_round_lead(10);
+        core.unit_created(Some(Hash(0)));
+        core.advance_round();
+        assert_eq!(core.current_round(), Round(2));
+        assert_eq!(
+            core.add_unit(Round(1), NodeIndex(4), Hash(1)),
+            AddOutcome::UnknownCreator
+        );
+        assert_eq!(
+            core.add_unit(Round(13), NodeIndex(1), Hash(1)),
+            AddOutcome::TooFarAhead
+        );
+        assert_eq!(
+            core.add_unit(Round(0), NodeIndex(1), Hash(1)),
+            AddOutcome::Stale
+        );
+        feed_round(&mut core, 1, &[1]);
+        assert_eq!(
+            core.add_unit(Round(1), NodeIndex(1), Hash(1001)),
+            AddOutcome::Known
+        );
+        // a fork is reported once, then it is as good as known
+        for outcome in [AddOutcome::Fork { first: Hash(1001) }, AddOutcome::Known] {
+            assert_eq!(core.add_unit(Round(1), NodeIndex(1), Hash(7)), outcome);
+        }
+        assert_eq!(core.candidate(Round(1), NodeIndex(1)), Some(Hash(1001)));
+    }
+
+    #[test]
+    fn impostors_are_reported_once() {
+        let mut core = core(4);
+        core.unit_created(Some(Hash(0)));
+        for outcome in [
+            AddOutcome::Impostor { expected: Hash(0) },
+            AddOutcome::Known,
+        ] {
+            assert_eq!(core.add_unit(Round(0), NodeIndex(0), Hash(9)), outcome);
+        }
+        assert_eq!(core.candidate(Round(0), NodeIndex(0)), None);
+        assert_eq!(
+            core.add_unit(Round(0), NodeIndex(0), Hash(0)),
+            AddOutcome::Added
+        );
+    }
+
+    #[test]
+    fn duplicates_and_forks_are_not_rate_limited() {
+        let mut core = core(4).with_max_units_per_creator(1);
+        assert_eq!(
+            core.add_unit(Round(1), NodeIndex(1), Hash(1001)),
+            AddOutcome::Added
+        );
+        assert_eq!(
+            core.add_unit(Round(2), NodeIndex(1), Hash(2001)),
+            AddOutcome::RateLimited
+        );
+        // the resent unit of round 1 is still known, and its fork still reported
+        assert_eq!(
+            core.add_unit(Round(1), NodeIndex(1), Hash(1001)),
+            AddOutcome::Known
+        );
+        assert_eq!(
+            core.add_unit(Round(1), NodeIndex(1), Hash(1009)),
+            AddOutcome::Fork { first: Hash(1001) }
+        );
+    }
+
+    #[test]
+    fn parents_are_capped() {
+        let mut core = core(7).with_max_parents(NodeCount(5));
+        core.unit_created(None);
+        feed_round(&mut core, 0, &[0, 1, 2, 3, 4, 5, 6]);
+        let parents = core.parents().unwrap();
+        assert_eq!(parents.count_present(), NodeCount(5));
+        assert!(parents[NodeIndex(0)].is_some());
+        assert!(parents[NodeIndex(6)].is_none());
+    }
+
+    #[test]
+    fn weights_decide_readiness() {
+        let mut core = core(4).with_weights(vec![1, 1, 1, 10].into());
+        core.unit_created(None);
+        feed_round(&mut core, 0, &[0, 1, 2]);
+        assert_eq!(
+            core.can_create(Round(1)),
+            CreateReadiness::NotEnoughWeight { have: 3, need: 9 }
+        );
+        feed_round(&mut core, 0, &[3]);
+        assert!(core.is_ready());
+    }
+
+    #[test]
+    fn invariants_hold_after_adding_units() {
+        let mut core = core(4).with_weights(vec![1, 2, 3, 4].into());
+        core.unit_created(None);
+        feed_round(&mut core, 0, &[0, 2, 3]);
+        feed_round(&mut core, 2, &[1]);
+        core.add_unit(Round(0), NodeIndex(2), Hash(7));
+        core.verify_invariants();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "miscounted")]
+    fn miscounted_candidates_are_caught() {
+        // claims one candidate more than it has
+        struct MiscountingStore(InMemoryStore<Hash>);
+
+        impl CandidateStore<Hash> for MiscountingStore {
+            fn get(&self, round: Round, pid: NodeIndex) -> Option<Hash> {
+                self.0.get(round, pid)
+            }
+
+            fn put(&mut self, round: Round, pid: NodeIndex, hash: Hash) {
+                self.0.put(round, pid, hash)
+            }
+
+            fn remove(&mut self, round: Round, pid: NodeIndex) {
+                self.0.remove(round, pid)
+            }
+
+            fn count(&self, round: Round) -> NodeCount {
+                self.0.count(round) + NodeCount(1)
+            }
+
+            fn prune(&mut self, before: Round) {
+                self.0.prune(before)
+            }
+
+            fn reset(&mut self, n_members: NodeCount) {
+                self.0.reset(n_members)
+            }
+        }
+
+        let mut core = core(4);
+        feed_round(&mut core, 0, &[0, 1]);
+        core.set_candidate_store(Box::new(MiscountingStore(InMemoryStore::new(NodeCount(4)))));
+    }
+
+    #[test]
+    fn observer_follows_rounds() {
+        let mut observer = CreatorCore::new(NodeCount(4), None, Round(0));
+        assert!(observer.step(&(coord_hashing as MockHashing)).is_none());
+        assert!(!observer.follow_rounds());
+        feed_round(&mut observer, 0, &[0, 1, 2]);
+        feed_round(&mut observer, 1, &[1, 2, 3]);
+        assert!(observer.follow_rounds());
+        assert_eq!(observer.current_round(), Round(2));
+        assert!(observer.parents().is_none());
+    }
+
+    #[test]
+    fn snapshot_restores_the_core() {
+        let mut core = core(4);
+        core.unit_created(Some(Hash(0)));
+        feed_round(&mut core, 0, &[0, 1, 2]);
+        feed_round(&mut core, 1, &[2]);
+        let state = core.snapshot();
+        let mut restored = self::core(4);
+        assert_eq!(restored.restore(state.clone()), Ok(()));
+        assert_eq!(restored.snapshot(), state);
+        assert_eq!(restored.current_round(), Round(1));
+        assert_eq!(restored.frontier(), core.frontier());
+        assert_eq!(restored.own_hash(Round(0)), Some(&Hash(0)));
+        assert!(restored.is_ready());
+    }
+
+    #[test]
+    fn reconfiguration_needs_a_round_boundary() {
+        let mut core = core(4);
+        core.unit_created(None);
+        assert_eq!(
+            core.reconfigure(NodeCount(7), NodeIndex(7)),
+            Err(ReconfigureError::NodeIndexOutOfRange {
+                index: NodeIndex(7),
+                n_members: NodeCount(7),
+            })
+        );
+        assert_eq!(core.reconfigure(NodeCount(7), NodeIndex(5)), Ok(()));
+        assert_eq!(core.n_members(), NodeCount(7));
+        assert_eq!(core.index(), Some(NodeIndex(5)));
+        feed_round(&mut core, 0, &[6]);
+        assert_eq!(
+            core.reconfigure(NodeCount(4), NodeIndex(0)),
+            Err(ReconfigureError::MidRound(Round(0)))
+        );
+        core.reset();
+        assert_eq!(core.current_round(), Round(0));
+        assert_eq!(core.n_candidates(Round(0)), NodeCount(0));
+    }
+}
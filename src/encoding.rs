@@ -0,0 +1,499 @@
+//! The wire format of units and of the notifications of the Creator. The round is encoded as a
+//! compact integer and the parents of a unit as a bitmask of N bits, so a unit takes O(N) bits
+//! rather than O(N) words.
+use bit_vec::BitVec;
+use codec::{Compact, Decode, Encode, Error as CodecError, Input, Output};
+
+use crate::{
+    nodes::{NodeCount, NodeIndex, NodeInt, NodeMap},
+    ControlHash, HashT, NotificationOut, PreUnit, Round, Unit,
+};
+
+impl<H: HashT> Encode for ControlHash<H> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        Compact(self.parents.len() as u32).encode_to(dest);
+        let bitmask: BitVec = self.parents.iter().copied().collect();
+        dest.write(&bitmask.to_bytes());
+        self.hash.encode_to(dest);
+    }
+}
+
+impl<H: HashT> Decode for ControlHash<H> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        ControlHash::decode_checked(input, None)
+    }
+}
+
+impl<H: HashT> ControlHash<H> {
+    // Decodes a control hash, failing if the number of parent slots is not the expected one.
+    fn decode_checked<I: Input>(
+        input: &mut I,
+        n_members: Option<NodeCount>,
+    ) -> Result<Self, CodecError> {
+        let len = Compact::<u32>::decode(input)?.0 as usize;
+        if let Some(n_members) = n_members {
+            if len != n_members.as_usize() {
+                return Err("unexpected number of parent slots".into());
+            }
+        }
+        let n_bytes = len.div_ceil(8);
+        // do not let a bogus length make us allocate more than the input could provide
+        if let Some(remaining) = input.remaining_len()? {
+            if remaining < n_bytes {
+                return Err("not enough data for the parents bitmask".into());
+            }
+        }
+        let mut bytes = vec![0u8; n_bytes];
+        input.read(&mut bytes)?;
+        let bitmask = BitVec::from_bytes(&bytes);
+        if bitmask.iter().skip(len).any(|bit| bit) {
+            return Err("non-zero padding of the parents bitmask".into());
+        }
+        let parents: NodeMap<bool> = bitmask.iter().take(len).collect();
+        let hash = H::decode(input)?;
+        Ok(ControlHash { parents, hash })
+    }
+}
+
+impl<H: HashT> Encode for PreUnit<H> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.creator.encode_to(dest);
+        Compact(self.round).encode_to(dest);
+        self.control_hash.encode_to(dest);
+        encode_count(self.n_parents, dest);
+        self.data.encode_to(dest);
+    }
+}
+
+impl<H: HashT> Decode for PreUnit<H> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        PreUnit::decode_checked(input, None)
+    }
+}
+
+impl<H: HashT> PreUnit<H> {
+    /// Decodes a preunit of a committee with `n_members` members, failing if the parents map
+    /// of the preunit is of a different size.
+    pub fn decode_with_len<I: Input>(
+        input: &mut I,
+        n_members: NodeCount,
+    ) -> Result<Self, CodecError> {
+        PreUnit::decode_checked(input, Some(n_members))
+    }
+
+    fn decode_checked<I: Input>(
+        input: &mut I,
+        n_members: Option<NodeCount>,
+    ) -> Result<Self, CodecError> {
+        let creator = NodeIndex::decode(input)?;
+        let round = Compact::<u64>::decode(input)?.0;
+        let control_hash = ControlHash::decode_checked(input, n_members)?;
+        let n_parents = decode_count(input)?;
+        if n_parents != control_hash.n_parents() {
+            return Err("parent count does not match the parents map".into());
+        }
+        let data = Vec::decode(input)?;
+        Ok(PreUnit {
+            creator,
+            round,
+            control_hash,
+            n_parents,
+            data,
+        })
+    }
+}
+
+impl<H: HashT> Encode for Unit<H> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.creator.encode_to(dest);
+        Compact(self.round).encode_to(dest);
+        self.hash.encode_to(dest);
+        self.control_hash.encode_to(dest);
+        self.data.encode_to(dest);
+    }
+}
+
+impl<H: HashT> Decode for Unit<H> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Unit::decode_checked(input, None)
+    }
+}
+
+impl<H: HashT> Unit<H> {
+    /// Decodes a unit of a committee with `n_members` members, failing if the parents map of
+    /// the unit is of a different size.
+    pub fn decode_with_len<I: Input>(
+        input: &mut I,
+        n_members: NodeCount,
+    ) -> Result<Self, CodecError> {
+        Unit::decode_checked(input, Some(n_members))
+    }
+
+    fn decode_checked<I: Input>(
+        input: &mut I,
+        n_members: Option<NodeCount>,
+    ) -> Result<Self, CodecError> {
+        let creator = NodeIndex::decode(input)?;
+        let round = Compact::<u64>::decode(input)?.0;
+        let hash = H::decode(input)?;
+        let control_hash = ControlHash::decode_checked(input, n_members)?;
+        let data = Vec::decode(input)?;
+        Ok(Unit {
+            creator,
+            round,
+            hash,
+            control_hash,
+            data,
+        })
+    }
+}
+
+fn encode_round<T: Output + ?Sized>(round: Round, dest: &mut T) {
+    Compact(round.0 as u64).encode_to(dest);
+}
+
+fn decode_round<I: Input>(input: &mut I) -> Result<Round, CodecError> {
+    Ok(Round(Compact::<u64>::decode(input)?.0 as usize))
+}
+
+fn encode_count<T: Output + ?Sized>(count: NodeCount, dest: &mut T) {
+    Compact(count.as_u64()).encode_to(dest);
+}
+
+fn decode_count<I: Input>(input: &mut I) -> Result<NodeCount, CodecError> {
+    Ok(NodeCount(Compact::<u64>::decode(input)?.0 as NodeInt))
+}
+
+// Notifications are encoded as a tag byte identifying the variant followed by its fields, with
+// rounds and counts as compact integers. To reject trailing bytes, decode them with
+// codec::DecodeAll.
+impl<H: HashT> Encode for NotificationOut<H> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        match self {
+            NotificationOut::CreatedPreUnit(preunit) => {
+                dest.push_byte(0);
+                preunit.encode_to(dest);
+            }
+            NotificationOut::SignedUnit {
+                preunit,
+                creator,
+                signature,
+            } => {
+                dest.push_byte(1);
+                preunit.encode_to(dest);
+                creator.encode_to(dest);
+                signature.encode_to(dest);
+            }
+            NotificationOut::CreatedUnitParents { round, parents } => {
+                dest.push_byte(2);
+                encode_round(*round, dest);
+                parents.encode_to(dest);
+            }
+            NotificationOut::MissingUnits(coords, aux_data) => {
+                dest.push_byte(3);
+                coords.encode_to(dest);
+                aux_data.encode_to(dest);
+            }
+            NotificationOut::CreatorStalled {
+                round,
+                have_parents,
+                needed,
+            } => {
+                dest.push_byte(4);
+                encode_round(*round, dest);
+                encode_count(*have_parents, dest);
+                encode_count(*needed, dest);
+            }
+            NotificationOut::ForkDetected {
+                round,
+                creator,
+                hash_a,
+                hash_b,
+            } => {
+                dest.push_byte(5);
+                encode_round(*round, dest);
+                creator.encode_to(dest);
+                hash_a.encode_to(dest);
+                hash_b.encode_to(dest);
+            }
+            NotificationOut::ImpostorUnit {
+                round,
+                expected,
+                received,
+            } => {
+                dest.push_byte(6);
+                encode_round(*round, dest);
+                expected.encode_to(dest);
+                received.encode_to(dest);
+            }
+            NotificationOut::SessionComplete { last_round } => {
+                dest.push_byte(7);
+                encode_round(*last_round, dest);
+            }
+            NotificationOut::OwnUnitMissing { round } => {
+                dest.push_byte(8);
+                encode_round(*round, dest);
+            }
+            NotificationOut::Terminated => dest.push_byte(9),
+            NotificationOut::BannedParentsNeeded { round } => {
+                dest.push_byte(10);
+                encode_round(*round, dest);
+            }
+            NotificationOut::ForcedUnit { round, n_parents } => {
+                dest.push_byte(11);
+                encode_round(*round, dest);
+                encode_count(*n_parents, dest);
+            }
+            NotificationOut::CatchupRequest { round, missing } => {
+                dest.push_byte(12);
+                encode_round(*round, dest);
+                missing.encode_to(dest);
+            }
+        }
+    }
+}
+
+impl<H: HashT> Decode for NotificationOut<H> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        match input.read_byte()? {
+            0 => Ok(NotificationOut::CreatedPreUnit(PreUnit::decode(input)?)),
+            1 => Ok(NotificationOut::SignedUnit {
+                preunit: PreUnit::decode(input)?,
+                creator: NodeIndex::decode(input)?,
+                signature: Decode::decode(input)?,
+            }),
+            2 => Ok(NotificationOut::CreatedUnitParents {
+                round: decode_round(input)?,
+                parents: Decode::decode(input)?,
+            }),
+            3 => Ok(NotificationOut::MissingUnits(
+                Decode::decode(input)?,
+                Decode::decode(input)?,
+            )),
+            4 => Ok(NotificationOut::CreatorStalled {
+                round: decode_round(input)?,
+                have_parents: decode_count(input)?,
+                needed: decode_count(input)?,
+            }),
+            5 => Ok(NotificationOut::ForkDetected {
+                round: decode_round(input)?,
+                creator: NodeIndex::decode(input)?,
+                hash_a: H::decode(input)?,
+                hash_b: H::decode(input)?,
+            }),
+            6 => Ok(NotificationOut::ImpostorUnit {
+                round: decode_round(input)?,
+                expected: H::decode(input)?,
+                received: H::decode(input)?,
+            }),
+            7 => Ok(NotificationOut::SessionComplete {
+                last_round: decode_round(input)?,
+            }),
+            8 => Ok(NotificationOut::OwnUnitMissing {
+                round: decode_round(input)?,
+            }),
+            9 => Ok(NotificationOut::Terminated),
+            10 => Ok(NotificationOut::BannedParentsNeeded {
+                round: decode_round(input)?,
+            }),
+            11 => Ok(NotificationOut::ForcedUnit {
+                round: decode_round(input)?,
+                n_parents: decode_count(input)?,
+            }),
+            12 => Ok(NotificationOut::CatchupRequest {
+                round: decode_round(input)?,
+                missing: Decode::decode(input)?,
+            }),
+            _ => Err("unknown notification tag".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::mock::{hashing, Hash},
+        RequestAuxData, Signature, UnitCoord,
+    };
+    use codec::DecodeAll;
+
+    fn preunit(n_members: usize) -> PreUnit<Hash> {
+        let parents = (0..n_members)
+            .map(|idx| {
+                if idx % 4 == 1 {
+                    None
+                } else {
+                    Some(Hash(idx as u32))
+                }
+            })
+            .collect();
+        PreUnit::new_from_parents(NodeIndex(2), Round(300), &parents, &hashing)
+            .with_data(vec![1, 2, 3])
+    }
+
+    #[test]
+    fn preunit_round_trips() {
+        let pu = preunit(10);
+        let encoded = pu.encode();
+        assert_eq!(PreUnit::decode(&mut &encoded[..]), Ok(pu.clone()));
+        assert_eq!(
+            PreUnit::decode_with_len(&mut &encoded[..], NodeCount(10)),
+            Ok(pu)
+        );
+    }
+
+    #[test]
+    fn preunit_carries_its_parent_count() {
+        let pu = preunit(10);
+        assert_eq!(pu.n_parents(), pu.control_hash().n_parents());
+        assert_eq!(pu.n_parents(), NodeCount(7));
+    }
+
+    #[test]
+    fn preunit_with_tampered_parent_count_is_rejected() {
+        let pu = preunit(10);
+        let mut tampered = pu.clone();
+        tampered.n_parents = NodeCount(10);
+        let encoded = tampered.encode();
+        assert_eq!(encoded.len(), pu.encode().len());
+        assert!(PreUnit::<Hash>::decode(&mut &encoded[..]).is_err());
+        assert!(PreUnit::<Hash>::decode_with_len(&mut &encoded[..], NodeCount(10)).is_err());
+    }
+
+    #[test]
+    fn unit_round_trips() {
+        let unit = Unit::new_from_preunit(preunit(10), Hash(77));
+        let encoded = unit.encode();
+        assert_eq!(Unit::decode(&mut &encoded[..]), Ok(unit.clone()));
+        assert_eq!(
+            Unit::decode_with_len(&mut &encoded[..], NodeCount(10)),
+            Ok(unit)
+        );
+    }
+
+    #[test]
+    fn encoding_is_compact() {
+        // creator, a two byte round, a one byte length, two bytes of bitmask, the hash, a one
+        // byte parent count and the length prefixed data
+        assert_eq!(preunit(10).encode().len(), 8 + 2 + 1 + 2 + 4 + 1 + 1 + 3);
+    }
+
+    #[test]
+    fn unexpected_length_is_rejected() {
+        let encoded = Unit::new_from_preunit(preunit(10), Hash(77)).encode();
+        assert!(Unit::<Hash>::decode_with_len(&mut &encoded[..], NodeCount(4)).is_err());
+        assert!(Unit::<Hash>::decode_with_len(&mut &encoded[..], NodeCount(11)).is_err());
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        let encoded = preunit(10).encode();
+        for len in 0..encoded.len() {
+            assert!(PreUnit::<Hash>::decode(&mut &encoded[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn oversized_map_is_rejected() {
+        let mut encoded = NodeIndex(0).encode();
+        Compact(0u64).encode_to(&mut encoded);
+        Compact(u32::MAX).encode_to(&mut encoded);
+        encoded.extend_from_slice(&[0xff; 16]);
+        assert!(PreUnit::<Hash>::decode(&mut &encoded[..]).is_err());
+    }
+
+    #[test]
+    fn non_zero_padding_is_rejected() {
+        let mut encoded = preunit(10).encode();
+        // the second bitmask byte holds the last two of the 10 bits followed by the padding
+        encoded[12] |= 1;
+        assert!(PreUnit::<Hash>::decode(&mut &encoded[..]).is_err());
+    }
+
+    fn notifications() -> Vec<NotificationOut<Hash>> {
+        vec![
+            NotificationOut::CreatedPreUnit(preunit(10)),
+            NotificationOut::SignedUnit {
+                preunit: preunit(10),
+                creator: NodeIndex(2),
+                signature: Signature(vec![7; 64]),
+            },
+            NotificationOut::CreatedUnitParents {
+                round: Round(300),
+                parents: vec![(NodeIndex(0), Hash(1)), (NodeIndex(3), Hash(4))],
+            },
+            NotificationOut::MissingUnits(
+                vec![UnitCoord {
+                    creator: NodeIndex(1),
+                    round: 5,
+                }],
+                RequestAuxData::new(NodeIndex(3)),
+            ),
+            NotificationOut::CreatorStalled {
+                round: Round(6),
+                have_parents: NodeCount(2),
+                needed: NodeCount(3),
+            },
+            NotificationOut::ForkDetected {
+                round: Round(7),
+                creator: NodeIndex(1),
+                hash_a: Hash(8),
+                hash_b: Hash(9),
+            },
+            NotificationOut::ImpostorUnit {
+                round: Round(7),
+                expected: Hash(10),
+                received: Hash(11),
+            },
+            NotificationOut::SessionComplete {
+                last_round: Round(1 << 40),
+            },
+            NotificationOut::OwnUnitMissing { round: Round(12) },
+            NotificationOut::Terminated,
+            NotificationOut::BannedParentsNeeded { round: Round(4) },
+            NotificationOut::ForcedUnit {
+                round: Round(6),
+                n_parents: NodeCount(1),
+            },
+            NotificationOut::CatchupRequest {
+                round: Round(9),
+                missing: vec![NodeIndex(1), NodeIndex(3)],
+            },
+        ]
+    }
+
+    #[test]
+    fn notifications_round_trip() {
+        for (tag, notification) in notifications().into_iter().enumerate() {
+            let encoded = notification.encode();
+            assert_eq!(encoded[0], tag as u8);
+            assert_eq!(NotificationOut::decode_all(&encoded[..]), Ok(notification));
+        }
+    }
+
+    #[test]
+    fn created_preunit_reuses_the_preunit_encoding() {
+        let encoded = NotificationOut::CreatedPreUnit(preunit(10)).encode();
+        assert_eq!(encoded[1..], preunit(10).encode()[..]);
+    }
+
+    #[test]
+    fn unknown_notification_tag_is_rejected() {
+        let mut encoded = NotificationOut::<Hash>::SessionComplete {
+            last_round: Round(3),
+        }
+        .encode();
+        encoded[0] = 13;
+        assert!(NotificationOut::<Hash>::decode(&mut &encoded[..]).is_err());
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        for notification in notifications() {
+            let mut encoded = notification.encode();
+            encoded.push(0);
+            assert!(NotificationOut::<Hash>::decode_all(&encoded[..]).is_err());
+        }
+    }
+}
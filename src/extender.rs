@@ -47,8 +47,8 @@ struct CacheState {
 impl CacheState {
     fn empty_dag_cache() -> Self {
         CacheState {
-            highest_round: 0,
-            current_round: 0,
+            highest_round: Round(0),
+            current_round: Round(0),
             round_initialized: false,
             pending_cand_id: 0,
             votes_up_to_date: false,
@@ -63,7 +63,6 @@ impl CacheState {
 /// this process checks whether a new round can be finalized and if so, it computes the batch of
 /// units that should be finalized, unwraps them (leaving only a block hash per unit) and pushes
 /// such a batch to a channel via the finalizer_tx endpoint.
-
 pub(crate) struct Extender<H: HashT, NI: NodeIdT> {
     node_id: NI,
     electors: Receiver<ExtenderUnit<H>>,
@@ -102,11 +101,11 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
         }
         debug!(target: "rush-extender", "{} unit round {:?} state current_round {:?}", self.node_id, u.round, self.state.current_round);
         // need to extend the vector first to the required length
-        if self.units_by_round.len() <= round {
+        if self.units_by_round.len() <= round.0 {
             self.units_by_round.push(vec![]);
         }
         if round >= self.state.current_round {
-            self.units_by_round[round].push(u.hash);
+            self.units_by_round[round.0].push(u.hash);
             self.units.insert(u.hash, u);
             true
         } else {
@@ -119,12 +118,12 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
         // The clone below is necessary as we take "a snapshot" of the set of units at this round and never
         // go back and never update this list. From math it follows that each unit that is added to the Dag later
         // then the moment of round initialization will be decided as false, hence they can be ignored.
-        self.candidates = self.units_by_round[round].clone();
+        self.candidates = self.units_by_round[round.0].clone();
         // TODO: we sort units by hashes -- we could do some other permutation here
         self.candidates.sort();
     }
 
-    fn common_vote(&self, relative_round: Round) -> bool {
+    fn common_vote(&self, relative_round: usize) -> bool {
         if relative_round == 3 {
             return false;
         }
@@ -162,7 +161,7 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
             error!(target: "rush-extender", "{:?} Unable to send a batch to Finalizer: {:?}.", self.node_id, e);
         }
         debug!(target: "rush-extender", "{} Finalized round {}.", self.node_id, round);
-        self.units_by_round[round].clear();
+        self.units_by_round[round.0].clear();
     }
 
     fn vote_and_decision(
@@ -178,7 +177,7 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
         if voter.round <= candidate_round {
             return (false, None);
         }
-        let relative_round = voter.round - candidate_round;
+        let relative_round = voter.round.0 - candidate_round.0;
         if relative_round == 1 {
             return (
                 voter.parents[candidate_creator] == Some(*candidate_hash),
@@ -198,7 +197,7 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
         }
         let cv = self.common_vote(relative_round);
         let mut decision = None;
-        let threshold = (self.n_members * 2) / 3;
+        let threshold = self.n_members.threshold_two_thirds();
 
         if relative_round >= 3
             && ((cv && n_votes_true > threshold)
@@ -223,7 +222,7 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
     fn progress(&mut self, u_new_hash: H) {
         loop {
             if !self.state.round_initialized {
-                if self.state.highest_round >= self.state.current_round + 3 {
+                if self.state.highest_round >= self.state.current_round.saturating_add(3) {
                     self.initialize_round(self.state.current_round);
                     self.state.round_initialized = true;
                     self.state.pending_cand_id = 0;
@@ -242,7 +241,7 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
             if !self.state.votes_up_to_date {
                 // this means that for the unit currently considered for head we need to compute votes
                 // and check for decisions
-                for r in curr_round + 1..self.state.highest_round {
+                for r in curr_round.0 + 1..self.state.highest_round.0 {
                     for u_hash in self.units_by_round[r].iter() {
                         let (vote, u_decision) = self.vote_and_decision(
                             &candidate_hash,
@@ -275,7 +274,7 @@ impl<H: HashT, NI: NodeIdT> Extender<H, NI> {
 
             if decision == Some(true) {
                 self.finalize_round(self.state.current_round, &candidate_hash);
-                self.state.current_round += 1;
+                self.state.current_round = self.state.current_round.next();
                 self.state.round_initialized = false;
                 continue;
             }
@@ -323,16 +322,17 @@ mod tests {
     }
 
     fn construct_unit(creator: usize, round: usize, n_members: usize) -> ExtenderUnit<Hash> {
-        let mut parents = NodeMap::new_with_len(NodeCount(n_members));
+        let mut parents = NodeMap::new_with_len(NodeCount::from_usize(n_members));
         if round > 0 {
             for i in 0..n_members {
-                parents[NodeIndex(i)] = Some(Hash(coord_to_number(i, round - 1, n_members) as u32));
+                parents[NodeIndex::from_usize(i)] =
+                    Some(Hash(coord_to_number(i, round - 1, n_members) as u32));
             }
         }
 
         ExtenderUnit::new(
-            NodeIndex(creator),
-            round,
+            NodeIndex::from_usize(creator),
+            Round(round),
             Hash(coord_to_number(creator, round, n_members) as u32),
             parents,
         )
@@ -344,8 +344,12 @@ mod tests {
         let rounds = 6;
         let (batch_tx, mut batch_rx) = mpsc::unbounded_channel();
         let (electors_tx, electors_rx) = mpsc::unbounded_channel();
-        let mut extender =
-            Extender::<Hash, NodeId>::new(0.into(), NodeCount(n_members), electors_rx, batch_tx);
+        let mut extender = Extender::<Hash, NodeId>::new(
+            0.into(),
+            NodeCount::from_usize(n_members),
+            electors_rx,
+            batch_tx,
+        );
         let (exit_tx, exit_rx) = oneshot::channel();
         let extender_handle = tokio::spawn(async move { extender.extend(exit_rx).await });
 
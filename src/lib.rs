@@ -1,30 +1,53 @@
 //! Implements the Aleph BFT Consensus protocol as a "finality gadget". The [Consensus] struct
 //! requires access to an [Environment] object which black-boxes the network layer and gives
 //! appropriate access to the set of available blocks that we need to make consensus on.
+#![allow(clippy::type_complexity)]
+
+extern crate alloc;
 
 use codec::{Decode, Encode};
+use derive_more::{Display, From, Into};
 use futures::{Future, Sink, Stream};
 use log::{debug, error};
 use std::{
     fmt::{Debug, Display},
     hash::Hash,
 };
-use tokio::{
-    sync::{mpsc, oneshot},
-    time::Duration,
-};
+use tokio::sync::{mpsc, oneshot};
 
 use crate::{
-    creator::Creator,
     extender::Extender,
     nodes::{NodeCount, NodeIndex, NodeMap},
     syncer::Syncer,
     terminal::Terminal,
 };
 
+pub use crate::{
+    config::{
+        Config, ConfigBuilder, ConfigError, DEFAULT_LOG_TARGET, DEFAULT_MAX_ROUND_LEAD,
+        MAX_CREATE_LAG,
+    },
+    creator::{
+        Clock, Creator, CreatorBuilder, CreatorError, CreatorEvent, CreatorHealth, CreatorStats,
+        CreatorStatsHandle, DataProvider, LagStrategy, MetricsSink, NoopSink, NotificationReceiver,
+        RoundWatch, TokioClock, PARENT_HISTOGRAM_WINDOW,
+    },
+    creator_core::{
+        AddOutcome, CandidateStore, CreateReadiness, CreatorCore, CreatorDelta, CreatorState,
+        DeltaError, GreedySelector, InMemoryStore, ParentPreference, ParentSelector,
+        ReconfigureError, SnapshotError, ThresholdPolicy, TwoThirdsPolicy,
+    },
+};
+
+mod config;
 mod creator;
+mod creator_core;
+mod encoding;
 mod extender;
 pub mod nodes;
+mod rounds;
+#[cfg(feature = "sim")]
+pub mod sim;
 mod syncer;
 mod terminal;
 mod testing;
@@ -42,21 +65,119 @@ impl<I> NodeIdT for I where
 {
 }
 
-/// A hash, as an identifier for a block or unit.
+/// A hash, as an identifier for a block or unit. It is implemented for every type with all the
+/// bounds below, which the Creator and the rest of the consensus rely on without asking for more
+/// at any of their methods:
+/// - `Eq` tells a duplicate from a fork, or from an impostor of our own unit;
+/// - `Ord` sorts the parents for [ParentOrdering::ByHash] and keys ordered collections;
+/// - `Hash` keys the units kept by the Terminal and the Extender;
+/// - `Copy` keeps the hashes by value, e.g., in snapshots and in a [CandidateStore];
+/// - `Send + Sync + 'static` are needed for the Creator to be spawned as a task;
+/// - `Encode + Decode` are for the control hashes and the notifications on the wire;
+/// - `Debug + Display` are for logs.
 pub trait HashT:
-    Eq + Ord + Copy + Clone + Send + Sync + Debug + Display + Hash + Encode + Decode
+    Eq + Ord + Copy + Clone + Send + Sync + Debug + Display + Hash + Encode + Decode + 'static
 {
 }
 
 impl<H> HashT for H where
-    H: Eq + Ord + Copy + Clone + Send + Sync + Debug + Display + Hash + Encode + Decode
+    H: Eq + Ord + Copy + Clone + Send + Sync + Debug + Display + Hash + Encode + Decode + 'static
 {
 }
 
+/// A hashing function, used to compute the control hashes of units. It is implemented for all
+/// closures `Fn(&[u8]) -> H`, but implementing it directly allows the hasher to hold some state,
+/// e.g., a key for domain separation between sessions.
+///
+/// The Creator also remembers the hash of every unit it creates as the one of the encoded
+/// preunit, and rejects the units with its own index coming back with a different hash. The
+/// environment thus has to hash the units the same way, or the check has to be turned off with
+/// [ConfigBuilder::skip_own_hash_check].
+pub trait Hasher<H: HashT> {
+    fn hash(&self, data: &[u8]) -> H;
+
+    /// A variant of [Hasher::hash] for hashers that might fail. The Creator always hashes via
+    /// this method, so that a failure only skips creating a unit rather than panicking. A panic
+    /// of the hasher is caught by the Creator and handled as a failure as well.
+    fn try_hash(&self, data: &[u8]) -> Result<H, HashError> {
+        Ok(self.hash(data))
+    }
+}
+
+impl<H: HashT, F: Fn(&[u8]) -> H> Hasher<H> for F {
+    fn hash(&self, data: &[u8]) -> H {
+        self(data)
+    }
+}
+
+/// An error reported by a fallible hashing function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashError(pub String);
+
+impl Display for HashError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "hashing failed: {}", self.0)
+    }
+}
+
+/// Adapts a fallible hashing function `Fn(&[u8]) -> Result<H, HashError>` to a [Hasher]. Note
+/// that calling [Hasher::hash] on it panics if hashing fails, only [Hasher::try_hash] is safe.
+#[derive(Clone, Copy)]
+pub struct FallibleHashing<F>(pub F);
+
+impl<H: HashT, F: Fn(&[u8]) -> Result<H, HashError>> Hasher<H> for FallibleHashing<F> {
+    fn hash(&self, data: &[u8]) -> H {
+        match (self.0)(data) {
+            Ok(hash) => hash,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn try_hash(&self, data: &[u8]) -> Result<H, HashError> {
+        (self.0)(data)
+    }
+}
+
+/// A signature of a unit, as produced by a [Signer].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct Signature(pub Vec<u8>);
+
+/// Signs the units created by the Creator. The signed bytes are the encoding of the preunit, i.e.,
+/// exactly the bytes sent over the wire, so that verifiers can reproduce them.
+pub trait Signer: Send + Sync {
+    fn sign(&self, bytes: &[u8]) -> Signature;
+}
+
 pub enum Error {}
 
-/// A round.
-pub type Round = usize;
+/// A round. Moving between rounds goes through [Round::prev] and [Round::next] rather than bare
+/// arithmetic, so that the round preceding round 0 cannot be computed by accident.
+#[derive(
+    Copy, Clone, Debug, Default, Display, Eq, PartialEq, Hash, Ord, PartialOrd, From, Into,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Round(pub usize);
+
+impl Round {
+    /// The previous round, None for round 0.
+    pub fn prev(self) -> Option<Round> {
+        self.0.checked_sub(1).map(Round)
+    }
+
+    pub fn next(self) -> Round {
+        Round(self.0 + 1)
+    }
+
+    /// The round `rounds` rounds later, or the last representable round.
+    pub fn saturating_add(self, rounds: usize) -> Round {
+        Round(self.0.saturating_add(rounds))
+    }
+
+    /// The round `rounds` rounds earlier, or round 0.
+    pub fn saturating_sub(self, rounds: usize) -> Round {
+        Round(self.0.saturating_sub(rounds))
+    }
+}
 
 /// Type used in NotificationOut::MissingUnits to give additional info about the missing units that might
 /// help the Environment to fetch them (currently this is the node_ix of the unit whose parents are missing).
@@ -85,7 +206,7 @@ impl<H: HashT> From<Unit<H>> for UnitCoord {
     fn from(unit: Unit<H>) -> Self {
         UnitCoord {
             creator: unit.creator(),
-            round: unit.round() as u64,
+            round: unit.round().0 as u64,
         }
     }
 }
@@ -94,16 +215,16 @@ impl<H: HashT> From<&Unit<H>> for UnitCoord {
     fn from(unit: &Unit<H>) -> Self {
         UnitCoord {
             creator: unit.creator(),
-            round: unit.round() as u64,
+            round: unit.round().0 as u64,
         }
     }
 }
 
-impl From<(usize, NodeIndex)> for UnitCoord {
-    fn from(coord: (usize, NodeIndex)) -> Self {
+impl From<(Round, NodeIndex)> for UnitCoord {
+    fn from(coord: (Round, NodeIndex)) -> Self {
         UnitCoord {
             creator: coord.1,
-            round: coord.0 as u64,
+            round: coord.0 .0 as u64,
         }
     }
 }
@@ -123,39 +244,165 @@ pub enum NotificationOut<H: HashT> {
     // Notification about a preunit created by this Consensus Node. Environment is meant to
     // disseminate this preunit among other nodes.
     CreatedPreUnit(PreUnit<H>),
+    /// Notification about a preunit created by this Consensus Node, sent instead of
+    /// CreatedPreUnit if the node has a [Signer]. The signature is over the encoded preunit.
+    SignedUnit {
+        preunit: PreUnit<H>,
+        creator: NodeIndex,
+        signature: Signature,
+    },
+    /// Notification about the parents of our unit of the given round, by creator and in the order
+    /// of indices. Sent right after the unit itself if enabled with
+    /// [ConfigBuilder::include_parent_list].
+    CreatedUnitParents {
+        round: Round,
+        parents: Vec<(NodeIndex, H)>,
+    },
     /// Notification that some units are needed but missing. The role of the Environment
     /// is to fetch these unit (somehow). Auxiliary data is provided to help handle this request.
     MissingUnits(Vec<UnitCoord>, RequestAuxData),
+    /// Notification that the Creator did not manage to create a unit of the given round for
+    /// longer than the configured stall timeout, as only `have_parents` out of the `needed`
+    /// parents are available (or our own previous unit is still missing if there are enough).
+    CreatorStalled {
+        round: Round,
+        have_parents: NodeCount,
+        needed: NodeCount,
+    },
+    /// Notification that the creator of the given round produced two different units, a fork.
+    /// The unit with `hash_a` was seen first and remains the one used as a parent.
+    ForkDetected {
+        round: Round,
+        creator: NodeIndex,
+        hash_a: H,
+        hash_b: H,
+    },
+    /// Notification that a unit claiming to be our unit of the given round arrived, but its hash
+    /// differs from the one of the unit we created. The impostor is not used as a parent.
+    ImpostorUnit {
+        round: Round,
+        expected: H,
+        received: H,
+    },
+    /// Notification that the Creator created its unit of the configured maximal round,
+    /// `last_round`, and stopped.
+    SessionComplete {
+        last_round: Round,
+    },
+    /// Notification that our own unit of the given round is the only one missing for creating
+    /// the unit of the next round, for longer than the configured own unit timeout. It is likely
+    /// that it did not reach the others, or did not come back to us, so it should be broadcast
+    /// again.
+    OwnUnitMissing {
+        round: Round,
+    },
+    /// Notification that the Creator has enough parents for its unit of the given round only when
+    /// counting the units of the banned members, see [Creator::ban_node]. The unit is not created
+    /// until enough other parents arrive.
+    BannedParentsNeeded {
+        round: Round,
+    },
+    /// Notification that our unit of the given round, sent right before, was forced with only
+    /// `n_parents` parents, regardless of the threshold, see [Creator::force_advance]. Such a
+    /// unit may well be rejected by the others.
+    ForcedUnit {
+        round: Round,
+        n_parents: NodeCount,
+    },
+    /// Notification that the Creator needs the units of the given round created by the `missing`
+    /// members, sent right after [NotificationOut::CreatorStalled]. The units obtained from the
+    /// other nodes are to be passed back with [Creator::apply_catchup_response].
+    CatchupRequest {
+        round: Round,
+        missing: Vec<NodeIndex>,
+    },
+    /// Notification that the Creator stopped on the exit signal. It is the last one sent, so that
+    /// consumers listening on several channels can tell that the Creator is done.
+    Terminated,
     // TODO: RequestParents(H) and Alert() notifications
 }
 
-/// Type for sending a new ordered batch of units
-pub type OrderedBatch<H> = Vec<H>;
-
-#[derive(Clone)]
-pub struct Config<NI: NodeIdT> {
-    pub(crate) node_id: NI,
-    n_members: NodeCount,
-    create_lag: Duration,
-}
-
-impl<NI: NodeIdT> Config<NI> {
-    pub fn new(node_id: NI, n_members: NodeCount, create_lag: Duration) -> Self {
-        Config {
-            node_id,
-            n_members,
-            create_lag,
+impl<H: HashT> NotificationOut<H> {
+    /// A compact one-line description of the notification, meant for logs.
+    pub fn summary(&self) -> String {
+        match self {
+            NotificationOut::CreatedPreUnit(pu) => format!(
+                "CreatedPreUnit round={} creator={} parents={}",
+                pu.round(),
+                pu.creator(),
+                pu.control_hash.n_parents().0
+            ),
+            NotificationOut::SignedUnit { preunit, .. } => format!(
+                "SignedUnit round={} creator={} parents={}",
+                preunit.round(),
+                preunit.creator(),
+                preunit.control_hash.n_parents().0
+            ),
+            NotificationOut::CreatedUnitParents { round, parents } => format!(
+                "CreatedUnitParents round={} parents={}",
+                round,
+                parents.len()
+            ),
+            NotificationOut::MissingUnits(coords, aux) => format!(
+                "MissingUnits count={} child_creator={}",
+                coords.len(),
+                aux.child_creator()
+            ),
+            NotificationOut::CreatorStalled {
+                round,
+                have_parents,
+                needed,
+            } => format!(
+                "CreatorStalled round={} parents={}/{}",
+                round, have_parents.0, needed.0
+            ),
+            NotificationOut::ForkDetected {
+                round,
+                creator,
+                hash_a,
+                hash_b,
+            } => format!(
+                "ForkDetected round={} creator={} hashes={},{}",
+                round, creator, hash_a, hash_b
+            ),
+            NotificationOut::ImpostorUnit {
+                round,
+                expected,
+                received,
+            } => format!(
+                "ImpostorUnit round={} expected={} received={}",
+                round, expected, received
+            ),
+            NotificationOut::OwnUnitMissing { round } => {
+                format!("OwnUnitMissing round={}", round)
+            }
+            NotificationOut::SessionComplete { last_round } => {
+                format!("SessionComplete last_round={}", last_round)
+            }
+            NotificationOut::BannedParentsNeeded { round } => {
+                format!("BannedParentsNeeded round={}", round)
+            }
+            NotificationOut::ForcedUnit { round, n_parents } => {
+                format!("ForcedUnit round={} n_parents={}", round, n_parents)
+            }
+            NotificationOut::CatchupRequest { round, missing } => {
+                format!("CatchupRequest round={} missing={}", round, missing.len())
+            }
+            NotificationOut::Terminated => "Terminated".to_string(),
         }
     }
 }
 
+/// Type for sending a new ordered batch of units
+pub type OrderedBatch<H> = Vec<H>;
+
 pub trait SpawnHandle {
     fn spawn(&self, name: &'static str, task: impl Future<Output = ()> + Send + 'static);
 }
 
-pub struct Consensus<H: HashT, NI: NodeIdT> {
-    conf: Config<NI>,
-    creator: Option<Creator<H, NI>>,
+pub struct Consensus<H: HashT, NI: NodeIdT, Hashing: Hasher<H>> {
+    conf: Config<H, NI>,
+    creator: Option<Creator<H, NI, Hashing>>,
     terminal: Option<Terminal<H, NI>>,
     extender: Option<Extender<H, NI>>,
     syncer: Option<Syncer<H, NI>>,
@@ -164,17 +411,19 @@ pub struct Consensus<H: HashT, NI: NodeIdT> {
 pub(crate) type Receiver<T> = mpsc::UnboundedReceiver<T>;
 pub(crate) type Sender<T> = mpsc::UnboundedSender<T>;
 
-impl<H: HashT + 'static, NI: NodeIdT> Consensus<H, NI> {
+impl<H: HashT + 'static, NI: NodeIdT, Hashing: Hasher<H> + Send + Copy + 'static>
+    Consensus<H, NI, Hashing>
+{
     pub fn new(
-        conf: Config<NI>,
+        conf: Config<H, NI>,
         ntfct_rx: impl Stream<Item = NotificationIn<H>> + Send + Unpin + 'static,
         ntfct_tx: impl Sink<NotificationOut<H>, Error = Box<dyn std::error::Error>>
             + Send
             + Unpin
             + 'static,
         ordered_batch_tx: Sender<OrderedBatch<H>>,
-        hashing: impl Fn(&[u8]) -> H + Send + Copy + 'static,
-    ) -> Self {
+        hashing: Hashing,
+    ) -> Result<Self, CreatorError> {
         let n_members = conf.n_members;
 
         let (electors_tx, electors_rx) = mpsc::unbounded_channel();
@@ -195,11 +444,13 @@ impl<H: HashT + 'static, NI: NodeIdT> Consensus<H, NI> {
             parents_rx,
             new_units_tx,
             hashing,
-        ));
+        )?);
 
         let mut terminal = Terminal::new(
             conf.node_id.clone(),
-            hashing,
+            move |data: &[u8]| hashing.hash(data),
+            conf.session_id,
+            conf.parent_ordering,
             incoming_units_rx,
             requests_tx,
         );
@@ -221,26 +472,29 @@ impl<H: HashT + 'static, NI: NodeIdT> Consensus<H, NI> {
         let syncer = Some(syncer);
         let terminal = Some(terminal);
 
-        Consensus {
+        Ok(Consensus {
             conf,
             creator,
             terminal,
             extender,
             syncer,
-        }
+        })
     }
 }
 
-impl<H: HashT + 'static, NI: NodeIdT> Consensus<H, NI> {
+impl<H: HashT + 'static, NI: NodeIdT, Hashing: Hasher<H> + Send + 'static>
+    Consensus<H, NI, Hashing>
+{
     pub async fn run(mut self, spawn_handle: impl SpawnHandle, exit: oneshot::Receiver<()>) {
         debug!(target: "rush-root", "{} Starting all services...", self.conf.node_id);
 
         let (creator_exit, exit_rx) = oneshot::channel();
         let mut creator = self.creator.take().unwrap();
-        spawn_handle.spawn(
-            "consensus/creator",
-            async move { creator.create(exit_rx).await },
-        );
+        spawn_handle.spawn("consensus/creator", async move {
+            if let Err(e) = creator.create(exit_rx).await {
+                error!(target: "rush-root", "Creator stopped: {}.", e);
+            }
+        });
 
         let (terminal_exit, exit_rx) = oneshot::channel();
         let mut terminal = self.terminal.take().unwrap();
@@ -275,44 +529,131 @@ impl<H: HashT + 'static, NI: NodeIdT> Consensus<H, NI> {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Encode, Decode)]
+/// The parents of a unit, as the members who created them, together with the hash of the encoded
+/// map of the hashes of the parents. The hash is computed once per unit, when its parents are
+/// final, so creating a unit hashes the map only once. It is the hash of the whole map rather
+/// than one updated as the candidates arrive, as an arbitrary [Hasher] cannot be updated without
+/// changing the resulting value.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ControlHash<H: HashT> {
     // TODO we need to optimize it for it to take O(N) bits of memory not O(N) words.
     pub parents: NodeMap<bool>,
     pub hash: H,
 }
 
+// The session id, round and creator of a unit, hashed ahead of its parents for domain separation.
+pub(crate) type HashDomain = Option<(u64, Round, NodeIndex)>;
+
+/// The order in which the hashes of the parents of a unit are fed into its control hash. The
+/// ordering is part of the protocol: all the members of a session have to use the same one, or
+/// they will not accept each other's units.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ParentOrdering {
+    /// The hashes are fed as the map from the indices of their creators, including the members
+    /// without a parent, as they always were. The default.
+    #[default]
+    ByIndex,
+    /// Only the hashes of the parents are fed, in ascending order, so the result does not depend
+    /// on which member created which parent.
+    ByHash,
+}
+
 impl<H: HashT> ControlHash<H> {
-    fn new(parent_map: &NodeMap<Option<H>>, hashing: impl Fn(&[u8]) -> H) -> Self {
-        let hash = Self::combine_hashes(&parent_map, hashing);
+    fn new(parent_map: &NodeMap<Option<H>>, hashing: &impl Hasher<H>) -> Self {
+        Self::new_in(parent_map, None, hashing)
+    }
+
+    fn new_in(
+        parent_map: &NodeMap<Option<H>>,
+        domain: HashDomain,
+        hashing: &impl Hasher<H>,
+    ) -> Self {
+        let hash = Self::combine_hashes_in(parent_map, domain, ParentOrdering::ByIndex, hashing);
         let parents = parent_map.iter().map(|h| h.is_some()).collect();
 
         ControlHash { parents, hash }
     }
 
-    pub(crate) fn combine_hashes(
+    fn try_new(
         parent_map: &NodeMap<Option<H>>,
-        hashing: impl Fn(&[u8]) -> H,
+        domain: HashDomain,
+        ordering: ParentOrdering,
+        hashing: &impl Hasher<H>,
+    ) -> Result<Self, HashError> {
+        let hash = Self::hash_with(parent_map, domain, ordering, |bytes| {
+            hashing.try_hash(bytes)
+        })?;
+        let parents = parent_map.iter().map(|h| h.is_some()).collect();
+
+        Ok(ControlHash { parents, hash })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn combine_hashes(parent_map: &NodeMap<Option<H>>, hashing: &impl Hasher<H>) -> H {
+        Self::combine_hashes_in(parent_map, None, ParentOrdering::ByIndex, hashing)
+    }
+
+    pub(crate) fn combine_hashes_in(
+        parent_map: &NodeMap<Option<H>>,
+        domain: HashDomain,
+        ordering: ParentOrdering,
+        hashing: &impl Hasher<H>,
     ) -> H {
-        parent_map.using_encoded(hashing)
+        Self::hash_with(parent_map, domain, ordering, |bytes| hashing.hash(bytes))
+    }
+
+    // Without a domain and ordered by index only the encoded parents are hashed, as they always
+    // were.
+    fn hash_with<T>(
+        parent_map: &NodeMap<Option<H>>,
+        domain: HashDomain,
+        ordering: ParentOrdering,
+        hash: impl FnOnce(&[u8]) -> T,
+    ) -> T {
+        if domain.is_none() && ordering == ParentOrdering::ByIndex {
+            return parent_map.using_encoded(hash);
+        }
+        let mut bytes = Vec::new();
+        if let Some((session_id, round, creator)) = domain {
+            (session_id, round.0 as UnitRound, creator).encode_to(&mut bytes);
+        }
+        match ordering {
+            ParentOrdering::ByIndex => parent_map.encode_to(&mut bytes),
+            ParentOrdering::ByHash => {
+                let mut hashes: Vec<H> = parent_map.iter().flatten().copied().collect();
+                hashes.sort();
+                hashes.encode_to(&mut bytes);
+            }
+        }
+        hash(&bytes)
     }
 
     pub(crate) fn n_parents(&self) -> NodeCount {
-        NodeCount(self.parents.iter().filter(|&b| *b).count())
+        NodeCount::from_usize(self.parents.iter().filter(|&b| *b).count())
     }
 
     pub(crate) fn n_members(&self) -> NodeCount {
-        NodeCount(self.parents.len())
+        NodeCount::from_usize(self.parents.len())
     }
 }
 
 type UnitRound = u64;
 
-#[derive(Clone, Debug, Default, PartialEq, Encode, Decode)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct PreUnit<H: HashT> {
     pub(crate) creator: NodeIndex,
     round: UnitRound,
     pub(crate) control_hash: ControlHash<H>,
+    // the number of parents in the control hash, carried explicitly so that it can be checked
+    // without counting the parents map
+    n_parents: NodeCount,
+    // the encoded payload obtained from the DataProvider of the creator
+    data: Vec<u8>,
 }
 
 impl<H: HashT> PreUnit<H> {
@@ -321,28 +662,99 @@ impl<H: HashT> PreUnit<H> {
     }
 
     pub fn round(&self) -> Round {
-        self.round as Round
+        Round(self.round as usize)
+    }
+
+    /// Which members the parents of the unit were created by, together with the hash of the
+    /// parents.
+    pub fn control_hash(&self) -> &ControlHash<H> {
+        &self.control_hash
+    }
+
+    /// The number of parents of the unit, as stored in the preunit. Preunits decoded from the
+    /// wire are checked to agree with their parents map, so this can be compared against the
+    /// required threshold without going through the map.
+    pub fn n_parents(&self) -> NodeCount {
+        self.n_parents
+    }
+
+    pub fn new_from_parents(
+        creator: NodeIndex,
+        round: Round,
+        parents: &NodeMap<Option<H>>,
+        hashing: &impl Hasher<H>,
+    ) -> Self {
+        let control_hash = ControlHash::new(parents, hashing);
+        PreUnit {
+            creator,
+            round: round.0 as UnitRound,
+            n_parents: control_hash.n_parents(),
+            control_hash,
+            data: Vec::new(),
+        }
     }
 
-    pub(crate) fn new_from_parents(
+    /// Like [PreUnit::new_from_parents], but with the session id, the round and the creator
+    /// hashed into the control hash ahead of the parents, see [ConfigBuilder::session_id].
+    pub fn new_from_parents_in_session(
         creator: NodeIndex,
         round: Round,
-        parents: NodeMap<Option<H>>,
-        hashing: impl Fn(&[u8]) -> H,
+        parents: &NodeMap<Option<H>>,
+        session_id: u64,
+        hashing: &impl Hasher<H>,
     ) -> Self {
-        let control_hash = ControlHash::new(&parents, &hashing);
+        let control_hash =
+            ControlHash::new_in(parents, Some((session_id, round, creator)), hashing);
         PreUnit {
             creator,
-            round: round as u64,
+            round: round.0 as UnitRound,
+            n_parents: control_hash.n_parents(),
             control_hash,
+            data: Vec::new(),
         }
     }
 
+    pub(crate) fn try_new_from_parents(
+        creator: NodeIndex,
+        round: Round,
+        parents: &NodeMap<Option<H>>,
+        session_id: Option<u64>,
+        ordering: ParentOrdering,
+        hashing: &impl Hasher<H>,
+    ) -> Result<Self, HashError> {
+        let domain = session_id.map(|session_id| (session_id, round, creator));
+        let control_hash = ControlHash::try_new(parents, domain, ordering, hashing)?;
+        Ok(PreUnit {
+            creator,
+            round: round.0 as UnitRound,
+            n_parents: control_hash.n_parents(),
+            control_hash,
+            data: Vec::new(),
+        })
+    }
+
+    pub(crate) fn with_data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// The encoded payload of the unit, empty if its creator has no DataProvider.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Decodes the payload of the unit.
+    pub fn decode_data<D: Decode>(&self) -> Result<D, codec::Error> {
+        D::decode(&mut &self.data[..])
+    }
+
     pub fn new(creator: NodeIndex, round: Round, control_hash: ControlHash<H>) -> Self {
         PreUnit {
             creator,
-            round: round as UnitRound,
+            round: round.0 as UnitRound,
+            n_parents: control_hash.n_parents(),
             control_hash,
+            data: Vec::new(),
         }
     }
 }
@@ -353,12 +765,13 @@ impl<H: HashT> From<PreUnit<H>> for NotificationOut<H> {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Encode, Decode)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Unit<H: HashT> {
     pub(crate) creator: NodeIndex,
     round: UnitRound,
     pub(crate) hash: H,
     pub(crate) control_hash: ControlHash<H>,
+    data: Vec<u8>,
 }
 
 impl<H: HashT> Unit<H> {
@@ -370,15 +783,16 @@ impl<H: HashT> Unit<H> {
         self.creator
     }
     pub fn round(&self) -> Round {
-        self.round as Round
+        Round(self.round as usize)
     }
 
     pub fn new(creator: NodeIndex, round: Round, hash: H, control_hash: ControlHash<H>) -> Self {
         Unit {
             creator,
-            round: round as UnitRound,
+            round: round.0 as UnitRound,
             hash,
             control_hash,
+            data: Vec::new(),
         }
     }
 
@@ -388,16 +802,28 @@ impl<H: HashT> Unit<H> {
             round: pu.round,
             hash,
             control_hash: pu.control_hash,
+            data: pu.data,
         }
     }
+
+    /// The encoded payload of the unit, empty if its creator has no DataProvider.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Decodes the payload of the unit.
+    pub fn decode_data<D: Decode>(&self) -> Result<D, codec::Error> {
+        D::decode(&mut &self.data[..])
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testing::mock::{hashing, Network, NodeId};
+    use crate::testing::mock::{hashing, Hash, Network, NodeId};
     use parking_lot::Mutex;
     use std::sync::Arc;
+    use tokio::time::Duration;
 
     fn init_log() {
         let _ = env_logger::builder()
@@ -419,12 +845,106 @@ mod tests {
 
     impl Spawner {
         async fn wait(&self) {
-            for h in self.handles.lock().iter_mut() {
+            let handles = std::mem::take(&mut *self.handles.lock());
+            for h in handles {
                 let _ = h.await;
             }
         }
     }
 
+    #[test]
+    fn created_unit_summary() {
+        let parents = vec![Some(Hash(0)), None, Some(Hash(2)), Some(Hash(3))]
+            .into_iter()
+            .collect();
+        let pu = PreUnit::new_from_parents(NodeIndex(2), Round(7), &parents, &hashing);
+        let notification = NotificationOut::from(pu);
+        assert_eq!(
+            notification.clone().summary(),
+            "CreatedPreUnit round=7 creator=2 parents=3"
+        );
+        let stalled = NotificationOut::<Hash>::CreatorStalled {
+            round: Round(3),
+            have_parents: NodeCount(1),
+            needed: NodeCount(3),
+        };
+        assert_eq!(stalled.summary(), "CreatorStalled round=3 parents=1/3");
+    }
+
+    #[test]
+    fn test_hash_is_a_hash() {
+        fn assert_hash<H: HashT + Default>() -> H {
+            H::default()
+        }
+        let hash: Hash = assert_hash();
+        let mut hashes = vec![Hash(2), hash, Hash(1)];
+        hashes.sort();
+        assert_eq!(hashes, vec![Hash(0), Hash(1), Hash(2)]);
+    }
+
+    #[test]
+    fn round_is_displayed_as_the_plain_number() {
+        assert_eq!(Round(7).to_string(), "7");
+        assert_eq!(format!("round {}", Round(0).next()), "round 1");
+    }
+
+    #[test]
+    fn sessions_separate_control_hashes() {
+        let parents = vec![Some(Hash(0)), None, Some(Hash(2))]
+            .into_iter()
+            .collect();
+        let in_session = |session_id| {
+            PreUnit::new_from_parents_in_session(
+                NodeIndex(1),
+                Round(4),
+                &parents,
+                session_id,
+                &hashing,
+            )
+        };
+        let (first, second) = (in_session(0), in_session(1));
+        assert_eq!(first.control_hash.parents, second.control_hash.parents);
+        assert_ne!(first.control_hash.hash, second.control_hash.hash);
+        // without a session only the parents are hashed, as before
+        let plain = PreUnit::new_from_parents(NodeIndex(1), Round(4), &parents, &hashing);
+        assert_eq!(plain.control_hash.hash, parents.using_encoded(hashing));
+        assert_ne!(plain.control_hash.hash, first.control_hash.hash);
+    }
+
+    #[test]
+    fn parents_ordered_by_hash_do_not_depend_on_indices() {
+        let parents: NodeMap<_> = vec![Some(Hash(7)), None, Some(Hash(3)), Some(Hash(5))].into();
+        let shuffled: NodeMap<_> = vec![Some(Hash(5)), Some(Hash(3)), None, Some(Hash(7))].into();
+        let by = |parents, ordering| {
+            PreUnit::try_new_from_parents(NodeIndex(1), Round(4), parents, None, ordering, &hashing)
+                .unwrap()
+                .control_hash
+                .hash
+        };
+        let by_hash = by(&parents, ParentOrdering::ByHash);
+        assert_eq!(by_hash, by(&parents, ParentOrdering::ByHash));
+        assert_eq!(by_hash, by(&shuffled, ParentOrdering::ByHash));
+        assert_eq!(
+            by_hash,
+            vec![Hash(3), Hash(5), Hash(7)].using_encoded(hashing)
+        );
+        // ordered by index, the hash depends on who created which parent
+        let by_index = by(&parents, ParentOrdering::ByIndex);
+        assert_eq!(by_index, parents.using_encoded(hashing));
+        assert_ne!(by_index, by(&shuffled, ParentOrdering::ByIndex));
+        assert_ne!(by_index, by_hash);
+    }
+
+    #[test]
+    fn round_navigation_is_checked() {
+        assert_eq!(Round(0).prev(), None);
+        assert_eq!(Round(1).prev(), Some(Round(0)));
+        assert_eq!(Round(0).next(), Round(1));
+        assert_eq!(Round(1).saturating_sub(3), Round(0));
+        assert_eq!(Round(usize::MAX).saturating_add(1), Round(usize::MAX));
+        assert_eq!(Round(2).saturating_add(3), Round(5));
+    }
+
     #[tokio::test(max_threads = 1)]
     async fn small() {
         init_log();
@@ -436,15 +956,20 @@ mod tests {
 
         for node_ix in 0..n_nodes {
             let (o, i) = net.consensus_data(node_ix.into());
-            let conf =
-                Config::<NodeId>::new(node_ix.into(), n_nodes.into(), Duration::from_millis(10));
+            let conf = Config::<Hash, NodeId>::new(
+                node_ix.into(),
+                NodeCount::from_usize(n_nodes),
+                Duration::from_millis(10),
+            );
             let (exit_tx, exit_rx) = oneshot::channel();
             exits.push(exit_tx);
             let (batch_tx, batch_rx) = mpsc::unbounded_channel();
             batch_rxs.push(batch_rx);
             spawner.spawn(
                 "consensus",
-                Consensus::new(conf, i, o, batch_tx, hashing).run(spawner.clone(), exit_rx),
+                Consensus::new(conf, i, o, batch_tx, hashing)
+                    .unwrap()
+                    .run(spawner.clone(), exit_rx),
             );
         }
 
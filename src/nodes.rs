@@ -1,18 +1,56 @@
+use alloc::{vec, vec::Vec};
+use bit_vec::BitVec;
 use codec::{Decode, Encode, Error as CodecError, Input, Output};
-use derive_more::{Add, AddAssign, Display, From, Into, Sub, SubAssign, Sum};
-use std::{
+use core::{
+    fmt,
     iter::FromIterator,
     ops::{Div, Index, IndexMut, Mul},
-    slice, vec,
+    slice,
 };
+use derive_more::{Add, AddAssign, Display, From, Into, Sub, SubAssign, Sum};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The integer behind [NodeIndex] and [NodeCount], a machine word by default and `u64` with the
+/// `wide-index` feature. Either way a node index is encoded as 8 bytes.
+#[cfg(not(feature = "wide-index"))]
+pub type NodeInt = usize;
+/// The integer behind [NodeIndex] and [NodeCount], a machine word by default and `u64` with the
+/// `wide-index` feature. Either way a node index is encoded as 8 bytes.
+#[cfg(feature = "wide-index")]
+pub type NodeInt = u64;
+
+// The conversions below are no-ops for one of the widths of NodeInt, hence the allows. Positions
+// in memory always fit in a usize, as the maps are indexed by nodes of which there are way fewer.
+#[allow(clippy::unnecessary_cast)]
+fn to_usize(value: NodeInt) -> usize {
+    value as usize
+}
+
+#[allow(clippy::unnecessary_cast)]
+fn to_u64(value: NodeInt) -> u64 {
+    value as u64
+}
 
 /// The index of a node
 #[derive(Copy, Clone, Debug, Display, Default, Eq, PartialEq, Hash, Ord, PartialOrd, From)]
-pub struct NodeIndex(pub usize);
+pub struct NodeIndex(pub NodeInt);
+
+impl NodeIndex {
+    /// The position of the node in a [NodeMap].
+    pub fn as_usize(self) -> usize {
+        to_usize(self.0)
+    }
+
+    /// The index of the node at the given position in a [NodeMap].
+    pub fn from_usize(position: usize) -> NodeIndex {
+        NodeIndex(position as NodeInt)
+    }
+}
 
 impl Encode for NodeIndex {
     fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
-        let val = self.0 as u64;
+        let val = to_u64(self.0);
         let bytes = val.to_le_bytes();
         dest.write(&bytes);
     }
@@ -23,7 +61,7 @@ impl Decode for NodeIndex {
         let mut arr = [0u8; 8];
         value.read(&mut arr)?;
         let val: u64 = u64::from_le_bytes(arr);
-        Ok(NodeIndex(val as usize))
+        Ok(NodeIndex(val as NodeInt))
     }
 }
 
@@ -33,6 +71,7 @@ impl Decode for NodeIndex {
     Clone,
     Debug,
     Default,
+    Display,
     Eq,
     PartialEq,
     Hash,
@@ -46,24 +85,56 @@ impl Decode for NodeIndex {
     From,
     Into,
 )]
-pub struct NodeCount(pub(crate) usize);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeCount(pub(crate) NodeInt);
+
+impl NodeCount {
+    /// The count as a length, e.g., of a [NodeMap].
+    pub fn as_usize(self) -> usize {
+        to_usize(self.0)
+    }
+
+    pub fn from_usize(count: usize) -> NodeCount {
+        NodeCount(count as NodeInt)
+    }
+
+    pub(crate) fn as_u64(self) -> u64 {
+        to_u64(self.0)
+    }
+
+    /// Returns floor(2*N/3), i.e., N minus the maximal number of faulty nodes among N nodes.
+    /// Having more than that many units of a round is a quorum.
+    pub fn threshold_two_thirds(self) -> NodeCount {
+        // computed so that it does not overflow for huge counts
+        NodeCount(self.0 / 3 * 2 + self.0 % 3 * 2 / 3)
+    }
+
+    pub fn saturating_sub(self, rhs: NodeCount) -> NodeCount {
+        NodeCount(self.0.saturating_sub(rhs.0))
+    }
+
+    pub fn checked_add(self, rhs: NodeCount) -> Option<NodeCount> {
+        self.0.checked_add(rhs.0).map(NodeCount)
+    }
+}
 
 // deriving Mul and Div is somehow cumbersome
-impl Mul<usize> for NodeCount {
+impl Mul<NodeInt> for NodeCount {
     type Output = Self;
-    fn mul(self, rhs: usize) -> Self::Output {
+    fn mul(self, rhs: NodeInt) -> Self::Output {
         NodeCount(self.0 * rhs)
     }
 }
 
-impl Div<usize> for NodeCount {
+impl Div<NodeInt> for NodeCount {
     type Output = Self;
-    fn div(self, rhs: usize) -> Self::Output {
+    fn div(self, rhs: NodeInt) -> Self::Output {
         NodeCount(self.0 / rhs)
     }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, From, Hash, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NodeMap<T>(Vec<T>);
 
 impl<T> NodeMap<T> {
@@ -72,7 +143,7 @@ impl<T> NodeMap<T> {
     where
         T: Default + Clone,
     {
-        let v: Vec<T> = vec![T::default(); len.into()];
+        let v: Vec<T> = vec![T::default(); len.as_usize()];
         NodeMap(v)
     }
 
@@ -90,7 +161,79 @@ impl<T> NodeMap<T> {
     pub(crate) fn enumerate(&self) -> impl Iterator<Item = (NodeIndex, &T)> {
         self.iter()
             .enumerate()
-            .map(|(idx, value)| (NodeIndex(idx), value))
+            .map(|(idx, value)| (NodeIndex::from_usize(idx), value))
+    }
+}
+
+/// An index not smaller than the length of a [NodeMap].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IndexOutOfRange {
+    pub index: NodeIndex,
+    pub len: NodeCount,
+}
+
+impl fmt::Display for IndexOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "node index {} out of range for a node map of length {}",
+            self.index.0, self.len.0
+        )
+    }
+}
+
+impl<T: Default + Clone> NodeMap<T> {
+    /// Constructs a node map of the given length with the given values at the given indices,
+    /// and default values elsewhere. Later values override earlier ones at the same index.
+    pub fn from_parts(
+        len: NodeCount,
+        parts: impl IntoIterator<Item = (NodeIndex, T)>,
+    ) -> Result<Self, IndexOutOfRange> {
+        let mut map = NodeMap::new_with_len(len);
+        for (index, value) in parts {
+            if index.0 >= len.0 {
+                return Err(IndexOutOfRange { index, len });
+            }
+            map[index] = value;
+        }
+        Ok(map)
+    }
+}
+
+impl<T> NodeMap<Option<T>> {
+    /// Returns an iterator over the present values, by node index.
+    pub fn present(&self) -> impl Iterator<Item = (NodeIndex, &T)> {
+        self.enumerate()
+            .filter_map(|(idx, value)| value.as_ref().map(|value| (idx, value)))
+    }
+
+    /// Returns the number of present values.
+    pub fn count_present(&self) -> NodeCount {
+        NodeCount::from_usize(self.iter().filter(|value| value.is_some()).count())
+    }
+
+    /// Returns the pattern of present values, with the bit of a node index set iff the value at
+    /// that index is present. Together with the present values (in the order of indices) it is a
+    /// compact representation of the map, see [NodeMap::from_bitmask].
+    pub fn to_bitmask(&self) -> BitVec {
+        self.iter().map(|value| value.is_some()).collect()
+    }
+
+    /// Reconstructs a map from its [NodeMap::to_bitmask] and the present values in the order of
+    /// indices. Returns None if the number of values differs from the number of set bits.
+    pub fn from_bitmask(bitmask: &BitVec, values: impl IntoIterator<Item = T>) -> Option<Self> {
+        let mut values = values.into_iter();
+        let mut map = Vec::with_capacity(bitmask.len());
+        for present in bitmask.iter() {
+            map.push(match present {
+                true => Some(values.next()?),
+                false => None,
+            });
+        }
+        match values.next() {
+            Some(_) => None,
+            None => Some(NodeMap(map)),
+        }
     }
 }
 
@@ -108,17 +251,42 @@ impl<T> FromIterator<T> for NodeMap<T> {
     }
 }
 
+/// Collects a value for every node index, in any order. The length of the resulting map is the
+/// number of pairs, hence this panics if an index is out of range or repeated.
+impl<T> FromIterator<(NodeIndex, T)> for NodeMap<T> {
+    fn from_iter<I: IntoIterator<Item = (NodeIndex, T)>>(ii: I) -> NodeMap<T> {
+        let pairs: Vec<(NodeIndex, T)> = ii.into_iter().collect();
+        let len = pairs.len();
+        let mut values: Vec<Option<T>> = (0..len).map(|_| None).collect();
+        for (index, value) in pairs {
+            assert!(
+                index.as_usize() < len,
+                "node index {} out of range for {} values",
+                index.0,
+                len
+            );
+            assert!(
+                values[index.as_usize()].is_none(),
+                "node index {} given more than once",
+                index.0
+            );
+            values[index.as_usize()] = Some(value);
+        }
+        NodeMap(values.into_iter().map(Option::unwrap).collect())
+    }
+}
+
 impl<T> Index<NodeIndex> for NodeMap<T> {
     type Output = T;
 
     fn index(&self, vidx: NodeIndex) -> &T {
-        &self.0[vidx.0 as usize]
+        &self.0[vidx.as_usize()]
     }
 }
 
 impl<T> IndexMut<NodeIndex> for NodeMap<T> {
     fn index_mut(&mut self, vidx: NodeIndex) -> &mut T {
-        &mut self.0[vidx.0 as usize]
+        &mut self.0[vidx.as_usize()]
     }
 }
 
@@ -133,8 +301,15 @@ impl<'a, T> IntoIterator for &'a NodeMap<T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::nodes::NodeIndex;
+    use crate::nodes::{IndexOutOfRange, NodeCount, NodeIndex, NodeInt, NodeMap};
+    use bit_vec::BitVec;
     use codec::{Decode, Encode};
+    #[test]
+    fn display_is_the_plain_number() {
+        assert_eq!(NodeIndex(3).to_string(), "3");
+        assert_eq!(NodeCount(5).to_string(), "5");
+    }
+
     #[test]
     fn decoding_works() {
         for i in 0..1000 {
@@ -144,4 +319,135 @@ mod tests {
             assert_eq!(node_index, decoded.unwrap());
         }
     }
+
+    #[test]
+    fn threshold_is_two_thirds_rounded_down() {
+        assert_eq!(NodeCount(1).threshold_two_thirds(), NodeCount(0));
+        assert_eq!(NodeCount(3).threshold_two_thirds(), NodeCount(2));
+        assert_eq!(NodeCount(4).threshold_two_thirds(), NodeCount(2));
+        assert_eq!(NodeCount(100).threshold_two_thirds(), NodeCount(66));
+        assert_eq!(
+            NodeCount(NodeInt::MAX).threshold_two_thirds(),
+            NodeCount(NodeInt::MAX / 3 * 2)
+        );
+    }
+
+    #[test]
+    fn count_arithmetic_does_not_overflow() {
+        assert_eq!(NodeCount(2).saturating_sub(NodeCount(5)), NodeCount(0));
+        assert_eq!(NodeCount(5).saturating_sub(NodeCount(2)), NodeCount(3));
+        assert_eq!(NodeCount(2).checked_add(NodeCount(5)), Some(NodeCount(7)));
+        assert_eq!(NodeCount(NodeInt::MAX).checked_add(NodeCount(1)), None);
+    }
+
+    #[test]
+    fn empty_map_has_nothing_present() {
+        let map: NodeMap<Option<u32>> = NodeMap::new_with_len(NodeCount(4));
+        assert_eq!(map.present().count(), 0);
+        assert_eq!(map.count_present(), NodeCount(0));
+    }
+
+    #[test]
+    fn full_map_has_everything_present() {
+        let map: NodeMap<Option<u32>> = (0..4).map(Some).collect();
+        let present: Vec<_> = map.present().collect();
+        assert_eq!(
+            present,
+            vec![
+                (NodeIndex(0), &0),
+                (NodeIndex(1), &1),
+                (NodeIndex(2), &2),
+                (NodeIndex(3), &3)
+            ]
+        );
+        assert_eq!(map.count_present(), NodeCount(4));
+    }
+
+    #[test]
+    fn sparse_map_yields_present_values_with_indices() {
+        let mut map: NodeMap<Option<u32>> = NodeMap::new_with_len(NodeCount(5));
+        map[NodeIndex(1)] = Some(10);
+        map[NodeIndex(4)] = Some(40);
+        let present: Vec<_> = map.present().collect();
+        assert_eq!(present, vec![(NodeIndex(1), &10), (NodeIndex(4), &40)]);
+        assert_eq!(map.count_present(), NodeCount(2));
+    }
+
+    #[test]
+    fn map_is_collected_from_pairs() {
+        let map: NodeMap<char> = vec![
+            (NodeIndex(2), 'c'),
+            (NodeIndex(0), 'a'),
+            (NodeIndex(1), 'b'),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(map, NodeMap::from(vec!['a', 'b', 'c']));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn collecting_out_of_range_index_panics() {
+        let _: NodeMap<char> = vec![(NodeIndex(0), 'a'), (NodeIndex(2), 'c')]
+            .into_iter()
+            .collect();
+    }
+
+    #[test]
+    fn map_is_built_from_parts() {
+        let parts = vec![(NodeIndex(3), Some(30)), (NodeIndex(1), Some(10))];
+        let map = NodeMap::from_parts(NodeCount(4), parts).unwrap();
+        assert_eq!(map, NodeMap::from(vec![None, Some(10), None, Some(30)]));
+    }
+
+    #[test]
+    fn building_from_out_of_range_part_fails() {
+        let parts = vec![(NodeIndex(1), Some(10)), (NodeIndex(4), Some(40))];
+        assert_eq!(
+            NodeMap::from_parts(NodeCount(4), parts),
+            Err(IndexOutOfRange {
+                index: NodeIndex(4),
+                len: NodeCount(4),
+            })
+        );
+    }
+
+    fn sparse_parents() -> NodeMap<Option<u64>> {
+        (0..10)
+            .map(|idx| if idx % 3 == 0 { None } else { Some(idx * 100) })
+            .collect()
+    }
+
+    #[test]
+    fn map_round_trips_through_bitmask() {
+        let map = sparse_parents();
+        let bitmask = map.to_bitmask();
+        assert_eq!(bitmask, BitVec::from_fn(10, |idx| idx % 3 != 0),);
+        let values: Vec<u64> = map.present().map(|(_, value)| *value).collect();
+        assert_eq!(NodeMap::from_bitmask(&bitmask, values.clone()), Some(map));
+        assert_eq!(NodeMap::from_bitmask(&bitmask, values[1..].to_vec()), None);
+        assert_eq!(NodeMap::from_bitmask(&bitmask, vec![0; 7]), None);
+    }
+
+    #[test]
+    fn bitmask_encoding_is_smaller() {
+        let map = sparse_parents();
+        let values: Vec<u64> = map.present().map(|(_, value)| *value).collect();
+        let compact = (map.to_bitmask().to_bytes(), values).encode();
+        let plain = map.encode();
+        // a length prefix, a tag byte for every slot and the present values
+        assert_eq!(plain.len(), 1 + 10 + 6 * 8);
+        // two length prefixes, two bytes of the bitmask and the present values
+        assert_eq!(compact.len(), 1 + 2 + 1 + 6 * 8);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn map_round_trips_through_serde() {
+        let map = sparse_parents();
+        let encoded = serde_json::to_string(&map).unwrap();
+        assert_eq!(encoded, "[null,100,200,null,400,500,null,700,800,null]");
+        let decoded: NodeMap<Option<u64>> = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, map);
+    }
 }
@@ -0,0 +1,270 @@
+use alloc::collections::VecDeque;
+use core::ops::{Index, IndexMut};
+
+use crate::Round;
+
+/// Values for a contiguous range of rounds, starting from the base round. The value of round r is
+/// kept at position r - base, so that moving the base forward, i.e., dropping the old rounds,
+/// costs nothing for the remaining ones. None of the operations below overflow, whatever the
+/// rounds, and rounds outside of the window are simply not there.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct RoundWindow<T> {
+    base: Round,
+    values: VecDeque<T>,
+}
+
+impl<T> RoundWindow<T> {
+    /// An empty window starting at the given round.
+    pub(crate) fn new(base: Round) -> Self {
+        RoundWindow {
+            base,
+            values: VecDeque::new(),
+        }
+    }
+
+    /// A window starting at the given round with the given values of the subsequent rounds.
+    pub(crate) fn from_values(base: Round, values: impl IntoIterator<Item = T>) -> Self {
+        let mut window = RoundWindow::new(base);
+        for value in values {
+            let round = window.end();
+            // past the last representable round there is nowhere to put the remaining values
+            if window.contains(round) || !window.insert(round, value) {
+                break;
+            }
+        }
+        window
+    }
+
+    /// The first round of the window. All the rounds before it were dropped.
+    pub(crate) fn base(&self) -> Round {
+        self.base
+    }
+
+    /// The first round after the kept ones, i.e., the next one to be inserted, or the last
+    /// representable round if that one is kept.
+    pub(crate) fn end(&self) -> Round {
+        self.base.saturating_add(self.values.len())
+    }
+
+    /// The number of kept rounds.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn position(&self, round: Round) -> Option<usize> {
+        let position = round.0.checked_sub(self.base.0)?;
+        Some(position).filter(|position| *position < self.values.len())
+    }
+
+    pub(crate) fn contains(&self, round: Round) -> bool {
+        self.position(round).is_some()
+    }
+
+    pub(crate) fn get(&self, round: Round) -> Option<&T> {
+        let position = self.position(round)?;
+        self.values.get(position)
+    }
+
+    pub(crate) fn get_mut(&mut self, round: Round) -> Option<&mut T> {
+        let position = self.position(round)?;
+        self.values.get_mut(position)
+    }
+
+    /// Keeps the value for the given round, replacing the previous one. Rounds can only be added
+    /// right after the kept ones, so that there are no gaps. Returns false, dropping the value, for
+    /// rounds before the base or after the end of the window.
+    pub(crate) fn insert(&mut self, round: Round, value: T) -> bool {
+        if let Some(kept) = self.get_mut(round) {
+            *kept = value;
+            return true;
+        }
+        if round != self.end() {
+            return false;
+        }
+        self.values.push_back(value);
+        true
+    }
+
+    /// Drops the values of the rounds before the given one, which becomes the base, unless it is
+    /// before the current base. Moving past the end drops everything.
+    pub(crate) fn advance_base(&mut self, round: Round) {
+        if round <= self.base {
+            return;
+        }
+        let dropped = round.0 - self.base.0;
+        if dropped >= self.values.len() {
+            self.values.clear();
+        } else {
+            self.values.drain(..dropped);
+        }
+        self.base = round;
+    }
+
+    /// Drops all the values and starts anew at the given round, which may be before the current
+    /// base.
+    pub(crate) fn reset(&mut self, base: Round) {
+        self.values.clear();
+        self.base = base;
+    }
+
+    /// The kept values with their rounds, starting from the base.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Round, &T)> {
+        let base = self.base;
+        self.values
+            .iter()
+            .enumerate()
+            .map(move |(position, value)| (Round(base.0 + position), value))
+    }
+
+    /// The kept values, starting from the base.
+    #[cfg(test)]
+    pub(crate) fn values(&self) -> impl Iterator<Item = &T> {
+        self.values.iter()
+    }
+}
+
+impl<T> Index<Round> for RoundWindow<T> {
+    type Output = T;
+
+    fn index(&self, round: Round) -> &T {
+        match self.get(round) {
+            Some(value) => value,
+            None => panic!(
+                "round {} outside of the window of rounds {}..{}",
+                round,
+                self.base,
+                self.end()
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<Round> for RoundWindow<T> {
+    fn index_mut(&mut self, round: Round) -> &mut T {
+        let (base, end) = (self.base, self.end());
+        match self.get_mut(round) {
+            Some(value) => value,
+            None => panic!(
+                "round {} outside of the window of rounds {}..{}",
+                round, base, end
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoundWindow;
+    use crate::Round;
+
+    fn window(base: usize, values: &[u32]) -> RoundWindow<u32> {
+        RoundWindow::from_values(Round(base), values.iter().copied())
+    }
+
+    #[test]
+    fn empty_window_has_no_rounds() {
+        let window = RoundWindow::<u32>::new(Round(3));
+        assert_eq!(window.base(), Round(3));
+        assert_eq!(window.end(), Round(3));
+        assert_eq!(window.len(), 0);
+        for round in 0..6 {
+            assert_eq!(window.get(Round(round)), None);
+        }
+    }
+
+    #[test]
+    fn rounds_map_to_values() {
+        let mut window = window(5, &[50, 60, 70]);
+        assert_eq!(window.end(), Round(8));
+        assert_eq!(window.get(Round(4)), None);
+        assert_eq!(window.get(Round(5)), Some(&50));
+        assert_eq!(window[Round(7)], 70);
+        assert_eq!(window.get(Round(8)), None);
+        *window.get_mut(Round(6)).unwrap() += 1;
+        window[Round(7)] += 2;
+        assert_eq!(
+            window.values().copied().collect::<Vec<_>>(),
+            vec![50, 61, 72]
+        );
+        assert_eq!(
+            window
+                .iter()
+                .map(|(round, value)| (round, *value))
+                .collect::<Vec<_>>(),
+            vec![(Round(5), 50), (Round(6), 61), (Round(7), 72)]
+        );
+        assert!(window.get_mut(Round(4)).is_none());
+        assert!(window.get_mut(Round(8)).is_none());
+    }
+
+    #[test]
+    fn insert_replaces_or_appends_without_gaps() {
+        let mut window = window(2, &[20]);
+        assert!(window.insert(Round(2), 21));
+        assert!(window.insert(Round(3), 30));
+        assert!(!window.insert(Round(5), 50));
+        assert!(!window.insert(Round(1), 10));
+        assert!(!window.insert(Round(0), 0));
+        assert_eq!(window.base(), Round(2));
+        assert_eq!(window.values().copied().collect::<Vec<_>>(), vec![21, 30]);
+    }
+
+    #[test]
+    fn advancing_the_base_drops_old_rounds() {
+        let mut window = window(0, &[0, 10, 20, 30]);
+        window.advance_base(Round(2));
+        assert_eq!(window.base(), Round(2));
+        assert_eq!(window.get(Round(1)), None);
+        assert_eq!(window[Round(2)], 20);
+        assert_eq!(window.len(), 2);
+        // the base never moves back
+        window.advance_base(Round(1));
+        assert_eq!(window.base(), Round(2));
+        assert_eq!(window.len(), 2);
+        // moving past the end leaves an empty window at the new base
+        window.advance_base(Round(10));
+        assert_eq!(window.base(), Round(10));
+        assert_eq!(window.len(), 0);
+        assert!(window.insert(Round(10), 100));
+        assert_eq!(window[Round(10)], 100);
+    }
+
+    #[test]
+    fn reset_may_move_the_base_back() {
+        let mut window = window(7, &[70, 80]);
+        window.reset(Round(0));
+        assert_eq!(window.base(), Round(0));
+        assert_eq!(window.len(), 0);
+        assert_eq!(window.get(Round(7)), None);
+        assert!(window.insert(Round(0), 0));
+    }
+
+    #[test]
+    fn extreme_rounds_do_not_overflow() {
+        let last = Round(usize::MAX);
+        let mut window = window(usize::MAX - 1, &[1, 2, 3]);
+        // the third value has no round to go to
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.end(), last);
+        assert_eq!(window[last], 2);
+        assert!(window.insert(last, 4));
+        assert_eq!(window[last], 4);
+        assert_eq!(window.get(Round(0)), None);
+        window.advance_base(last);
+        assert_eq!(window.values().copied().collect::<Vec<_>>(), vec![4]);
+        let mut window = RoundWindow::new(Round(0));
+        assert!(!window.insert(last, 0));
+        window.advance_base(last);
+        assert_eq!(window.base(), last);
+        assert!(window.insert(last, 0));
+        assert_eq!(window.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "round 1 outside of the window of rounds 2..4")]
+    fn indexing_outside_of_the_window_panics() {
+        let window = window(2, &[20, 30]);
+        let _ = window[Round(1)];
+    }
+}
@@ -0,0 +1,497 @@
+//! A deterministic simulation of a committee of Creators, for testing how the rounds of the Dag
+//! emerge from their interplay, e.g., whether the committee stays live while some nodes are
+//! partitioned. All the Creators run on the calling thread, without any runtime, on a shared
+//! simulated clock, and their units travel through a [Network] that delays, reorders or drops
+//! them as told, so that every run of a scenario is the same. Enabled with the `sim` feature.
+//!
+//! The simulation polls the Creators itself, so it must not be run from within a tokio runtime,
+//! i.e., it is meant for plain `#[test]`s.
+use crate::{
+    nodes::{NodeCount, NodeIndex},
+    Clock, Config, ConfigBuilder, ConfigError, Creator, MyIndex, NotificationOut, Receiver, Round,
+    RoundWatch, Sender, Unit,
+};
+use codec::{Decode, Encode};
+use derive_more::Display;
+use futures::{future::FutureExt, task::noop_waker_ref};
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{Duration, Instant},
+};
+
+/// The identifier of a simulated node, the one of the given index.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash, Encode, Decode)]
+#[display(fmt = "Node-{}", _0)]
+pub struct NodeId(pub u64);
+
+impl MyIndex for NodeId {
+    fn my_index(&self) -> Option<NodeIndex> {
+        Some(NodeIndex::from_usize(self.0 as usize))
+    }
+}
+
+/// The hash of a simulated unit, see [hashing].
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+pub struct Hash(pub u64);
+
+/// The hashing of the simulated Creators, FNV-1a.
+pub fn hashing(data: &[u8]) -> Hash {
+    Hash(data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100_0000_01b3)
+    }))
+}
+
+type Hashing = fn(&[u8]) -> Hash;
+
+// A clock whose time moves only when the simulation advances it.
+#[derive(Clone)]
+struct SimClock(Arc<Mutex<SimClockState>>);
+
+struct SimClockState {
+    now: Instant,
+    timers: Vec<(Instant, oneshot::Sender<()>)>,
+}
+
+impl SimClock {
+    fn new() -> Self {
+        SimClock(Arc::new(Mutex::new(SimClockState {
+            now: Instant::now(),
+            timers: Vec::new(),
+        })))
+    }
+
+    fn advance(&self, duration: Duration) {
+        let mut state = self.0.lock();
+        state.now += duration;
+        let now = state.now;
+        let (due, pending) = state.timers.drain(..).partition(|(when, _)| *when <= now);
+        state.timers = pending;
+        for (_, tx) in due {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> Instant {
+        self.0.lock().now
+    }
+
+    fn delay(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let mut state = self.0.lock();
+        let (tx, rx) = oneshot::channel();
+        if duration == Duration::default() {
+            let _ = tx.send(());
+        } else {
+            let when = state.now + duration;
+            state.timers.push((when, tx));
+        }
+        Box::pin(rx.map(|_| ()))
+    }
+}
+
+/// How the units sent from one node to another travel. By default they arrive in the next step,
+/// in the order in which they were sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Link {
+    /// The number of additional steps a unit takes to arrive.
+    pub delay: u64,
+    /// Whether the units are lost on the way.
+    pub dropped: bool,
+    /// Whether the units arriving in the same step arrive in a random order.
+    pub reordered: bool,
+}
+
+struct InFlight {
+    to: NodeIndex,
+    // the step in which the unit arrives
+    due: u64,
+    reordered: bool,
+    unit: Unit<Hash>,
+}
+
+/// The links between the simulated nodes, see [Link]. The units a node sends to itself never go
+/// through the network.
+pub struct Network {
+    n_members: NodeCount,
+    links: BTreeMap<(NodeIndex, NodeIndex), Link>,
+    in_flight: Vec<InFlight>,
+    step: u64,
+    // the source of the reorderings, so that these are the same in every run
+    rng: StdRng,
+}
+
+impl Network {
+    fn new(n_members: NodeCount, seed: u64) -> Self {
+        Network {
+            n_members,
+            links: BTreeMap::new(),
+            in_flight: Vec::new(),
+            step: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The link from `from` to `to`.
+    pub fn link(&self, from: NodeIndex, to: NodeIndex) -> Link {
+        self.links.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    /// Sets the link from `from` to `to`, for the units sent from now on.
+    pub fn set_link(&mut self, from: NodeIndex, to: NodeIndex, link: Link) {
+        self.links.insert((from, to), link);
+    }
+
+    /// Drops all the units sent to and by the given node, until it is reconnected.
+    pub fn partition(&mut self, node: NodeIndex) {
+        for other in (0..self.n_members.as_usize()).map(NodeIndex::from_usize) {
+            if other != node {
+                self.set_link(
+                    node,
+                    other,
+                    Link {
+                        dropped: true,
+                        ..Link::default()
+                    },
+                );
+                self.set_link(
+                    other,
+                    node,
+                    Link {
+                        dropped: true,
+                        ..Link::default()
+                    },
+                );
+            }
+        }
+    }
+
+    /// Restores the default links to and from the given node.
+    pub fn reconnect(&mut self, node: NodeIndex) {
+        self.links
+            .retain(|(from, to), _| *from != node && *to != node);
+    }
+
+    fn send(&mut self, from: NodeIndex, to: NodeIndex, unit: Unit<Hash>) {
+        let link = self.link(from, to);
+        if link.dropped {
+            return;
+        }
+        self.in_flight.push(InFlight {
+            to,
+            due: self.step + 1 + link.delay,
+            reordered: link.reordered,
+            unit,
+        });
+    }
+
+    // Moves on to the next step and takes the units arriving in it, in the order in which they
+    // were sent, apart from the ones of reordered links, which are shuffled among themselves.
+    fn next_step(&mut self) -> Vec<(NodeIndex, Unit<Hash>)> {
+        self.step += 1;
+        let step = self.step;
+        let (mut due, pending): (Vec<_>, Vec<_>) =
+            self.in_flight.drain(..).partition(|unit| unit.due <= step);
+        self.in_flight = pending;
+        let positions: Vec<_> = (0..due.len()).filter(|i| due[*i].reordered).collect();
+        let mut shuffled = positions.clone();
+        shuffled.shuffle(&mut self.rng);
+        let mut order: Vec<_> = (0..due.len()).collect();
+        for (position, from) in positions.into_iter().zip(shuffled) {
+            order[position] = from;
+        }
+        let mut due: Vec<_> = due.drain(..).map(Some).collect();
+        order
+            .into_iter()
+            .filter_map(|i| due[i].take())
+            .map(|unit| (unit.to, unit.unit))
+            .collect()
+    }
+}
+
+struct SimNode {
+    parents_tx: Sender<Unit<Hash>>,
+    new_units_rx: Receiver<NotificationOut<Hash>>,
+    creation: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    // kept only for the exit signal not to fire when dropped
+    _exit_tx: oneshot::Sender<()>,
+    round_watch: RoundWatch,
+    // the units this node knows of, which it sends to the nodes asking for them
+    units: BTreeMap<(Round, NodeIndex), Unit<Hash>>,
+    notifications: Vec<NotificationOut<Hash>>,
+}
+
+/// A committee of Creators connected by a [Network]. Every step moves the simulated clock
+/// forward by a tick, delivers the units arriving in it and lets all the Creators process
+/// everything they can. The created units are sent to all the other nodes, and to the node
+/// itself right away. The units asked for with [NotificationOut::CatchupRequest] are sent by all
+/// the others that know them, and so are the ones of the rounds a node missed once it receives a
+/// unit of a later round, as the Dag would ask for the missing parents of that unit.
+pub struct Simulation {
+    nodes: Vec<SimNode>,
+    network: Network,
+    clock: SimClock,
+    tick: Duration,
+}
+
+impl Simulation {
+    /// A committee of `n_members` nodes configured by `configure`, which is given a builder with
+    /// the node id, the committee size, the simulated clock and a seed for the randomness of the
+    /// Creator already set. All of these seeds and the reorderings are determined by `seed`.
+    pub fn new(
+        n_members: NodeCount,
+        tick: Duration,
+        seed: u64,
+        configure: impl Fn(ConfigBuilder<Hash, NodeId>) -> Result<Config<Hash, NodeId>, ConfigError>,
+    ) -> Result<Self, ConfigError> {
+        let clock = SimClock::new();
+        let mut nodes = Vec::new();
+        for index in 0..n_members.as_usize() {
+            let builder = Config::builder()
+                .node_id(NodeId(index as u64))
+                .n_members(n_members)
+                .clock(clock.clone())
+                .rng_seed(seed ^ index as u64);
+            let conf = configure(builder)?;
+            let (parents_tx, parents_rx) = mpsc::unbounded_channel();
+            let (new_units_tx, new_units_rx) = mpsc::unbounded_channel();
+            let (exit_tx, exit_rx) = oneshot::channel();
+            let mut creator = Creator::new(conf, parents_rx, new_units_tx, hashing as Hashing)
+                .expect("every simulated node has an index");
+            let round_watch = creator.round_watch();
+            nodes.push(SimNode {
+                parents_tx,
+                new_units_rx,
+                creation: Some(Box::pin(async move {
+                    let _ = creator.create(exit_rx).await;
+                })),
+                _exit_tx: exit_tx,
+                round_watch,
+                units: BTreeMap::new(),
+                notifications: Vec::new(),
+            });
+        }
+        Ok(Simulation {
+            nodes,
+            network: Network::new(n_members, seed),
+            clock,
+            tick,
+        })
+    }
+
+    /// The network connecting the nodes, e.g., to set the links between them.
+    pub fn network(&mut self) -> &mut Network {
+        &mut self.network
+    }
+
+    /// Drops all the units sent to and by the given node, until it is reconnected.
+    pub fn partition(&mut self, node: NodeIndex) {
+        self.network.partition(node);
+    }
+
+    /// Restores the default links to and from the given node.
+    pub fn reconnect(&mut self, node: NodeIndex) {
+        self.network.reconnect(node);
+    }
+
+    /// The round of the next unit of the given node.
+    pub fn round(&self, node: NodeIndex) -> Round {
+        self.nodes[node.as_usize()].round_watch.current_round()
+    }
+
+    /// All the notifications sent by the Creator of the given node so far.
+    pub fn notifications(&self, node: NodeIndex) -> &[NotificationOut<Hash>] {
+        &self.nodes[node.as_usize()].notifications
+    }
+
+    /// Whether the Creator of the given node is still running.
+    pub fn is_running(&self, node: NodeIndex) -> bool {
+        self.nodes[node.as_usize()].creation.is_some()
+    }
+
+    /// Runs the given number of steps.
+    pub fn run(&mut self, n_steps: usize) {
+        for _ in 0..n_steps {
+            self.step();
+        }
+    }
+
+    /// Moves the clock forward by a tick, delivers the units arriving in this step and polls the
+    /// Creators until none of them has anything more to send.
+    pub fn step(&mut self) {
+        let mut behind = vec![false; self.nodes.len()];
+        for (to, unit) in self.network.next_step() {
+            behind[to.as_usize()] |= unit.round() > self.round(to).next();
+            self.deliver(to, unit);
+        }
+        for (node, behind) in behind.into_iter().enumerate() {
+            let node = NodeIndex::from_usize(node);
+            if behind {
+                let round = self.round(node).prev().unwrap_or(Round(0));
+                self.request_units(node, round);
+            }
+        }
+        self.clock.advance(self.tick);
+        loop {
+            self.poll();
+            let mut any = false;
+            for index in (0..self.nodes.len()).map(NodeIndex::from_usize) {
+                while let Ok(notification) = self.nodes[index.as_usize()].new_units_rx.try_recv() {
+                    self.handle(index, notification);
+                    any = true;
+                }
+            }
+            if !any {
+                break;
+            }
+        }
+    }
+
+    fn poll(&mut self) {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        for node in &mut self.nodes {
+            if let Some(creation) = &mut node.creation {
+                if let Poll::Ready(()) = creation.as_mut().poll(&mut cx) {
+                    node.creation = None;
+                }
+            }
+        }
+    }
+
+    fn deliver(&mut self, to: NodeIndex, unit: Unit<Hash>) {
+        let node = &mut self.nodes[to.as_usize()];
+        node.units
+            .entry((unit.round(), unit.creator()))
+            .or_insert_with(|| unit.clone());
+        let _ = node.parents_tx.send(unit);
+    }
+
+    fn handle(&mut self, from: NodeIndex, notification: NotificationOut<Hash>) {
+        match &notification {
+            NotificationOut::CreatedPreUnit(preunit)
+            | NotificationOut::SignedUnit { preunit, .. } => {
+                let hash = hashing(&preunit.encode());
+                let unit = Unit::new_from_preunit(preunit.clone(), hash);
+                for to in self.others(from) {
+                    self.network.send(from, to, unit.clone());
+                }
+                self.deliver(from, unit);
+            }
+            NotificationOut::CatchupRequest { round, .. } => self.request_units(from, *round),
+            _ => {}
+        }
+        self.nodes[from.as_usize()].notifications.push(notification);
+    }
+
+    // Makes the others send the units they know of the given round and the later ones.
+    fn request_units(&mut self, node: NodeIndex, round: Round) {
+        for peer in self.others(node) {
+            let units: Vec<_> = self.nodes[peer.as_usize()]
+                .units
+                .range((round, NodeIndex::from_usize(0))..)
+                .map(|(_, unit)| unit.clone())
+                .collect();
+            for unit in units {
+                self.network.send(peer, node, unit);
+            }
+        }
+    }
+
+    fn others(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        (0..self.nodes.len())
+            .map(NodeIndex::from_usize)
+            .filter(|other| *other != node)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulation(seed: u64) -> Simulation {
+        Simulation::new(NodeCount(4), Duration::from_millis(10), seed, |builder| {
+            builder
+                .create_lag(Duration::from_millis(50))
+                .jitter(Duration::from_millis(20))
+                .stall_timeout(Duration::from_millis(200))
+                .build()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn partitioned_node_catches_up_after_reconnection() {
+        let mut sim = simulation(0);
+        sim.run(50);
+        let partitioned = NodeIndex::from_usize(3);
+        let started_at: Vec<_> = (0..4)
+            .map(|i| sim.round(NodeIndex::from_usize(i)))
+            .collect();
+        assert!(started_at.iter().all(|round| *round >= Round(5)));
+
+        sim.partition(partitioned);
+        sim.run(100);
+        let stuck_at = sim.round(partitioned);
+        assert!(stuck_at <= started_at[3].next());
+        let others_at: Vec<_> = (0..3)
+            .map(|i| sim.round(NodeIndex::from_usize(i)))
+            .collect();
+        for (at, started) in others_at.iter().zip(&started_at) {
+            assert!(
+                at.0 >= started.0 + 5,
+                "{} did not advance from {}",
+                at,
+                started
+            );
+        }
+        assert!(sim
+            .notifications(partitioned)
+            .iter()
+            .any(|notification| matches!(notification, NotificationOut::CatchupRequest { .. })));
+
+        sim.reconnect(partitioned);
+        sim.run(100);
+        // the missed rounds are caught up with, from then on the node keeps the pace of the others
+        // as it waits for the create lag after each unit
+        assert!(sim.round(partitioned) >= *others_at.iter().max().unwrap());
+        let lowest = (0..3)
+            .map(|i| sim.round(NodeIndex::from_usize(i)))
+            .min()
+            .unwrap();
+        assert!(lowest.0 >= others_at.iter().max().unwrap().0 + 5);
+        assert!((0..4).all(|i| sim.is_running(NodeIndex::from_usize(i))));
+    }
+
+    #[test]
+    fn runs_are_reproducible() {
+        let run = |seed| {
+            let mut sim = simulation(seed);
+            for from in 0..4 {
+                for to in 0..4 {
+                    let link = Link {
+                        delay: ((from + to) % 3) as u64,
+                        reordered: true,
+                        ..Link::default()
+                    };
+                    let (from, to) = (NodeIndex::from_usize(from), NodeIndex::from_usize(to));
+                    sim.network().set_link(from, to, link);
+                }
+            }
+            sim.run(60);
+            (0..4)
+                .map(|i| sim.notifications(NodeIndex::from_usize(i)).to_vec())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(7), run(7));
+    }
+}
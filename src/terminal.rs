@@ -5,25 +5,21 @@ use tokio::sync::oneshot;
 use crate::{
     extender::ExtenderUnit,
     nodes::{NodeCount, NodeIndex, NodeMap},
-    ControlHash, HashT, NodeIdT, NotificationOut, Receiver, RequestAuxData, Round, Sender, Unit,
+    ControlHash, HashT, NodeIdT, NotificationOut, ParentOrdering, Receiver, RequestAuxData, Round,
+    Sender, Unit,
 };
 use log::{debug, error};
 
 /// An enum describing the status of a Unit in the Terminal pipeline.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum UnitStatus {
+    #[default]
     ReconstructingParents,
     WrongControlHash,
     WaitingParentsInDag,
     InDag,
 }
 
-impl Default for UnitStatus {
-    fn default() -> UnitStatus {
-        UnitStatus::ReconstructingParents
-    }
-}
-
 /// A Unit struct used in the Terminal. It stores a copy of a unit and apart from that some
 /// information on its status, i.e., already reconstructed parents etc.
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -69,10 +65,18 @@ impl<H: HashT> TerminalUnit<H> {
         self.unit.hash
     }
 
-    pub(crate) fn verify_control_hash<Hashing: Fn(&[u8]) -> H>(&self, hashing: &Hashing) -> bool {
+    pub(crate) fn verify_control_hash<Hashing: Fn(&[u8]) -> H>(
+        &self,
+        hashing: &Hashing,
+        session_id: Option<u64>,
+        ordering: ParentOrdering,
+    ) -> bool {
         // this will be called only after all parents have been reconstructed
 
-        self.unit.control_hash.hash == ControlHash::combine_hashes(&self.parents, hashing)
+        let domain =
+            session_id.map(|session_id| (session_id, self.unit.round(), self.unit.creator));
+        self.unit.control_hash.hash
+            == ControlHash::combine_hashes_in(&self.parents, domain, ordering, hashing)
     }
 }
 
@@ -95,6 +99,10 @@ type SyncClosure<X, Y> = Box<dyn Fn(X) -> Y + Sync + Send + 'static>;
 pub(crate) struct Terminal<H: HashT, NI: NodeIdT> {
     node_id: NI,
     hashing: Box<dyn Fn(&[u8]) -> H + Send>,
+    // hashed into the control hashes of units, if any, see ConfigBuilder::session_id
+    session_id: Option<u64>,
+    // the order of the parents in the control hashes of units, see ConfigBuilder::parent_ordering
+    parent_ordering: ParentOrdering,
     // A channel for receiving new units (they might also come from the local node).
     new_units_rx: Receiver<Unit<H>>,
     // A channel to push unit requests.
@@ -122,12 +130,16 @@ impl<H: HashT, NI: NodeIdT> Terminal<H, NI> {
     pub(crate) fn new(
         node_id: NI,
         hashing: impl Fn(&[u8]) -> H + Send + 'static,
+        session_id: Option<u64>,
+        parent_ordering: ParentOrdering,
         new_units_rx: Receiver<Unit<H>>,
         requests_tx: Sender<NotificationOut<H>>,
     ) -> Self {
         Terminal {
             node_id,
             hashing: Box::new(hashing),
+            session_id,
+            parent_ordering,
             new_units_rx,
             requests_tx,
             event_queue: VecDeque::new(),
@@ -190,20 +202,17 @@ impl<H: HashT, NI: NodeIdT> Terminal<H, NI> {
                 self.reconstruct_parent(&v_hash, pid, &u_hash);
             }
         }
-        if u_round == 0 {
-            self.event_queue
-                .push_back(TerminalEvent::ParentsReconstructed(u_hash));
-        } else {
+        if let Some(prev_round) = u_round.prev() {
             let mut coords_to_request = Vec::new();
             for (i, b) in u.control_hash.parents.enumerate() {
                 if *b {
-                    let coord = (u_round - 1, i);
+                    let coord = (prev_round, i);
                     let maybe_hash = self.unit_by_coord.get(&coord).cloned();
                     match maybe_hash {
                         Some(v_hash) => self.reconstruct_parent(&u_hash, i, &v_hash),
                         None => {
-                            self.add_coord_trigger(u_round - 1, i, u_hash);
-                            coords_to_request.push((u.round() - 1, i).into());
+                            self.add_coord_trigger(prev_round, i, u_hash);
+                            coords_to_request.push((prev_round, i).into());
                         }
                     }
                 }
@@ -218,6 +227,9 @@ impl<H: HashT, NI: NodeIdT> Terminal<H, NI> {
                     error!(target: "rush-terminal", "{:?} Unable to place a Fetch request: {:?}.", self.node_id, e);
                 }
             }
+        } else {
+            self.event_queue
+                .push_back(TerminalEvent::ParentsReconstructed(u_hash));
         }
     }
 
@@ -240,7 +252,7 @@ impl<H: HashT, NI: NodeIdT> Terminal<H, NI> {
     }
 
     fn inspect_parents_in_dag(&mut self, u_hash: &H) {
-        let u_parents = self.unit_store.get(&u_hash).unwrap().parents.clone();
+        let u_parents = self.unit_store.get(u_hash).unwrap().parents.clone();
         let mut n_parents_in_dag = NodeCount(0);
         for p_hash in u_parents.into_iter().flatten() {
             let p = self.unit_store.get(&p_hash).unwrap();
@@ -250,7 +262,7 @@ impl<H: HashT, NI: NodeIdT> Terminal<H, NI> {
                 self.add_hash_trigger(&p_hash, u_hash);
             }
         }
-        let u = self.unit_store.get_mut(&u_hash).unwrap();
+        let u = self.unit_store.get_mut(u_hash).unwrap();
         u.n_miss_par_dag -= n_parents_in_dag;
         if u.n_miss_par_dag == NodeCount(0) {
             self.event_queue
@@ -270,7 +282,7 @@ impl<H: HashT, NI: NodeIdT> Terminal<H, NI> {
             match event {
                 TerminalEvent::ParentsReconstructed(u_hash) => {
                     let u = self.unit_store.get_mut(&u_hash).unwrap();
-                    if u.verify_control_hash(&self.hashing) {
+                    if u.verify_control_hash(&self.hashing, self.session_id, self.parent_ordering) {
                         self.inspect_parents_in_dag(&u_hash);
                     } else {
                         u.status = UnitStatus::WrongControlHash;
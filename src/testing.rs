@@ -1,10 +1,12 @@
 #[cfg(test)]
 pub mod mock {
-    use crate::{MyIndex, NodeIndex, NotificationIn, NotificationOut, Unit, UnitCoord};
+    use crate::{MyIndex, NodeIndex, NotificationIn, NotificationOut, PreUnit, Unit, UnitCoord};
     use codec::{Decode, Encode, Error as CodecError, Input, Output};
     use derive_more::{Display, From, Into};
     use futures::{Sink, Stream};
     use parking_lot::Mutex;
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
 
     use std::{
         collections::{hash_map::DefaultHasher, HashMap},
@@ -17,6 +19,7 @@ pub mod mock {
     use tokio::sync::mpsc::*;
 
     #[derive(Hash, From, Into, Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct NodeId(pub usize);
 
     impl Encode for NodeId {
@@ -44,13 +47,14 @@ pub mod mock {
 
     impl MyIndex for NodeId {
         fn my_index(&self) -> Option<NodeIndex> {
-            Some(NodeIndex(self.0))
+            Some(NodeIndex::from_usize(self.0))
         }
     }
 
     #[derive(
         Hash, Debug, Default, Display, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Encode, Decode,
     )]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Hash(pub u32);
 
     impl From<u32> for Hash {
@@ -119,7 +123,7 @@ pub mod mock {
                 .for_each(|r| self.do_send(msg.clone(), r));
         }
         fn send_to_peer(&self, msg: NotificationIn<Hash>, peer: NodeId) {
-            let _ = self.senders.lock().iter().for_each(|r| {
+            self.senders.lock().iter().for_each(|r| {
                 if r.0 == peer {
                     self.do_send(msg.clone(), r);
                 }
@@ -143,9 +147,10 @@ pub mod mock {
 
         fn start_send(self: Pin<&mut Self>, m: NotificationOut<Hash>) -> Result<(), Self::Error> {
             match m {
-                NotificationOut::CreatedPreUnit(pu) => {
+                NotificationOut::CreatedPreUnit(pu)
+                | NotificationOut::SignedUnit { preunit: pu, .. } => {
                     let hash = pu.using_encoded(hashing);
-                    let u = Unit::new(pu.creator, pu.round(), hash, pu.control_hash);
+                    let u = Unit::new_from_preunit(pu, hash);
                     self.units.lock().insert(u.clone().into(), u.clone());
                     self.send_to_all(NotificationIn::NewUnits(vec![u]));
                 }
@@ -159,6 +164,16 @@ pub mod mock {
                         self.send_to_peer(response, self.node_id);
                     }
                 }
+                NotificationOut::CreatedUnitParents { .. } => {}
+                NotificationOut::CreatorStalled { .. } => {}
+                NotificationOut::ForkDetected { .. } => {}
+                NotificationOut::ImpostorUnit { .. } => {}
+                NotificationOut::SessionComplete { .. } => {}
+                NotificationOut::OwnUnitMissing { .. } => {}
+                NotificationOut::Terminated => {}
+                NotificationOut::BannedParentsNeeded { .. } => {}
+                NotificationOut::ForcedUnit { .. } => {}
+                NotificationOut::CatchupRequest { .. } => {}
             }
             Ok(())
         }
@@ -179,6 +194,173 @@ pub mod mock {
         hasher.write(x);
         Hash(hasher.finish() as u32)
     }
+
+    /// Hashes an encoded preunit to `Hash(1000 * round + creator)`, the hash the tests give to
+    /// the unit of these coordinates, and anything else as [hashing] does. A Creator using it
+    /// thus knows its own units by the hashes with which the tests feed them back.
+    pub(crate) fn coord_hashing(x: &[u8]) -> Hash {
+        let mut input = x;
+        match PreUnit::<Hash>::decode(&mut input) {
+            Ok(pu) if input.is_empty() => {
+                Hash((1000 * pu.round().0 + pu.creator().as_usize()) as u32)
+            }
+            _ => hashing(x),
+        }
+    }
+}
+
+/// Runs a Creator for scenario tests. The tokio clock is paused, so timers only fire once the
+/// harness advances the time, and the creation loop is polled by the harness directly, so that it
+/// makes progress only in [CreatorHarness::step].
+///
+/// A paused clock jumps to the next timer whenever the runtime has nothing to do, so the harness
+/// keeps a task spinning in the background for as long as it exists. Timers are rounded up to
+/// whole milliseconds, so the clock is aligned to a millisecond first, making a timer of a
+/// whole number of milliseconds fire exactly after that much time.
+#[cfg(test)]
+pub(crate) mod harness {
+    use super::mock::{coord_hashing, hashing, Hash, NodeId};
+    use crate::{
+        nodes::{NodeIndex, NodeMap},
+        Config, Creator, NotificationOut, PreUnit, Receiver, Round, Sender, Unit,
+    };
+    use futures::task::noop_waker_ref;
+    use std::{
+        future::Future,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+    use tokio::{sync::oneshot, time::Duration};
+
+    pub(crate) struct CreatorHarness {
+        conf: Config<Hash, NodeId>,
+        parents_tx: Sender<Unit<Hash>>,
+        new_units_rx: Receiver<NotificationOut<Hash>>,
+        exit_tx: Option<oneshot::Sender<()>>,
+        creation: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+        notifications: Vec<NotificationOut<Hash>>,
+        spinning: Arc<AtomicBool>,
+    }
+
+    impl CreatorHarness {
+        /// Sets up a Creator with the given config. Has to be called within a runtime with the
+        /// basic scheduler, i.e., from `#[tokio::test]`.
+        pub(crate) async fn new(conf: Config<Hash, NodeId>) -> Self {
+            tokio::time::pause();
+            // nothing else is running yet, so the clock jumps to the next millisecond
+            tokio::time::delay_for(Duration::from_nanos(1)).await;
+            let spinning = Arc::new(AtomicBool::new(true));
+            let spinner = spinning.clone();
+            tokio::spawn(async move {
+                while spinner.load(Ordering::Relaxed) {
+                    let _ = tokio::task::yield_now().await;
+                }
+            });
+            let (parents_tx, parents_rx) = tokio::sync::mpsc::unbounded_channel();
+            let (new_units_tx, new_units_rx) = tokio::sync::mpsc::unbounded_channel();
+            let (exit_tx, exit_rx) = oneshot::channel();
+            let mut creator = Creator::new(
+                conf.clone(),
+                parents_rx,
+                new_units_tx,
+                coord_hashing as fn(&[u8]) -> Hash,
+            )
+            .expect("the node has an index");
+            CreatorHarness {
+                conf,
+                parents_tx,
+                new_units_rx,
+                exit_tx: Some(exit_tx),
+                creation: Some(Box::pin(async move {
+                    let _ = creator.create(exit_rx).await;
+                })),
+                notifications: Vec::new(),
+                spinning,
+            }
+        }
+
+        /// Sends a parentless unit of the given coordinates to the Creator. Our own units are known
+        /// by the hash `Hash(1000 * round + creator)`.
+        pub(crate) fn feed_unit(&self, round: Round, pid: NodeIndex, hash: Hash) {
+            let pu = PreUnit::new_from_parents(
+                pid,
+                round,
+                &NodeMap::new_with_len(self.conf.n_members),
+                &hashing,
+            );
+            let _ = self.parents_tx.send(Unit::new_from_preunit(pu, hash));
+        }
+
+        /// Lets the Creator process everything it can at the current time. Returns whether the
+        /// creation loop is still running.
+        pub(crate) async fn step(&mut self) -> bool {
+            loop {
+                self.poll();
+                // turns the timer driver, so that the timers due by now fire
+                let _ = tokio::task::yield_now().await;
+                self.poll();
+                if !self.collect() {
+                    return self.creation.is_some();
+                }
+            }
+        }
+
+        /// Moves the paused clock forward and steps the Creator.
+        pub(crate) async fn advance(&mut self, duration: Duration) -> bool {
+            tokio::time::advance(duration).await;
+            self.step().await
+        }
+
+        /// Sends the exit signal and steps the Creator.
+        pub(crate) async fn exit(&mut self) -> bool {
+            if let Some(exit_tx) = self.exit_tx.take() {
+                let _ = exit_tx.send(());
+            }
+            self.step().await
+        }
+
+        /// Returns the units created since the last call, skipping any other notifications.
+        pub(crate) fn drain_created(&mut self) -> Vec<PreUnit<Hash>> {
+            self.collect();
+            self.notifications
+                .drain(..)
+                .filter_map(|notification| match notification {
+                    NotificationOut::CreatedPreUnit(pu) => Some(pu),
+                    NotificationOut::SignedUnit { preunit, .. } => Some(preunit),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn poll(&mut self) {
+            let mut cx = Context::from_waker(noop_waker_ref());
+            if let Some(creation) = &mut self.creation {
+                if let Poll::Ready(()) = creation.as_mut().poll(&mut cx) {
+                    self.creation = None;
+                }
+            }
+        }
+
+        // Moves the notifications sent so far to the buffer, returns whether there were any.
+        fn collect(&mut self) -> bool {
+            let mut any = false;
+            while let Ok(notification) = self.new_units_rx.try_recv() {
+                self.notifications.push(notification);
+                any = true;
+            }
+            any
+        }
+    }
+
+    impl Drop for CreatorHarness {
+        fn drop(&mut self) {
+            self.spinning.store(false, Ordering::Relaxed);
+        }
+    }
 }
 
 #[cfg(test)]